@@ -0,0 +1,93 @@
+use std::{env, path::Path};
+
+use drawsvg::{
+    objects::{svg, ObjectMgr},
+    render::{
+        gl::{GLSceneRenderer, GLViewer, GLViewport},
+        Viewer,
+    },
+};
+use sdl2::{
+    event::{Event, WindowEvent},
+    video::GLProfile,
+};
+
+const USAGE: &str = "Usage: gl_scene <svg file>";
+
+// Draws an SVG file with a GL context this example owns, as an application that already has its
+// own window and context would.
+fn main() {
+    let Some(svg_path) = env::args_os().nth(1) else {
+        println!("{}", USAGE);
+        return;
+    };
+
+    let svg_object = match svg::read_from_file(Path::new(&svg_path)) {
+        Ok(svg_object) => svg_object,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
+    let sdl = sdl2::init().unwrap();
+    let video_subsystem = sdl.video().unwrap();
+
+    let gl_attr = video_subsystem.gl_attr();
+    gl_attr.set_context_profile(GLProfile::Core);
+    gl_attr.set_context_version(3, 3);
+
+    let window = video_subsystem
+        .window("GL scene", 800, 600)
+        .resizable()
+        .opengl()
+        .build()
+        .unwrap();
+    let _gl_ctx = window.gl_create_context().unwrap();
+    gl::load_with(|name| video_subsystem.gl_get_proc_address(name) as *const _);
+
+    let mut object_mgr = ObjectMgr::new();
+    object_mgr.add_object(svg_object.into());
+
+    let mut scene = match GLSceneRenderer::new(&object_mgr) {
+        Ok(scene) => scene,
+        Err(err) => {
+            println!("Error while building the scene renderer: {}", err);
+            return;
+        }
+    };
+
+    let (width, height) = window.size();
+    let mut viewer = GLViewer::new(width, height);
+    viewer.fit_to_content(&object_mgr.get_objects()[0]);
+
+    let mut event_pump = sdl.event_pump().unwrap();
+    'running: loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. } => break 'running,
+                Event::Window {
+                    win_event: WindowEvent::Resized(width, height),
+                    ..
+                } => viewer.resize(width as u32, height as u32),
+                _ => (),
+            }
+        }
+
+        let (width, height) = window.size();
+        unsafe {
+            gl::ClearColor(0.2, 0.2, 0.2, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+
+        if let Err(err) = scene.update_objects(&mut object_mgr) {
+            println!("Error while updating the scene: {}", err);
+        }
+        scene.render(
+            &GLViewport::full(width, height),
+            viewer.get_norm_to_viewer(),
+        );
+
+        window.gl_swap_window();
+    }
+}
@@ -0,0 +1,65 @@
+use std::{env, fs::File, io::BufWriter, path::Path};
+
+use drawsvg::{
+    objects::{svg, ObjectMgr},
+    render::{gl::headless::HeadlessGLRenderer, Renderer},
+};
+
+const USAGE: &str = "Usage: headless_gl <svg file> <png file>";
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 600;
+
+// Draws an SVG file with the OpenGL renderer, without a window, and writes what it drew to a PNG.
+fn main() {
+    let args: Vec<_> = env::args_os().skip(1).collect();
+    let [svg_path, png_path] = args.as_slice() else {
+        println!("{}", USAGE);
+        return;
+    };
+
+    let svg_object = match svg::read_from_file(Path::new(svg_path)) {
+        Ok(svg_object) => svg_object,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
+    let mut object_mgr = ObjectMgr::new();
+    object_mgr.add_object(svg_object.into());
+
+    let mut renderer = match HeadlessGLRenderer::new(WIDTH, HEIGHT, &object_mgr) {
+        Ok(renderer) => renderer,
+        Err(err) => {
+            println!("Error while building the headless renderer: {}", err);
+            return;
+        }
+    };
+
+    renderer
+        .get_viewer()
+        .center_on_object(&object_mgr.get_objects()[0]);
+    renderer.clear();
+    renderer.update_objects(&mut object_mgr);
+    renderer.render_objects(&object_mgr);
+    renderer.present();
+
+    let file = match File::create(png_path) {
+        Ok(file) => file,
+        Err(err) => {
+            println!("Error while creating the PNG: {}", err);
+            return;
+        }
+    };
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), WIDTH, HEIGHT);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let result = encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&renderer.to_rgba8()));
+    if let Err(err) = result {
+        println!("Error while writing the PNG: {}", err);
+    }
+}
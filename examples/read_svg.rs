@@ -2,19 +2,28 @@ use std::env;
 use std::ffi::OsString;
 use std::path::Path;
 
-use drawsvg::objects::svg;
+use drawsvg::objects::svg::{self, ParseOptions};
 
-const USAGE: &'static str = "Usage: read_svg <svg file>";
+const USAGE: &'static str = "Usage: read_svg [--strict] <svg file>";
 
 fn main() {
-    let args: Vec<OsString> = env::args_os().collect();
-    if args.len() < 2 {
+    let mut args: Vec<OsString> = env::args_os().skip(1).collect();
+
+    let options = match args.iter().position(|arg| arg == "--strict") {
+        Some(index) => {
+            args.remove(index);
+            ParseOptions::STRICT
+        }
+        None => ParseOptions::LENIENT,
+    };
+
+    if args.is_empty() {
         println!("{}", USAGE);
         return;
     }
 
-    let svg_path = Path::new(&args[1]);
-    match svg::read_from_file(svg_path) {
+    let svg_path = Path::new(&args[0]);
+    match svg::read_from_file_with_options(svg_path, &options) {
         Err(err) => println!("{}", err),
         _ => (),
     };
@@ -0,0 +1,331 @@
+use hex::FromHex;
+
+/// An 8-bit RGBA color that does not depend on any windowing library.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::RGB(0, 0, 0);
+    pub const WHITE: Self = Self::RGB(255, 255, 255);
+    pub const TRANSPARENT: Self = Self::RGBA(0, 0, 0, 0);
+
+    #[allow(non_snake_case)]
+    pub const fn RGB(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    #[allow(non_snake_case)]
+    pub const fn RGBA(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Reads a color written as 3, 4, 6 or 8 hex digits, with or without a leading `#`.
+    pub fn from_hex(value: &str) -> Option<Self> {
+        let hex = value.strip_prefix('#').unwrap_or(value);
+
+        match hex.len() {
+            3 | 4 => {
+                let mut channels = [255; 4];
+                for (channel, digit) in channels.iter_mut().zip(hex.bytes()) {
+                    *channel = (digit as char).to_digit(16)? as u8 * 17;
+                }
+                let [r, g, b, a] = channels;
+                Some(Self::RGBA(r, g, b, a))
+            }
+            6 => {
+                let [r, g, b] = <[u8; 3]>::from_hex(hex).ok()?;
+                Some(Self::RGB(r, g, b))
+            }
+            8 => {
+                let [r, g, b, a] = <[u8; 4]>::from_hex(hex).ok()?;
+                Some(Self::RGBA(r, g, b, a))
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads a CSS color: a named color, hex digits or `rgb()`/`rgba()`.
+    pub fn from_css(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.starts_with('#') {
+            return Self::from_hex(value);
+        }
+        if let Some((_, color)) = NAMED_COLORS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value))
+        {
+            return Some(*color);
+        }
+
+        match value.split_once('(') {
+            Some((function, arguments)) => Self::from_rgb_function(function, arguments),
+            None => Self::from_hex(value),
+        }
+    }
+
+    // The arguments of `rgb()` and `rgba()` are separated by commas, or by whitespace with the
+    // alpha after a `/`. Both functions take an optional alpha.
+    fn from_rgb_function(function: &str, arguments: &str) -> Option<Self> {
+        if !function.eq_ignore_ascii_case("rgb") && !function.eq_ignore_ascii_case("rgba") {
+            return None;
+        }
+
+        let arguments: Vec<&str> = arguments
+            .strip_suffix(')')?
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|argument| !argument.is_empty())
+            .collect();
+        let (r, g, b, a) = match arguments[..] {
+            [r, g, b] => (r, g, b, "1"),
+            [r, g, b, a] => (r, g, b, a),
+            _ => return None,
+        };
+
+        Some(Self::RGBA(
+            Self::channel(r, 255.0)?,
+            Self::channel(g, 255.0)?,
+            Self::channel(b, 255.0)?,
+            Self::channel(a, 1.0)?,
+        ))
+    }
+
+    // A number out of `max`, or a percentage.
+    fn channel(value: &str, max: f32) -> Option<u8> {
+        let fraction = match value.strip_suffix('%') {
+            Some(percentage) => percentage.parse::<f32>().ok()? / 100.0,
+            None => value.parse::<f32>().ok()? / max,
+        };
+        if !fraction.is_finite() {
+            return None;
+        }
+        Some((fraction.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+}
+
+// The named colors of CSS, which SVG uses too.
+const NAMED_COLORS: [(&str, Color); 149] = [
+    ("aliceblue", Color::RGB(240, 248, 255)),
+    ("antiquewhite", Color::RGB(250, 235, 215)),
+    ("aqua", Color::RGB(0, 255, 255)),
+    ("aquamarine", Color::RGB(127, 255, 212)),
+    ("azure", Color::RGB(240, 255, 255)),
+    ("beige", Color::RGB(245, 245, 220)),
+    ("bisque", Color::RGB(255, 228, 196)),
+    ("black", Color::RGB(0, 0, 0)),
+    ("blanchedalmond", Color::RGB(255, 235, 205)),
+    ("blue", Color::RGB(0, 0, 255)),
+    ("blueviolet", Color::RGB(138, 43, 226)),
+    ("brown", Color::RGB(165, 42, 42)),
+    ("burlywood", Color::RGB(222, 184, 135)),
+    ("cadetblue", Color::RGB(95, 158, 160)),
+    ("chartreuse", Color::RGB(127, 255, 0)),
+    ("chocolate", Color::RGB(210, 105, 30)),
+    ("coral", Color::RGB(255, 127, 80)),
+    ("cornflowerblue", Color::RGB(100, 149, 237)),
+    ("cornsilk", Color::RGB(255, 248, 220)),
+    ("crimson", Color::RGB(220, 20, 60)),
+    ("cyan", Color::RGB(0, 255, 255)),
+    ("darkblue", Color::RGB(0, 0, 139)),
+    ("darkcyan", Color::RGB(0, 139, 139)),
+    ("darkgoldenrod", Color::RGB(184, 134, 11)),
+    ("darkgray", Color::RGB(169, 169, 169)),
+    ("darkgreen", Color::RGB(0, 100, 0)),
+    ("darkgrey", Color::RGB(169, 169, 169)),
+    ("darkkhaki", Color::RGB(189, 183, 107)),
+    ("darkmagenta", Color::RGB(139, 0, 139)),
+    ("darkolivegreen", Color::RGB(85, 107, 47)),
+    ("darkorange", Color::RGB(255, 140, 0)),
+    ("darkorchid", Color::RGB(153, 50, 204)),
+    ("darkred", Color::RGB(139, 0, 0)),
+    ("darksalmon", Color::RGB(233, 150, 122)),
+    ("darkseagreen", Color::RGB(143, 188, 143)),
+    ("darkslateblue", Color::RGB(72, 61, 139)),
+    ("darkslategray", Color::RGB(47, 79, 79)),
+    ("darkslategrey", Color::RGB(47, 79, 79)),
+    ("darkturquoise", Color::RGB(0, 206, 209)),
+    ("darkviolet", Color::RGB(148, 0, 211)),
+    ("deeppink", Color::RGB(255, 20, 147)),
+    ("deepskyblue", Color::RGB(0, 191, 255)),
+    ("dimgray", Color::RGB(105, 105, 105)),
+    ("dimgrey", Color::RGB(105, 105, 105)),
+    ("dodgerblue", Color::RGB(30, 144, 255)),
+    ("firebrick", Color::RGB(178, 34, 34)),
+    ("floralwhite", Color::RGB(255, 250, 240)),
+    ("forestgreen", Color::RGB(34, 139, 34)),
+    ("fuchsia", Color::RGB(255, 0, 255)),
+    ("gainsboro", Color::RGB(220, 220, 220)),
+    ("ghostwhite", Color::RGB(248, 248, 255)),
+    ("gold", Color::RGB(255, 215, 0)),
+    ("goldenrod", Color::RGB(218, 165, 32)),
+    ("gray", Color::RGB(128, 128, 128)),
+    ("green", Color::RGB(0, 128, 0)),
+    ("greenyellow", Color::RGB(173, 255, 47)),
+    ("grey", Color::RGB(128, 128, 128)),
+    ("honeydew", Color::RGB(240, 255, 240)),
+    ("hotpink", Color::RGB(255, 105, 180)),
+    ("indianred", Color::RGB(205, 92, 92)),
+    ("indigo", Color::RGB(75, 0, 130)),
+    ("ivory", Color::RGB(255, 255, 240)),
+    ("khaki", Color::RGB(240, 230, 140)),
+    ("lavender", Color::RGB(230, 230, 250)),
+    ("lavenderblush", Color::RGB(255, 240, 245)),
+    ("lawngreen", Color::RGB(124, 252, 0)),
+    ("lemonchiffon", Color::RGB(255, 250, 205)),
+    ("lightblue", Color::RGB(173, 216, 230)),
+    ("lightcoral", Color::RGB(240, 128, 128)),
+    ("lightcyan", Color::RGB(224, 255, 255)),
+    ("lightgoldenrodyellow", Color::RGB(250, 250, 210)),
+    ("lightgray", Color::RGB(211, 211, 211)),
+    ("lightgreen", Color::RGB(144, 238, 144)),
+    ("lightgrey", Color::RGB(211, 211, 211)),
+    ("lightpink", Color::RGB(255, 182, 193)),
+    ("lightsalmon", Color::RGB(255, 160, 122)),
+    ("lightseagreen", Color::RGB(32, 178, 170)),
+    ("lightskyblue", Color::RGB(135, 206, 250)),
+    ("lightslategray", Color::RGB(119, 136, 153)),
+    ("lightslategrey", Color::RGB(119, 136, 153)),
+    ("lightsteelblue", Color::RGB(176, 196, 222)),
+    ("lightyellow", Color::RGB(255, 255, 224)),
+    ("lime", Color::RGB(0, 255, 0)),
+    ("limegreen", Color::RGB(50, 205, 50)),
+    ("linen", Color::RGB(250, 240, 230)),
+    ("magenta", Color::RGB(255, 0, 255)),
+    ("maroon", Color::RGB(128, 0, 0)),
+    ("mediumaquamarine", Color::RGB(102, 205, 170)),
+    ("mediumblue", Color::RGB(0, 0, 205)),
+    ("mediumorchid", Color::RGB(186, 85, 211)),
+    ("mediumpurple", Color::RGB(147, 112, 219)),
+    ("mediumseagreen", Color::RGB(60, 179, 113)),
+    ("mediumslateblue", Color::RGB(123, 104, 238)),
+    ("mediumspringgreen", Color::RGB(0, 250, 154)),
+    ("mediumturquoise", Color::RGB(72, 209, 204)),
+    ("mediumvioletred", Color::RGB(199, 21, 133)),
+    ("midnightblue", Color::RGB(25, 25, 112)),
+    ("mintcream", Color::RGB(245, 255, 250)),
+    ("mistyrose", Color::RGB(255, 228, 225)),
+    ("moccasin", Color::RGB(255, 228, 181)),
+    ("navajowhite", Color::RGB(255, 222, 173)),
+    ("navy", Color::RGB(0, 0, 128)),
+    ("oldlace", Color::RGB(253, 245, 230)),
+    ("olive", Color::RGB(128, 128, 0)),
+    ("olivedrab", Color::RGB(107, 142, 35)),
+    ("orange", Color::RGB(255, 165, 0)),
+    ("orangered", Color::RGB(255, 69, 0)),
+    ("orchid", Color::RGB(218, 112, 214)),
+    ("palegoldenrod", Color::RGB(238, 232, 170)),
+    ("palegreen", Color::RGB(152, 251, 152)),
+    ("paleturquoise", Color::RGB(175, 238, 238)),
+    ("palevioletred", Color::RGB(219, 112, 147)),
+    ("papayawhip", Color::RGB(255, 239, 213)),
+    ("peachpuff", Color::RGB(255, 218, 185)),
+    ("peru", Color::RGB(205, 133, 63)),
+    ("pink", Color::RGB(255, 192, 203)),
+    ("plum", Color::RGB(221, 160, 221)),
+    ("powderblue", Color::RGB(176, 224, 230)),
+    ("purple", Color::RGB(128, 0, 128)),
+    ("rebeccapurple", Color::RGB(102, 51, 153)),
+    ("red", Color::RGB(255, 0, 0)),
+    ("rosybrown", Color::RGB(188, 143, 143)),
+    ("royalblue", Color::RGB(65, 105, 225)),
+    ("saddlebrown", Color::RGB(139, 69, 19)),
+    ("salmon", Color::RGB(250, 128, 114)),
+    ("sandybrown", Color::RGB(244, 164, 96)),
+    ("seagreen", Color::RGB(46, 139, 87)),
+    ("seashell", Color::RGB(255, 245, 238)),
+    ("sienna", Color::RGB(160, 82, 45)),
+    ("silver", Color::RGB(192, 192, 192)),
+    ("skyblue", Color::RGB(135, 206, 235)),
+    ("slateblue", Color::RGB(106, 90, 205)),
+    ("slategray", Color::RGB(112, 128, 144)),
+    ("slategrey", Color::RGB(112, 128, 144)),
+    ("snow", Color::RGB(255, 250, 250)),
+    ("springgreen", Color::RGB(0, 255, 127)),
+    ("steelblue", Color::RGB(70, 130, 180)),
+    ("tan", Color::RGB(210, 180, 140)),
+    ("teal", Color::RGB(0, 128, 128)),
+    ("thistle", Color::RGB(216, 191, 216)),
+    ("tomato", Color::RGB(255, 99, 71)),
+    ("turquoise", Color::RGB(64, 224, 208)),
+    ("violet", Color::RGB(238, 130, 238)),
+    ("wheat", Color::RGB(245, 222, 179)),
+    ("white", Color::RGB(255, 255, 255)),
+    ("whitesmoke", Color::RGB(245, 245, 245)),
+    ("yellow", Color::RGB(255, 255, 0)),
+    ("yellowgreen", Color::RGB(154, 205, 50)),
+    ("transparent", Color::TRANSPARENT),
+];
+
+#[cfg(feature = "sdl")]
+impl From<Color> for sdl2::pixels::Color {
+    fn from(value: Color) -> Self {
+        Self::RGBA(value.r, value.g, value.b, value.a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Color;
+
+    #[test]
+    fn hex_colors_are_read_with_any_number_of_digits() {
+        assert_eq!(Color::from_hex("#f80"), Some(Color::RGB(255, 136, 0)));
+        assert_eq!(
+            Color::from_hex("#f808"),
+            Some(Color::RGBA(255, 136, 0, 136))
+        );
+        assert_eq!(Color::from_hex("ff8800"), Some(Color::RGB(255, 136, 0)));
+        assert_eq!(
+            Color::from_hex("#ff880080"),
+            Some(Color::RGBA(255, 136, 0, 128))
+        );
+        assert_eq!(
+            Color::from_hex("#ff88"),
+            Some(Color::RGBA(255, 255, 136, 136))
+        );
+        assert_eq!(Color::from_hex("#ff8"), Some(Color::RGB(255, 255, 136)));
+        assert_eq!(Color::from_hex("#ff880"), None);
+        assert_eq!(Color::from_hex("#fg0"), None);
+    }
+
+    #[test]
+    fn css_colors_are_read() {
+        assert_eq!(Color::from_css("red"), Some(Color::RGB(255, 0, 0)));
+        assert_eq!(
+            Color::from_css(" CornflowerBlue "),
+            Some(Color::RGB(100, 149, 237))
+        );
+        assert_eq!(Color::from_css("transparent"), Some(Color::TRANSPARENT));
+        assert_eq!(Color::from_css("#0f0"), Some(Color::RGB(0, 255, 0)));
+        assert_eq!(
+            Color::from_css("rgb(255, 128, 0)"),
+            Some(Color::RGB(255, 128, 0))
+        );
+        assert_eq!(
+            Color::from_css("rgba(100%, 0%, 0%, 0.5)"),
+            Some(Color::RGBA(255, 0, 0, 128))
+        );
+        assert_eq!(
+            Color::from_css("rgb(0 0 255 / 25%)"),
+            Some(Color::RGBA(0, 0, 255, 64))
+        );
+        assert_eq!(
+            Color::from_css("RGB(300, -5, 0)"),
+            Some(Color::RGB(255, 0, 0))
+        );
+
+        for invalid in [
+            "reddish",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4, 5)",
+            "hsl(0, 0%, 0%)",
+            "rgb(1, 2, 3",
+        ] {
+            assert_eq!(Color::from_css(invalid), None, "{}", invalid);
+        }
+    }
+}
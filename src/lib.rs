@@ -1,6 +1,8 @@
+pub mod color;
 pub mod matrix;
 pub mod objects;
 pub mod render;
+#[cfg(feature = "sdl")]
 pub mod sdl_wrapper;
 pub mod texture;
 pub mod tools;
@@ -1,6 +1,11 @@
-use std::{env, ffi::OsString, path::PathBuf, time::Instant};
+use std::{env, ffi::OsString, fs::File, io::BufWriter, path::PathBuf, process};
 
+#[cfg(feature = "viewer")]
+use std::time::Instant;
+
+#[cfg(feature = "viewer")]
 use num_traits::Pow;
+#[cfg(feature = "viewer")]
 use sdl2::{
     event::{Event, WindowEvent},
     keyboard::KeyboardState,
@@ -9,53 +14,280 @@ use sdl2::{
 };
 
 use drawsvg::{
+    color::Color,
     objects::{svg, ObjectMgr},
-    render::Renderer,
-    sdl_wrapper::SDLContext,
+    render::{software::SoftwareRenderer, Renderer},
+    vector::Vector2D,
+};
+#[cfg(feature = "viewer")]
+use drawsvg::{
+    sdl_wrapper::{Backend, SDLContext},
     tools::FpsCounter,
 };
 
 // Measured in pixels
+#[cfg(feature = "viewer")]
 const WINDOW_HEIGHT: u32 = 400;
+#[cfg(feature = "viewer")]
 const WINDOW_WIDTH: u32 = 800;
 
 // Number of pixels to move per microsecond
+#[cfg(feature = "viewer")]
 const CAMERA_MOVE_SPEED: f32 = 0.0002;
 
 // Fraction to zoom in or out by per microsecond.
 // A value of 2.0 would double the zoom every microsecond exponentially.
 // A value of 1.000001 works out to zooming by about 2.72x per second.
 // (Don't you love it when things just work out to approximating 'e'?)
+#[cfg(feature = "viewer")]
 const KEYBOARD_ZOOM_IN_SPEED: f32 = 1.000001;
+#[cfg(feature = "viewer")]
 const KEYBOARD_ZOOM_OUT_SPEED: f32 = 1.0 / KEYBOARD_ZOOM_IN_SPEED;
 
 // Fraction to zoom in or out by when the mouse wheel ticks up or down by one.
 // A value of 1.1 increases the zoom by 1.1 per tick.
+#[cfg(feature = "viewer")]
 const MOUSE_ZOOM_IN_SPEED: f32 = 1.1;
+#[cfg(feature = "viewer")]
 const MOUSE_ZOOM_OUT_SPEED: f32 = 1.0 / MOUSE_ZOOM_IN_SPEED;
 
+#[cfg(feature = "viewer")]
 const WINDOW_TITLE: &str = "Diligent Garbanzo";
 
-struct Args {
+// CSS pixels are defined as 1/96th of an inch.
+const CSS_PIXELS_PER_INCH: f32 = 96.0;
+
+// Largest width or height of an exported image, in pixels.
+const MAX_EXPORT_SIZE: u32 = 16384;
+
+enum Command {
+    #[cfg(feature = "viewer")]
+    View(ViewArgs),
+    Render(RenderArgs),
+}
+
+#[cfg(feature = "viewer")]
+struct ViewArgs {
     svg_path: PathBuf,
+    backend: Backend,
+}
+
+struct RenderArgs {
+    svg_path: PathBuf,
+    output_path: PathBuf,
+    width: Option<u32>,
+    height: Option<u32>,
+    scale: Option<f32>,
+    background: Color,
 }
 
 fn print_usage() {
-    println!("Usage: {} <svg file>", env!("CARGO_PKG_NAME"));
+    #[cfg(feature = "viewer")]
+    println!(
+        "Usage: {} <svg file> [--backend gl|canvas|software]",
+        env!("CARGO_PKG_NAME")
+    );
+    println!(
+        "Usage: {} render <svg file> -o <png file> [options]",
+        env!("CARGO_PKG_NAME")
+    );
+    println!();
+    println!("Options for render:");
+    println!(
+        "  --width <px>, --height <px>  Size of the image. Defaults to the size of the document."
+    );
+    println!("  --scale <factor>             Multiplies the size of the document.");
+    println!(
+        "  --dpi <dpi>                  Scales the document as if it were printed at this DPI."
+    );
+    println!("  --background <color>         `transparent` (the default) or a hex color.");
+}
+
+// Takes the arguments without the name of the program.
+fn parse_args(args: impl Iterator<Item = OsString>) -> Result<Command, String> {
+    let mut args = args.peekable();
+
+    if args.peek().is_some_and(|arg| arg == "render") {
+        args.next();
+        return parse_render_args(args);
+    }
+
+    parse_view_args(args)
+}
+
+#[cfg(not(feature = "viewer"))]
+fn parse_view_args(_args: impl Iterator<Item = OsString>) -> Result<Command, String> {
+    Err(String::from(
+        "This build can only render to PNG. Viewing needs the `viewer` feature.",
+    ))
 }
 
-fn parse_args() -> Option<Args> {
-    let args: Vec<OsString> = env::args_os().collect();
-    if args.len() < 2 {
-        print_usage();
-        return None;
+#[cfg(feature = "viewer")]
+fn parse_view_args(mut args: impl Iterator<Item = OsString>) -> Result<Command, String> {
+    let mut svg_path = None;
+    let mut backend = Backend::GL;
+
+    while let Some(arg) = args.next() {
+        if arg != "--backend" {
+            if svg_path.replace(PathBuf::from(arg)).is_some() {
+                return Err(String::from("Only one SVG file can be viewed at a time."));
+            }
+            continue;
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| String::from("Missing a value after --backend."))?;
+        backend = value
+            .to_str()
+            .ok_or_else(|| String::from("Invalid value for --backend."))?
+            .parse()?;
     }
 
-    return Some(Args {
-        svg_path: PathBuf::from(args.into_iter().nth(1).unwrap()),
-    });
+    Ok(Command::View(ViewArgs {
+        svg_path: svg_path.ok_or("Missing the path to an SVG file.")?,
+        backend,
+    }))
 }
 
+fn parse_render_args(mut args: impl Iterator<Item = OsString>) -> Result<Command, String> {
+    let mut svg_path = None;
+    let mut output_path = None;
+    let mut width = None;
+    let mut height = None;
+    let mut scale = None;
+    let mut background = Color::TRANSPARENT;
+
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.to_str().filter(|arg| arg.starts_with('-')) else {
+            if svg_path.replace(PathBuf::from(arg)).is_some() {
+                return Err(String::from("Only one SVG file can be rendered at a time."));
+            }
+            continue;
+        };
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing a value after {}.", flag))?;
+        if flag == "-o" || flag == "--output" {
+            output_path = Some(PathBuf::from(value));
+            continue;
+        }
+
+        let value = value
+            .to_str()
+            .ok_or_else(|| format!("Invalid value for {}.", flag))?;
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        match flag {
+            "--width" => width = Some(value.parse().map_err(|_| invalid())?),
+            "--height" => height = Some(value.parse().map_err(|_| invalid())?),
+            "--scale" | "--dpi" => {
+                let number: f32 = value.parse().map_err(|_| invalid())?;
+                if !number.is_finite() || number <= 0.0 {
+                    return Err(invalid());
+                }
+                if scale.is_some() {
+                    return Err(String::from("Only one of --scale or --dpi can be given."));
+                }
+                scale = Some(if flag == "--dpi" {
+                    number / CSS_PIXELS_PER_INCH
+                } else {
+                    number
+                });
+            }
+            "--background" => {
+                background = if value == "transparent" {
+                    Color::TRANSPARENT
+                } else {
+                    Color::from_hex(value).ok_or_else(invalid)?
+                }
+            }
+            _ => return Err(format!("Unknown option {}.", flag)),
+        }
+    }
+
+    if scale.is_some() && (width.is_some() || height.is_some()) {
+        return Err(String::from(
+            "--scale and --dpi cannot be combined with --width or --height.",
+        ));
+    }
+
+    Ok(Command::Render(RenderArgs {
+        svg_path: svg_path.ok_or("Missing the path to an SVG file.")?,
+        output_path: output_path.ok_or("Missing the path of the PNG file to write with -o.")?,
+        width,
+        height,
+        scale,
+        background,
+    }))
+}
+
+// Sizes the image like the document unless told otherwise. Giving only one side keeps the
+// document's aspect ratio.
+fn export_size(args: &RenderArgs, document_size: &Vector2D<f32>) -> Result<(u32, u32), String> {
+    let (document_width, document_height) = (document_size[0], document_size[1]);
+    let scaled = |length: f32| length.round() as u32;
+
+    let (width, height) = match (args.width, args.height) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (
+            width,
+            scaled(width as f32 * document_height / document_width),
+        ),
+        (None, Some(height)) => (
+            scaled(height as f32 * document_width / document_height),
+            height,
+        ),
+        (None, None) => {
+            let scale = args.scale.unwrap_or(1.0);
+            (
+                scaled(document_width * scale),
+                scaled(document_height * scale),
+            )
+        }
+    };
+
+    if width == 0 || height == 0 || width > MAX_EXPORT_SIZE || height > MAX_EXPORT_SIZE {
+        return Err(format!(
+            "Cannot export a {}x{} image. Both sides must be between 1 and {} pixels.",
+            width, height, MAX_EXPORT_SIZE
+        ));
+    }
+
+    Ok((width, height))
+}
+
+// Draws the document to fill the image, keeping its aspect ratio, and writes it as a PNG.
+fn export_png(args: &RenderArgs) -> Result<(), String> {
+    let svg_object = svg::read_from_file(&args.svg_path).map_err(|err| err.to_string())?;
+    let (width, height) = export_size(args, &svg_object.dimension)?;
+
+    let mut object_mgr = ObjectMgr::new();
+    object_mgr.add_object(svg_object.into());
+
+    let mut renderer = SoftwareRenderer::new(width, height);
+    renderer.set_background(args.background);
+    renderer
+        .get_viewer()
+        .center_on_object(&object_mgr.get_objects()[0]);
+
+    renderer.clear();
+    renderer.update_objects(&mut object_mgr);
+    renderer.render_objects(&object_mgr);
+
+    let file = File::create(&args.output_path).map_err(|err| {
+        format!(
+            "Error while creating {}: {}",
+            args.output_path.display(),
+            err
+        )
+    })?;
+    renderer
+        .write_png(BufWriter::new(file))
+        .map_err(|err| format!("Error while writing the PNG: {}", err))
+}
+
+#[cfg(feature = "viewer")]
 fn update_vsync_settings(
     sdl_context: &SDLContext,
     keyboard_state: &KeyboardState,
@@ -77,6 +309,7 @@ fn update_vsync_settings(
     Ok(())
 }
 
+#[cfg(feature = "viewer")]
 fn update_viewer_from_keyboard(
     renderer: &mut dyn Renderer,
     keyboard_state: &KeyboardState,
@@ -96,6 +329,11 @@ fn update_viewer_from_keyboard(
             viewer.center_on_object(object);
         }
     }
+    if keyboard_state.is_scancode_pressed(sdl2::keyboard::Scancode::F) {
+        if let Some(object) = object_mgr.get_objects().first() {
+            viewer.fit_to_content(object);
+        }
+    }
     if keyboard_state.is_scancode_pressed(sdl2::keyboard::Scancode::Left) {
         viewer.move_by_pixels(-CAMERA_MOVE_SPEED * us_of_frame, 0.0);
     }
@@ -110,6 +348,7 @@ fn update_viewer_from_keyboard(
     }
 }
 
+#[cfg(feature = "viewer")]
 fn update_viewer_from_mouse_scrolling(renderer: &mut dyn Renderer, mouse_wheel_movement: f32) {
     if mouse_wheel_movement == 0.0 {
         return;
@@ -126,6 +365,7 @@ fn update_viewer_from_mouse_scrolling(renderer: &mut dyn Renderer, mouse_wheel_m
     }
 }
 
+#[cfg(feature = "viewer")]
 fn update_viewer_from_mouse_position(
     renderer: &mut dyn Renderer,
     prev_state: &MouseState,
@@ -150,6 +390,7 @@ fn update_viewer_from_mouse_position(
         .move_by_pixels(-delta_x as f32, -delta_y as f32);
 }
 
+#[cfg(feature = "viewer")]
 fn update_viewer_from_mouse(
     renderer: &mut dyn Renderer,
     prev_state: &MouseState,
@@ -161,11 +402,25 @@ fn update_viewer_from_mouse(
 }
 
 fn main() {
-    let args = match parse_args() {
-        None => return,
-        Some(args) => args,
-    };
+    match parse_args(env::args_os().skip(1)) {
+        #[cfg(feature = "viewer")]
+        Ok(Command::View(args)) => view(args),
+        Ok(Command::Render(args)) => {
+            if let Err(err) = export_png(&args) {
+                println!("{}", err);
+                process::exit(1);
+            }
+        }
+        Err(err) => {
+            println!("{}", err);
+            print_usage();
+            process::exit(2);
+        }
+    }
+}
 
+#[cfg(feature = "viewer")]
+fn view(args: ViewArgs) {
     let svg_object = match svg::read_from_file(args.svg_path.as_ref()) {
         Err(err) => {
             println!("{}", err);
@@ -174,6 +429,9 @@ fn main() {
         Ok(svg) => svg,
     };
 
+    // Documents without a title are shown under the name of the viewer.
+    let window_title = svg_object.title().unwrap_or(WINDOW_TITLE).to_owned();
+
     let mut object_mgr = ObjectMgr::new();
     object_mgr.add_object(svg_object.into());
 
@@ -185,16 +443,27 @@ fn main() {
         }
     };
 
-    let mut renderer = match sdl_context.build_new_gl_window(
-        WINDOW_TITLE,
-        WINDOW_WIDTH,
-        WINDOW_HEIGHT,
-        &object_mgr,
-    ) {
-        Ok(renderer) => renderer,
-        Err(err) => {
-            println!("Error while building a new window: {}", err);
-            return;
+    // Backends that cannot start, like GL on a machine with broken drivers, give way to the next.
+    let mut backend = args.backend;
+    let mut renderer = loop {
+        match sdl_context.build_new_backend_window(
+            backend,
+            &window_title,
+            WINDOW_WIDTH,
+            WINDOW_HEIGHT,
+            &object_mgr,
+        ) {
+            Ok(renderer) => break renderer,
+            Err(err) => {
+                println!("Error while building a new {} window: {}", backend, err);
+                match backend.fallback() {
+                    Some(fallback) => {
+                        println!("Falling back to the {} backend.", fallback);
+                        backend = fallback;
+                    }
+                    None => return,
+                }
+            }
         }
     };
 
@@ -251,7 +520,8 @@ fn main() {
         }
 
         renderer.clear();
-        renderer.render_objects();
+        renderer.update_objects(&mut object_mgr);
+        renderer.render_objects(&object_mgr);
         renderer.present();
 
         let frame_end_time = Instant::now();
@@ -265,10 +535,14 @@ fn main() {
 
         let keyboard_state = sdl_context.event_pump.keyboard_state();
 
-        match update_vsync_settings(&sdl_context, &keyboard_state) {
-            Err(err) => println!("Error while updating Vsync: {}", err),
-            _ => (),
-        };
+        // The swap interval belongs to the GL context. The other backends ask for vsync when
+        // their canvas is made.
+        if backend == Backend::GL {
+            match update_vsync_settings(&sdl_context, &keyboard_state) {
+                Err(err) => println!("Error while updating Vsync: {}", err),
+                _ => (),
+            };
+        }
 
         update_viewer_from_keyboard(
             renderer.as_mut(),
@@ -289,3 +563,141 @@ fn main() {
         last_mouse_state = Some(mouse_state.clone());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(OsString::from))
+    }
+
+    fn parse_render(args: &[&str]) -> RenderArgs {
+        match parse(args) {
+            Ok(Command::Render(args)) => args,
+            #[cfg(feature = "viewer")]
+            Ok(_) => panic!("Expected a render command"),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn size(args: &[&str], document_size: [f32; 2]) -> Result<(u32, u32), String> {
+        export_size(&parse_render(args), &Vector2D::from(document_size))
+    }
+
+    #[cfg(feature = "viewer")]
+    #[test]
+    fn the_viewer_takes_a_file_and_a_backend() {
+        let Ok(Command::View(args)) = parse(&["in.svg", "--backend", "software"]) else {
+            panic!("Expected a view command");
+        };
+        assert_eq!(args.svg_path, PathBuf::from("in.svg"));
+        assert_eq!(args.backend, Backend::Software);
+
+        assert!(parse(&["in.svg", "--backend"]).is_err());
+        assert!(parse(&["in.svg", "other.svg"]).is_err());
+        assert!(parse(&[]).is_err());
+    }
+
+    #[cfg(not(feature = "viewer"))]
+    #[test]
+    fn only_render_is_built_without_the_viewer() {
+        assert!(parse(&["in.svg"]).is_err());
+    }
+
+    #[test]
+    fn render_takes_its_options_in_any_order() {
+        let args = parse_render(&[
+            "render",
+            "--width",
+            "640",
+            "in.svg",
+            "--background",
+            "#ff0000",
+            "-o",
+            "out.png",
+        ]);
+
+        assert_eq!(args.svg_path, PathBuf::from("in.svg"));
+        assert_eq!(args.output_path, PathBuf::from("out.png"));
+        assert_eq!(
+            (args.width, args.height, args.scale),
+            (Some(640), None, None)
+        );
+        assert_eq!(args.background, Color::RGBA(255, 0, 0, 255));
+    }
+
+    #[test]
+    fn dpi_is_a_scale_from_css_pixels() {
+        let args = parse_render(&["render", "in.svg", "-o", "out.png", "--dpi", "192"]);
+        assert_eq!(args.scale, Some(2.0));
+        assert_eq!(args.background, Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn render_rejects_invalid_arguments() {
+        for args in [
+            &["render", "in.svg"][..],
+            &["render", "-o", "out.png"],
+            &["render", "in.svg", "other.svg", "-o", "out.png"],
+            &["render", "in.svg", "-o"],
+            &["render", "in.svg", "-o", "out.png", "--width", "wide"],
+            &["render", "in.svg", "-o", "out.png", "--scale", "0"],
+            &[
+                "render", "in.svg", "-o", "out.png", "--scale", "2", "--dpi", "96",
+            ],
+            &[
+                "render", "in.svg", "-o", "out.png", "--scale", "2", "--height", "10",
+            ],
+            &[
+                "render", "in.svg", "-o", "out.png", "--dpi", "96", "--width", "10",
+            ],
+            &["render", "in.svg", "-o", "out.png", "--background", "red"],
+            &["render", "in.svg", "-o", "out.png", "--frobnicate", "1"],
+        ] {
+            assert!(parse(args).is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn images_are_sized_like_the_document_by_default() {
+        let args = ["render", "in.svg", "-o", "out.png"];
+        assert_eq!(size(&args, [200.0, 100.4]), Ok((200, 100)));
+    }
+
+    #[test]
+    fn one_side_keeps_the_aspect_ratio() {
+        let width = ["render", "in.svg", "-o", "out.png", "--width", "50"];
+        assert_eq!(size(&width, [200.0, 100.0]), Ok((50, 25)));
+
+        let height = ["render", "in.svg", "-o", "out.png", "--height", "50"];
+        assert_eq!(size(&height, [200.0, 100.0]), Ok((100, 50)));
+
+        let both = [
+            "render", "in.svg", "-o", "out.png", "--width", "30", "--height", "40",
+        ];
+        assert_eq!(size(&both, [200.0, 100.0]), Ok((30, 40)));
+    }
+
+    #[test]
+    fn scale_and_dpi_resize_the_document() {
+        let scale = ["render", "in.svg", "-o", "out.png", "--scale", "1.5"];
+        assert_eq!(size(&scale, [200.0, 100.0]), Ok((300, 150)));
+
+        let dpi = ["render", "in.svg", "-o", "out.png", "--dpi", "48"];
+        assert_eq!(size(&dpi, [200.0, 100.0]), Ok((100, 50)));
+    }
+
+    #[test]
+    fn images_must_have_a_size_that_can_be_exported() {
+        let args = ["render", "in.svg", "-o", "out.png"];
+        assert!(size(&args, [0.0, 100.0]).is_err());
+        assert!(size(&args, [0.2, 100.0]).is_err());
+
+        let huge = ["render", "in.svg", "-o", "out.png", "--scale", "100"];
+        assert!(size(&huge, [200.0, 100.0]).is_err());
+
+        let largest = ["render", "in.svg", "-o", "out.png", "--width", "16384"];
+        assert_eq!(size(&largest, [200.0, 100.0]), Ok((16384, 8192)));
+    }
+}
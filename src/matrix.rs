@@ -113,6 +113,35 @@ where
     ]);
 }
 
+impl<T> Matrix3x3<T>
+where
+    T: num_traits::Float,
+{
+    /// Returns the inverse of this matrix.
+    ///
+    /// Returns None if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.0;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+
+        let adjugate = [
+            [cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+            [-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+            [cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)],
+        ];
+
+        let determinant =
+            m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+        if determinant == T::zero() || !determinant.is_finite() {
+            return None;
+        }
+
+        Some(Self(adjugate.map(|row| row.map(|item| item / determinant))))
+    }
+}
+
 impl<T, const ROWS: usize, const COLS: usize> From<[[T; COLS]; ROWS]>
     for StaticMatrix<T, ROWS, COLS>
 {
@@ -398,6 +427,20 @@ mod tests {
         assert_eq!(mat_a * mat_b, mat_res);
     }
 
+    #[test]
+    fn matrix_inverse() {
+        let mat: Matrix3x3<f32> = StaticMatrix([[2.0, 0.0, 4.0], [0.0, 0.5, -2.0], [0.0, 0.0, 1.0]]);
+        let mat_inv = StaticMatrix([[0.5, 0.0, -2.0], [0.0, 2.0, 4.0], [0.0, 0.0, 1.0]]);
+        assert_eq!(mat.inverse(), Some(mat_inv));
+        assert_eq!(&mat * &mat.inverse().unwrap(), Matrix3x3::IDENTITY3X3);
+    }
+
+    #[test]
+    fn matrix_inverse_singular() {
+        let mat: Matrix3x3<f32> = StaticMatrix([[1.0, 2.0, 0.0], [2.0, 4.0, 0.0], [0.0, 0.0, 1.0]]);
+        assert_eq!(mat.inverse(), None);
+    }
+
     #[test]
     fn mat_vec_mul() {
         let mat = StaticMatrix([[1, 2, 3], [-1, -2, -3], [1, 0, -1]]);
@@ -33,6 +33,10 @@ impl ObjectMgr {
         &self.objects
     }
 
+    pub fn get_objects_mut(&mut self) -> &mut [Object] {
+        &mut self.objects
+    }
+
     pub fn add_object(&mut self, object: Object) {
         self.objects.push(object);
     }
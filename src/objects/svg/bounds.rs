@@ -0,0 +1,434 @@
+use crate::vector::Vector2D;
+
+use super::{
+    outer_join, Element, EmptyTag, Line, OuterJoin, Paint, Rect, StartTag, Style, Transform, SVG,
+};
+
+/// Axis aligned box around an element, in the coordinates of the document it belongs to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vector2D<f32>,
+    pub max: Vector2D<f32>,
+}
+
+impl BoundingBox {
+    pub fn from_point(x: f32, y: f32) -> Self {
+        Self {
+            min: [x, y].into(),
+            max: [x, y].into(),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max[0] - self.min[0]
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max[1] - self.min[1]
+    }
+
+    pub fn size(&self) -> Vector2D<f32> {
+        &self.max - &self.min
+    }
+
+    pub fn center(&self) -> Vector2D<f32> {
+        (&self.min + &self.max) * 0.5
+    }
+
+    pub fn contains(&self, point: &Vector2D<f32>) -> bool {
+        point[0] >= self.min[0]
+            && point[0] <= self.max[0]
+            && point[1] >= self.min[1]
+            && point[1] <= self.max[1]
+    }
+
+    /// Whether the boxes share any point, edges included.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min[0] <= other.max[0]
+            && other.min[0] <= self.max[0]
+            && self.min[1] <= other.max[1]
+            && other.min[1] <= self.max[1]
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: [self.min[0].min(other.min[0]), self.min[1].min(other.min[1])].into(),
+            max: [self.max[0].max(other.max[0]), self.max[1].max(other.max[1])].into(),
+        }
+    }
+
+    /// Grows the box until it covers the point.
+    pub fn include(&mut self, x: f32, y: f32) {
+        self.min[0] = self.min[0].min(x);
+        self.min[1] = self.min[1].min(y);
+        self.max[0] = self.max[0].max(x);
+        self.max[1] = self.max[1].max(y);
+    }
+}
+
+fn union(bounds: Option<BoundingBox>, other: Option<BoundingBox>) -> Option<BoundingBox> {
+    match (bounds, other) {
+        (Some(bounds), Some(other)) => Some(bounds.union(&other)),
+        (bounds, None) => bounds,
+        (None, other) => other,
+    }
+}
+
+// Grows a box around geometry given in the local coordinates of an element.
+struct BoundsBuilder<'a> {
+    transform: &'a Transform,
+    bounds: Option<BoundingBox>,
+}
+
+impl<'a> BoundsBuilder<'a> {
+    fn new(transform: &'a Transform) -> Self {
+        Self {
+            transform,
+            bounds: None,
+        }
+    }
+
+    fn add_point(&mut self, x: f32, y: f32) {
+        let t = self.transform;
+        let x_out = t[0][0] * x + t[0][1] * y + t[0][2];
+        let y_out = t[1][0] * x + t[1][1] * y + t[1][2];
+
+        match &mut self.bounds {
+            Some(bounds) => bounds.include(x_out, y_out),
+            None => self.bounds = Some(BoundingBox::from_point(x_out, y_out)),
+        }
+    }
+
+    // A transformed ellipse reaches sqrt((a * rx)^2 + (c * ry)^2) away from its center in x, and
+    // likewise in y, where a and c are the first row of the linear part of the transform.
+    fn add_ellipse(&mut self, cx: f32, cy: f32, rx: f32, ry: f32) {
+        let t = self.transform;
+        let half_width = (t[0][0] * rx).hypot(t[0][1] * ry);
+        let half_height = (t[1][0] * rx).hypot(t[1][1] * ry);
+
+        let center_x = t[0][0] * cx + t[0][1] * cy + t[0][2];
+        let center_y = t[1][0] * cx + t[1][1] * cy + t[1][2];
+
+        let mut bounds = BoundingBox::from_point(center_x - half_width, center_y - half_height);
+        bounds.include(center_x + half_width, center_y + half_height);
+
+        self.bounds = union(self.bounds.take(), Some(bounds));
+    }
+
+    fn add_rect(&mut self, rect: &Rect, half_stroke: f32) {
+        let (rx, ry) = rect.corner_radii();
+
+        // Square corners are stroked with square miters.
+        if rx == 0.0 {
+            self.add_point(rect.x - half_stroke, rect.y - half_stroke);
+            self.add_point(rect.x + rect.width + half_stroke, rect.y - half_stroke);
+            self.add_point(
+                rect.x + rect.width + half_stroke,
+                rect.y + rect.height + half_stroke,
+            );
+            self.add_point(rect.x - half_stroke, rect.y + rect.height + half_stroke);
+            return;
+        }
+
+        // A rounded rect is the convex hull of the ellipses making up its corners.
+        let left = rect.x + rx;
+        let right = rect.x + rect.width - rx;
+        let top = rect.y + ry;
+        let bottom = rect.y + rect.height - ry;
+        for (cx, cy) in [(left, top), (right, top), (right, bottom), (left, bottom)] {
+            self.add_ellipse(cx, cy, rx + half_stroke, ry + half_stroke);
+        }
+    }
+
+    // Lines end with butt caps.
+    fn add_segment(&mut self, from: &Vector2D<f32>, to: &Vector2D<f32>, half_stroke: f32) {
+        let Ok(direction) = (to - from).unit() else {
+            self.add_point(from[0], from[1]);
+            return;
+        };
+        let normal = Vector2D::from([-direction[1], direction[0]]) * half_stroke;
+
+        for point in [from, to] {
+            self.add_point(point[0] + normal[0], point[1] + normal[1]);
+            self.add_point(point[0] - normal[0], point[1] - normal[1]);
+        }
+    }
+
+    // Joins that exceed the miter limit are beveled, which the segments already cover.
+    fn add_join(
+        &mut self,
+        previous: &Vector2D<f32>,
+        point: &Vector2D<f32>,
+        next: &Vector2D<f32>,
+        half_stroke: f32,
+        miter_limit: f32,
+    ) {
+        if let Some(OuterJoin {
+            miter_tip: Some(tip),
+            ..
+        }) = outer_join(previous, point, next, half_stroke, miter_limit)
+        {
+            self.add_point(tip[0], tip[1]);
+        }
+    }
+
+    fn add_path(
+        &mut self,
+        points: &[Vector2D<f32>],
+        is_closed: bool,
+        style: &Style,
+        half_stroke: f32,
+    ) {
+        for point in points {
+            self.add_point(point[0], point[1]);
+        }
+
+        if half_stroke <= 0.0 || points.len() < 2 {
+            return;
+        }
+
+        for segment in points.windows(2) {
+            self.add_segment(&segment[0], &segment[1], half_stroke);
+        }
+
+        for join in points.windows(3) {
+            self.add_join(&join[0], &join[1], &join[2], half_stroke, style.miter_limit);
+        }
+
+        if is_closed && points.len() > 2 {
+            let first = &points[0];
+            let second = &points[1];
+            let last = &points[points.len() - 1];
+            let second_last = &points[points.len() - 2];
+
+            self.add_segment(last, first, half_stroke);
+            self.add_join(second_last, last, first, half_stroke, style.miter_limit);
+            self.add_join(last, first, second, half_stroke, style.miter_limit);
+        }
+    }
+
+    fn add_line(&mut self, line: &Line, half_stroke: f32) {
+        self.add_point(line.from[0], line.from[1]);
+        self.add_point(line.to[0], line.to[1]);
+
+        if half_stroke > 0.0 {
+            self.add_segment(&line.from, &line.to, half_stroke);
+        }
+    }
+}
+
+impl EmptyTag {
+    /// Returns the box around the geometry of this shape.
+    pub fn fill_bounds(&self) -> Option<BoundingBox> {
+        self.bounds(false)
+    }
+
+    /// Returns the box around the geometry of this shape and its stroke, if it has one.
+    pub fn stroke_bounds(&self) -> Option<BoundingBox> {
+        self.bounds(true)
+    }
+
+    fn bounds(&self, include_stroke: bool) -> Option<BoundingBox> {
+        let style = self.style();
+        let has_stroke = style.stroke != Paint::None && style.stroke_width > 0.0;
+        let half_stroke = if include_stroke && has_stroke {
+            style.stroke_width * 0.5
+        } else {
+            0.0
+        };
+
+        let mut builder = BoundsBuilder::new(&style.transform);
+        match self {
+            EmptyTag::Ellipse(ellipse) => builder.add_ellipse(
+                ellipse.center[0],
+                ellipse.center[1],
+                ellipse.radius[0].max(0.0) + half_stroke,
+                ellipse.radius[1].max(0.0) + half_stroke,
+            ),
+            EmptyTag::Image(image) => {
+                builder.add_point(image.x, image.y);
+                builder.add_point(image.x + image.width, image.y + image.height);
+                builder.add_point(image.x + image.width, image.y);
+                builder.add_point(image.x, image.y + image.height);
+            }
+            EmptyTag::Line(line) => builder.add_line(line, half_stroke),
+            EmptyTag::Point(point) => builder.add_point(point.position[0], point.position[1]),
+            EmptyTag::Polygon(polygon) => {
+                builder.add_path(&polygon.points, true, style, half_stroke)
+            }
+            EmptyTag::Polyline(polyline) => {
+                builder.add_path(&polyline.points, false, style, half_stroke)
+            }
+            EmptyTag::Rect(rect) => builder.add_rect(rect, half_stroke),
+        }
+
+        builder.bounds
+    }
+}
+
+impl Element {
+    /// Returns the box around the geometry of this element and all of its children.
+    pub fn fill_bounds(&self) -> Option<BoundingBox> {
+        self.bounds(false)
+    }
+
+    /// Returns the box around the geometry and strokes of this element and all of its children.
+    pub fn stroke_bounds(&self) -> Option<BoundingBox> {
+        self.bounds(true)
+    }
+
+    fn bounds(&self, include_stroke: bool) -> Option<BoundingBox> {
+        match self {
+            Element::EmptyTag(empty_tag) => empty_tag.bounds(include_stroke),
+            Element::EndTag(..) => None,
+            Element::StartTag(StartTag::Group(group)) => bounds_of(&group.elements, include_stroke),
+            Element::StartTag(StartTag::SVG(svg)) => bounds_of(&svg.elements, include_stroke),
+        }
+    }
+}
+
+impl SVG {
+    /// Returns the box around the geometry of the document. This can differ from its dimension.
+    pub fn fill_bounds(&self) -> Option<BoundingBox> {
+        bounds_of(&self.elements, false)
+    }
+
+    /// Returns the box around the geometry and strokes of the document.
+    pub fn stroke_bounds(&self) -> Option<BoundingBox> {
+        bounds_of(&self.elements, true)
+    }
+}
+
+fn bounds_of(elements: &[Element], include_stroke: bool) -> Option<BoundingBox> {
+    elements.iter().fold(None, |bounds, element| {
+        union(bounds, element.bounds(include_stroke))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::svg::{read_from_str, ParseOptions};
+
+    fn bounds(contents: &str) -> (BoundingBox, BoundingBox) {
+        let svg = read_from_str(contents, &ParseOptions::STRICT).unwrap();
+        (svg.fill_bounds().unwrap(), svg.stroke_bounds().unwrap())
+    }
+
+    fn new_box(min: [f32; 2], max: [f32; 2]) -> BoundingBox {
+        BoundingBox {
+            min: min.into(),
+            max: max.into(),
+        }
+    }
+
+    fn assert_close(actual: &BoundingBox, expected: &BoundingBox) {
+        const TOLERANCE: f32 = 1e-4;
+        let close = (0..2).all(|i| {
+            (actual.min[i] - expected.min[i]).abs() < TOLERANCE
+                && (actual.max[i] - expected.max[i]).abs() < TOLERANCE
+        });
+        assert!(close, "{:?} is not close to {:?}", actual, expected);
+    }
+
+    #[test]
+    fn rect_with_stroke() {
+        let (fill, stroke) =
+            bounds(r#"<svg><rect x="10" y="20" width="30" height="40" stroke-width="4"/></svg>"#);
+        assert_eq!(fill, new_box([10.0, 20.0], [40.0, 60.0]));
+        assert_eq!(stroke, new_box([8.0, 18.0], [42.0, 62.0]));
+    }
+
+    #[test]
+    fn unstroked_shape_has_the_same_boxes() {
+        let (fill, stroke) =
+            bounds(r#"<svg><rect width="30" height="40" stroke="none" stroke-width="4"/></svg>"#);
+        assert_eq!(fill, stroke);
+    }
+
+    #[test]
+    fn rotated_ellipse_is_tight() {
+        let (fill, _) =
+            bounds(r#"<svg><ellipse rx="2" ry="1" transform="rotate(90)" stroke="none"/></svg>"#);
+        assert_close(&fill, &new_box([-1.0, -2.0], [1.0, 2.0]));
+    }
+
+    #[test]
+    fn rotated_rounded_rect_is_tight() {
+        let (fill, _) = bounds(
+            r#"<svg><rect width="4" height="4" rx="2" transform="rotate(45)" stroke="none"/></svg>"#,
+        );
+        // A rect rounded all the way is a circle of radius 2 centered on (2, 2) before rotating.
+        let center_y = 2.0 * std::f32::consts::SQRT_2;
+        assert_close(
+            &fill,
+            &new_box([-2.0, center_y - 2.0], [2.0, center_y + 2.0]),
+        );
+    }
+
+    #[test]
+    fn polyline_miter_is_included_until_the_miter_limit() {
+        let (_, stroke) = bounds(
+            r#"<svg><polyline points="0,0 10,10 20,0" stroke-width="2" fill="none"/></svg>"#,
+        );
+        // The right angle join at (10, 10) reaches sqrt(2) past it.
+        assert_close(
+            &stroke,
+            &new_box(
+                [
+                    -std::f32::consts::FRAC_1_SQRT_2,
+                    -std::f32::consts::FRAC_1_SQRT_2,
+                ],
+                [
+                    20.0 + std::f32::consts::FRAC_1_SQRT_2,
+                    10.0 + std::f32::consts::SQRT_2,
+                ],
+            ),
+        );
+
+        let (_, beveled) = bounds(
+            r#"<svg><polyline points="0,0 10,10 20,0" stroke-width="2" stroke-miterlimit="1" fill="none"/></svg>"#,
+        );
+        assert_close(
+            &beveled,
+            &new_box(
+                [
+                    -std::f32::consts::FRAC_1_SQRT_2,
+                    -std::f32::consts::FRAC_1_SQRT_2,
+                ],
+                [
+                    20.0 + std::f32::consts::FRAC_1_SQRT_2,
+                    10.0 + std::f32::consts::FRAC_1_SQRT_2,
+                ],
+            ),
+        );
+    }
+
+    #[test]
+    fn groups_and_documents_cover_their_children() {
+        let svg = read_from_str(
+            r#"<svg width="10" height="10">
+  <g transform="translate(100 0)"><line x2="10" y2="10" stroke="none"/></g>
+  <rect x="-5" y="-5" width="1" height="1" stroke="none"/>
+</svg>"#,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        assert_eq!(
+            svg.get(&[0]).unwrap().fill_bounds(),
+            Some(new_box([100.0, 0.0], [110.0, 10.0]))
+        );
+        assert_eq!(
+            svg.fill_bounds(),
+            Some(new_box([-5.0, -5.0], [110.0, 10.0]))
+        );
+    }
+
+    #[test]
+    fn empty_document_has_no_bounds() {
+        let svg = read_from_str(r#"<svg><g></g></svg>"#, &ParseOptions::STRICT).unwrap();
+        assert_eq!(svg.fill_bounds(), None);
+        assert_eq!(svg.stroke_bounds(), None);
+    }
+}
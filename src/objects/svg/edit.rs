@@ -0,0 +1,427 @@
+use std::sync::Arc;
+
+use super::{Declarations, Element, ElementPath, EmptyTag, StartTag, Style, SVG};
+
+/// An edit made to a document, recorded so that renderers can update only what changed.
+///
+/// Paths refer to the document as it was right after the edit. Replaying the changes in order
+/// keeps a copy of the tree's layout in sync with the document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// An element was inserted. The siblings that followed it moved up by one index.
+    Inserted(ElementPath),
+    /// An element was removed. The siblings that followed it moved down by one index.
+    Removed(ElementPath),
+    /// The element or something in its subtree changed.
+    Modified(ElementPath),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EditError {
+    InvalidPath(ElementPath),
+    NotAContainer(ElementPath),
+    NotStyled(ElementPath),
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidPath(path) => write!(f, "There is no element at {:?}", path),
+            Self::NotAContainer(path) => {
+                write!(f, "The element at {:?} can not hold other elements", path)
+            }
+            Self::NotStyled(path) => write!(f, "The element at {:?} does not have a style", path),
+        }
+    }
+}
+
+type StyleParts<'a> = (&'a mut Arc<Style>, &'a mut Declarations);
+
+impl EmptyTag {
+    fn style_parts_mut(&mut self) -> StyleParts<'_> {
+        match self {
+            EmptyTag::Ellipse(ellipse) => (&mut ellipse.style, &mut ellipse.declarations),
+            EmptyTag::Image(image) => (&mut image.style, &mut image.declarations),
+            EmptyTag::Line(line) => (&mut line.style, &mut line.declarations),
+            EmptyTag::Point(point) => (&mut point.style, &mut point.declarations),
+            EmptyTag::Polygon(polygon) => (&mut polygon.style, &mut polygon.declarations),
+            EmptyTag::Polyline(polyline) => (&mut polyline.style, &mut polyline.declarations),
+            EmptyTag::Rect(rect) => (&mut rect.style, &mut rect.declarations),
+        }
+    }
+}
+
+impl Element {
+    fn children_mut(&mut self) -> Option<&mut Vec<Element>> {
+        match self {
+            Element::StartTag(StartTag::Group(group)) => Some(&mut group.elements),
+            Element::StartTag(StartTag::SVG(svg)) => Some(&mut svg.elements),
+            _ => None,
+        }
+    }
+
+    // Nested svg tags start over from the default style so they have no style to edit.
+    fn style_parts_mut(&mut self) -> Option<StyleParts<'_>> {
+        match self {
+            Element::EmptyTag(tag) => Some(tag.style_parts_mut()),
+            Element::StartTag(StartTag::Group(group)) => {
+                Some((&mut group.style, &mut group.declarations))
+            }
+            _ => None,
+        }
+    }
+
+    // Computes the style of this element and its subtree again on top of a new parent style.
+    fn inherit_style_change(&mut self, old_parent_style: &Style, new_parent_style: &Style) {
+        let Some((style, declarations)) = self.style_parts_mut() else {
+            return;
+        };
+
+        let new_style = match &declarations.0 {
+            Some(declarations) => Arc::new(declarations.apply(new_parent_style)),
+            None => {
+                let mut own_style = (**style).clone();
+                own_style.move_transform(old_parent_style, new_parent_style);
+                Arc::new(own_style)
+            }
+        };
+        let old_style = std::mem::replace(style, Arc::clone(&new_style));
+
+        if let Some(children) = self.children_mut() {
+            for child in children {
+                child.inherit_style_change(&old_style, &new_style);
+            }
+        }
+    }
+}
+
+impl Style {
+    // The element's own transform is whatever is left after undoing the parent's.
+    fn move_transform(&mut self, old_parent: &Style, new_parent: &Style) {
+        match old_parent.transform.inverse() {
+            Some(inverse) => {
+                self.transform = &new_parent.transform * &(&inverse * &self.transform);
+            }
+            None if self.transform == old_parent.transform => {
+                self.transform = new_parent.transform.clone();
+            }
+            None => (),
+        }
+    }
+}
+
+impl SVG {
+    fn elements_at_mut(&mut self, path: &[usize]) -> Option<&mut Vec<Element>> {
+        let mut elements = &mut self.elements;
+        for index in path {
+            elements = elements.get_mut(*index)?.children_mut()?;
+        }
+        Some(elements)
+    }
+
+    /// Inserts `element` so that it ends up at `path`.
+    ///
+    /// The element is inserted as is. Its style should already include what it inherits from its
+    /// new parent, see `Element::style`.
+    pub fn insert(&mut self, path: &[usize], element: Element) -> Result<(), EditError> {
+        let (index, parent) = path
+            .split_last()
+            .ok_or_else(|| EditError::InvalidPath(path.to_vec()))?;
+
+        if !parent.is_empty() && self.get(parent).is_none() {
+            return Err(EditError::InvalidPath(parent.to_vec()));
+        }
+
+        let siblings = self
+            .elements_at_mut(parent)
+            .ok_or_else(|| EditError::NotAContainer(parent.to_vec()))?;
+        if *index > siblings.len() {
+            return Err(EditError::InvalidPath(path.to_vec()));
+        }
+
+        siblings.insert(*index, element);
+        self.changes.push(Change::Inserted(path.to_vec()));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, path: &[usize]) -> Result<Element, EditError> {
+        let element = path
+            .split_last()
+            .and_then(|(index, parent)| {
+                let siblings = self.elements_at_mut(parent)?;
+                (*index < siblings.len()).then(|| siblings.remove(*index))
+            })
+            .ok_or_else(|| EditError::InvalidPath(path.to_vec()))?;
+
+        self.changes.push(Change::Removed(path.to_vec()));
+        Ok(element)
+    }
+
+    /// Hands the element at `path` to `edit` and records it as modified.
+    ///
+    /// Style changes made here are not passed on to the element's children, use `SVG::set_style`
+    /// for that. An element whose style was changed drops its declarations, so it keeps the new
+    /// style when its ancestors' styles change.
+    pub fn modify(
+        &mut self,
+        path: &[usize],
+        edit: impl FnOnce(&mut Element),
+    ) -> Result<(), EditError> {
+        let element = path
+            .split_last()
+            .and_then(|(index, parent)| self.elements_at_mut(parent)?.get_mut(*index))
+            .ok_or_else(|| EditError::InvalidPath(path.to_vec()))?;
+
+        let old_style = element
+            .style_parts_mut()
+            .map(|(style, _)| Arc::clone(style));
+        edit(element);
+        if let (Some(old_style), Some((style, declarations))) =
+            (old_style, element.style_parts_mut())
+        {
+            if *old_style != **style {
+                *declarations = Declarations::default();
+            }
+        }
+        self.changes.push(Change::Modified(path.to_vec()));
+        Ok(())
+    }
+
+    /// Replaces the computed style of the element at `path`.
+    ///
+    /// The element's subtree computes its style again from its own declarations on top of the new
+    /// style. Elements without declarations keep their style and only move along with the
+    /// element's transform.
+    pub fn set_style(&mut self, path: &[usize], new_style: Style) -> Result<(), EditError> {
+        let mut result = Ok(());
+
+        self.modify(path, |element| {
+            let Some((style, declarations)) = element.style_parts_mut() else {
+                result = Err(EditError::NotStyled(path.to_vec()));
+                return;
+            };
+
+            *declarations = Declarations::default();
+            let old_style = std::mem::replace(style, Arc::new(new_style.clone()));
+
+            if let Some(children) = element.children_mut() {
+                for child in children {
+                    child.inherit_style_change(&old_style, &new_style);
+                }
+            }
+        })?;
+
+        if result.is_err() {
+            self.changes.pop();
+        }
+        result
+    }
+
+    /// Returns the changes made since the last call, oldest first.
+    pub fn take_changes(&mut self) -> Vec<Change> {
+        std::mem::take(&mut self.changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+
+    use super::*;
+    use crate::objects::svg::{read_from_str, Paint, ParseOptions, Point};
+
+    const DOCUMENT: &str = r##"<svg>
+  <g id="gauge" fill="#ff0000" transform="translate(10)">
+    <rect id="needle" width="1" height="1"/>
+    <rect id="face" width="1" height="1" fill="#0000ff"/>
+  </g>
+  <line id="divider"/>
+</svg>"##;
+
+    fn read_document() -> SVG {
+        read_from_str(DOCUMENT, &ParseOptions::STRICT).unwrap()
+    }
+
+    fn new_point(x: f32) -> Element {
+        Element::EmptyTag(EmptyTag::Point(Point {
+            info: Default::default(),
+            style: Arc::new(Style::DEFAULT),
+            declarations: Default::default(),
+            position: [x, 0.0].into(),
+        }))
+    }
+
+    fn style_of<'a>(svg: &'a SVG, id: &str) -> &'a Style {
+        svg.find_by_id(id).unwrap().1.style().unwrap()
+    }
+
+    #[test]
+    fn parsed_document_has_no_changes() {
+        assert_eq!(read_document().take_changes(), vec![]);
+    }
+
+    #[test]
+    fn insert_and_remove_elements() {
+        let mut svg = read_document();
+
+        svg.insert(&[0, 1], new_point(1.0)).unwrap();
+        svg.insert(&[2], new_point(2.0)).unwrap();
+        assert_eq!(svg.get(&[0, 1]), Some(&new_point(1.0)));
+        assert_eq!(svg.get(&[2]), Some(&new_point(2.0)));
+        assert_eq!(svg.find_by_id("face").unwrap().0, vec![0, 2]);
+
+        assert_eq!(svg.remove(&[0, 1]), Ok(new_point(1.0)));
+        assert_eq!(svg.find_by_id("face").unwrap().0, vec![0, 1]);
+
+        assert_eq!(
+            svg.take_changes(),
+            vec![
+                Change::Inserted(vec![0, 1]),
+                Change::Inserted(vec![2]),
+                Change::Removed(vec![0, 1]),
+            ]
+        );
+        assert_eq!(svg.take_changes(), vec![]);
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected_and_not_recorded() {
+        let mut svg = read_document();
+
+        assert_eq!(
+            svg.insert(&[0, 3], new_point(0.0)),
+            Err(EditError::InvalidPath(vec![0, 3]))
+        );
+        assert_eq!(
+            svg.insert(&[1, 0], new_point(0.0)),
+            Err(EditError::NotAContainer(vec![1]))
+        );
+        assert_eq!(
+            svg.insert(&[5, 0], new_point(0.0)),
+            Err(EditError::InvalidPath(vec![5]))
+        );
+        assert!(svg.remove(&[2]).is_err());
+        assert!(svg.modify(&[0, 7], |_| ()).is_err());
+
+        assert_eq!(svg.take_changes(), vec![]);
+    }
+
+    #[test]
+    fn modify_records_the_element() {
+        let mut svg = read_document();
+
+        svg.modify(&[1], |element| {
+            if let Element::EmptyTag(EmptyTag::Line(line)) = element {
+                line.to = [3.0, 4.0].into();
+            }
+        })
+        .unwrap();
+
+        assert_eq!(svg.take_changes(), vec![Change::Modified(vec![1])]);
+    }
+
+    #[test]
+    fn set_style_updates_what_children_inherit() {
+        let mut svg = read_document();
+
+        let mut style = style_of(&svg, "gauge").clone();
+        style.fill = Paint::Color(Color::RGB(0, 255, 0));
+        style.transform[0][2] = 20.0;
+        style.transform[1][2] = 5.0;
+        svg.set_style(&[0], style).unwrap();
+
+        let needle = style_of(&svg, "needle");
+        assert_eq!(needle.fill, Paint::Color(Color::RGB(0, 255, 0)));
+        assert_eq!(needle.transform[0][2], 20.0);
+        assert_eq!(needle.transform[1][2], 5.0);
+
+        // The face sets its own fill so it keeps it.
+        let face = style_of(&svg, "face");
+        assert_eq!(face.fill, Paint::Color(Color::RGB(0, 0, 255)));
+        assert_eq!(face.transform[0][2], 20.0);
+
+        assert_eq!(svg.take_changes(), vec![Change::Modified(vec![0])]);
+    }
+
+    #[test]
+    fn set_style_keeps_explicit_values_that_match_the_parent() {
+        let mut svg = read_from_str(
+            r##"<svg><g fill="#ff0000">
+  <rect id="explicit" width="1" height="1" fill="#ff0000"/>
+  <rect id="inherited" width="1" height="1"/>
+</g></svg>"##,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        let mut style = svg.get(&[0]).unwrap().style().unwrap().clone();
+        style.fill = Paint::Color(Color::RGB(0, 255, 0));
+        svg.set_style(&[0], style).unwrap();
+
+        assert_eq!(
+            style_of(&svg, "explicit").fill,
+            Paint::Color(Color::RGB(255, 0, 0))
+        );
+        assert_eq!(
+            style_of(&svg, "inherited").fill,
+            Paint::Color(Color::RGB(0, 255, 0))
+        );
+    }
+
+    #[test]
+    fn styles_set_by_hand_are_kept() {
+        let mut svg = read_document();
+        svg.insert(&[0, 0], new_point(1.0)).unwrap();
+        svg.modify(&[0, 1], |element| {
+            if let Element::EmptyTag(EmptyTag::Rect(rect)) = element {
+                Arc::make_mut(&mut rect.style).stroke = Paint::Color(Color::RGB(0, 0, 255));
+            }
+        })
+        .unwrap();
+
+        let mut style = style_of(&svg, "gauge").clone();
+        style.fill = Paint::Color(Color::RGB(0, 255, 0));
+        style.stroke = Paint::Color(Color::RGB(0, 255, 0));
+        svg.set_style(&[0], style).unwrap();
+
+        let point = svg.get(&[0, 0]).unwrap().style().unwrap();
+        assert_eq!(point.fill, Style::DEFAULT.fill);
+        assert_eq!(point.transform, Style::DEFAULT.transform);
+
+        // The needle's style was edited, so it no longer follows the gauge.
+        let needle = style_of(&svg, "needle");
+        assert_eq!(needle.fill, Paint::Color(Color::RGB(255, 0, 0)));
+        assert_eq!(needle.stroke, Paint::Color(Color::RGB(0, 0, 255)));
+    }
+
+    #[test]
+    fn set_style_keeps_local_transforms() {
+        let mut svg = read_from_str(
+            r#"<svg><g transform="scale(2)"><rect transform="translate(1)" width="1" height="1"/></g></svg>"#,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        let mut style = Style::DEFAULT;
+        style.transform[0][0] = 4.0;
+        style.transform[1][1] = 4.0;
+        svg.set_style(&[0], style).unwrap();
+
+        let rect = svg.get(&[0, 0]).unwrap().style().unwrap();
+        assert_eq!(
+            rect.transform,
+            [[4.0, 0.0, 4.0], [0.0, 4.0, 0.0], [0.0, 0.0, 1.0]].into()
+        );
+    }
+
+    #[test]
+    fn nested_svg_has_no_style_to_set() {
+        let mut svg = read_from_str(r#"<svg><svg></svg></svg>"#, &ParseOptions::LENIENT).unwrap();
+
+        assert_eq!(
+            svg.set_style(&[0], Style::DEFAULT),
+            Err(EditError::NotStyled(vec![0]))
+        );
+        assert_eq!(svg.take_changes(), vec![]);
+    }
+}
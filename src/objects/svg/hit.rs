@@ -0,0 +1,360 @@
+use crate::vector::Vector2D;
+
+use super::{
+    outer_join, Element, ElementPath, Ellipse, EmptyTag, FillRule, Paint, PointerEvents, Rect,
+    Style, SVG,
+};
+
+impl PointerEvents {
+    // `painted` says whether the fill has a paint. Images always paint their whole area.
+    fn targets_fill(self, painted: bool) -> bool {
+        match self {
+            Self::VisiblePainted | Self::Painted => painted,
+            Self::VisibleFill | Self::Fill | Self::Visible | Self::All => true,
+            Self::VisibleStroke | Self::Stroke | Self::None => false,
+        }
+    }
+
+    fn targets_stroke(self, painted: bool) -> bool {
+        match self {
+            Self::VisiblePainted | Self::Painted => painted,
+            Self::VisibleStroke | Self::Stroke | Self::Visible | Self::All => true,
+            Self::VisibleFill | Self::Fill | Self::None => false,
+        }
+    }
+}
+
+impl SVG {
+    /// Returns the shapes whose fill or stroke contains `point`, topmost first.
+    ///
+    /// `point` is in the coordinates of the document. Groups are never returned themselves, use
+    /// `SVG::ancestors` to find the groups a shape belongs to.
+    pub fn hit_test(&self, point: &Vector2D<f32>) -> Vec<ElementPath> {
+        let mut hits: Vec<ElementPath> = self
+            .iter()
+            .filter_map(|(path, element)| match element {
+                Element::EmptyTag(tag) if tag.is_hit(point) => Some(path),
+                _ => None,
+            })
+            .collect();
+
+        // Later elements are drawn over earlier ones.
+        hits.reverse();
+        hits
+    }
+}
+
+impl EmptyTag {
+    fn is_hit(&self, point: &Vector2D<f32>) -> bool {
+        let style = self.style();
+        let pointer_events = style.pointer_events;
+        let targets_fill = pointer_events.targets_fill(style.fill != Paint::None);
+        let targets_stroke =
+            pointer_events.targets_stroke(style.stroke != Paint::None) && style.stroke_width > 0.0;
+
+        // Shapes are tested in their own coordinates where the stroke has the same width
+        // everywhere. A singular transform flattens the shape so there is nothing to hit.
+        let Some(inverse) = style.transform.inverse() else {
+            return false;
+        };
+        let point = Vector2D::from([
+            inverse[0][0] * point[0] + inverse[0][1] * point[1] + inverse[0][2],
+            inverse[1][0] * point[0] + inverse[1][1] * point[1] + inverse[1][2],
+        ]);
+        let half_stroke = style.stroke_width * 0.5;
+
+        match self {
+            EmptyTag::Ellipse(ellipse) => {
+                if ellipse.radius[0] <= 0.0 || ellipse.radius[1] <= 0.0 {
+                    return false;
+                }
+
+                (targets_fill && ellipse_contains(ellipse, 0.0, &point))
+                    || (targets_stroke
+                        && ellipse_contains(ellipse, half_stroke, &point)
+                        && !ellipse_contains(ellipse, -half_stroke, &point))
+            }
+            EmptyTag::Image(image) => {
+                pointer_events.targets_fill(true)
+                    && point[0] >= image.x
+                    && point[0] <= image.x + image.width
+                    && point[1] >= image.y
+                    && point[1] <= image.y + image.height
+            }
+            EmptyTag::Line(line) => {
+                targets_stroke && segment_contains(&line.from, &line.to, half_stroke, &point)
+            }
+            // Points have no area, they are only there to be seen.
+            EmptyTag::Point(..) => false,
+            EmptyTag::Polygon(polygon) => {
+                (targets_fill && fill_contains(&polygon.points, style.fill_rule, &point))
+                    || (targets_stroke && stroke_contains(&polygon.points, true, style, &point))
+            }
+            EmptyTag::Polyline(polyline) => {
+                (targets_fill && fill_contains(&polyline.points, style.fill_rule, &point))
+                    || (targets_stroke && stroke_contains(&polyline.points, false, style, &point))
+            }
+            EmptyTag::Rect(rect) => {
+                (targets_fill && rect_contains(rect, 0.0, &point))
+                    || (targets_stroke
+                        && rect_contains(rect, half_stroke, &point)
+                        && !rect_contains(rect, -half_stroke, &point))
+            }
+        }
+    }
+}
+
+// The stroke of an ellipse is taken to run between the ellipses with radii grown and shrunk by
+// half the stroke width. This is exact for circles and close for anything that is not too flat.
+fn ellipse_contains(ellipse: &Ellipse, grow: f32, point: &Vector2D<f32>) -> bool {
+    let rx = ellipse.radius[0] + grow;
+    let ry = ellipse.radius[1] + grow;
+    if rx <= 0.0 || ry <= 0.0 {
+        return false;
+    }
+
+    let dx = (point[0] - ellipse.center[0]) / rx;
+    let dy = (point[1] - ellipse.center[1]) / ry;
+    dx * dx + dy * dy <= 1.0
+}
+
+// Grows the rect on every side, keeping the centers of its rounded corners in place.
+fn rect_contains(rect: &Rect, grow: f32, point: &Vector2D<f32>) -> bool {
+    let half_width = rect.width * 0.5 + grow;
+    let half_height = rect.height * 0.5 + grow;
+    if half_width < 0.0 || half_height < 0.0 {
+        return false;
+    }
+
+    let dx = (point[0] - rect.x - rect.width * 0.5).abs();
+    let dy = (point[1] - rect.y - rect.height * 0.5).abs();
+    if dx > half_width || dy > half_height {
+        return false;
+    }
+
+    // Square corners are stroked with square miters, and shrinking a rounded corner past its
+    // radius leaves a square one.
+    let (rx, ry) = rect.corner_radii();
+    if rx == 0.0 {
+        return true;
+    }
+
+    let (rx, ry) = (rx + grow, ry + grow);
+    if rx <= 0.0 || ry <= 0.0 {
+        return true;
+    }
+
+    // How far into the corner ellipse the point is.
+    let qx = dx - (half_width - rx);
+    let qy = dy - (half_height - ry);
+    qx <= 0.0 || qy <= 0.0 || (qx / rx).powi(2) + (qy / ry).powi(2) <= 1.0
+}
+
+// Counts how many times the closed outline through `points` winds around `point`.
+fn winding_number(points: &[Vector2D<f32>], point: &Vector2D<f32>) -> i32 {
+    let mut winding = 0;
+
+    for (i, from) in points.iter().enumerate() {
+        let to = &points[(i + 1) % points.len()];
+        let side = (to - from).cross(&(point - from));
+
+        if from[1] <= point[1] {
+            if to[1] > point[1] && side > 0.0 {
+                winding += 1;
+            }
+        } else if to[1] <= point[1] && side < 0.0 {
+            winding -= 1;
+        }
+    }
+
+    winding
+}
+
+fn fill_contains(points: &[Vector2D<f32>], fill_rule: FillRule, point: &Vector2D<f32>) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+
+    let winding = winding_number(points, point);
+    match fill_rule {
+        FillRule::NonZero => winding != 0,
+        FillRule::EvenOdd => winding % 2 != 0,
+    }
+}
+
+// Segments end with butt caps.
+fn segment_contains(
+    from: &Vector2D<f32>,
+    to: &Vector2D<f32>,
+    half_stroke: f32,
+    point: &Vector2D<f32>,
+) -> bool {
+    let direction = to - from;
+    let length = direction.get_norm();
+    if length == 0.0 {
+        return false;
+    }
+
+    let offset = point - from;
+    let along = direction.dot(&offset) / length;
+    let across = direction.cross(&offset).abs() / length;
+    (0.0..=length).contains(&along) && across <= half_stroke
+}
+
+fn join_contains(
+    previous: &Vector2D<f32>,
+    joint: &Vector2D<f32>,
+    next: &Vector2D<f32>,
+    style: &Style,
+    point: &Vector2D<f32>,
+) -> bool {
+    let Some(join) = outer_join(
+        previous,
+        joint,
+        next,
+        style.stroke_width * 0.5,
+        style.miter_limit,
+    ) else {
+        return false;
+    };
+
+    let [incoming_edge, outgoing_edge] = join.edges;
+    let mut corner = vec![joint.clone(), incoming_edge];
+    corner.extend(join.miter_tip);
+    corner.push(outgoing_edge);
+
+    winding_number(&corner, point) != 0
+}
+
+fn stroke_contains(
+    points: &[Vector2D<f32>],
+    is_closed: bool,
+    style: &Style,
+    point: &Vector2D<f32>,
+) -> bool {
+    let half_stroke = style.stroke_width * 0.5;
+
+    let in_segment = points
+        .windows(2)
+        .any(|segment| segment_contains(&segment[0], &segment[1], half_stroke, point));
+    let in_join = points
+        .windows(3)
+        .any(|join| join_contains(&join[0], &join[1], &join[2], style, point));
+    if in_segment || in_join {
+        return true;
+    }
+
+    if !is_closed || points.len() < 3 {
+        return false;
+    }
+
+    let first = &points[0];
+    let second = &points[1];
+    let last = &points[points.len() - 1];
+    let second_last = &points[points.len() - 2];
+
+    segment_contains(last, first, half_stroke, point)
+        || join_contains(second_last, last, first, style, point)
+        || join_contains(last, first, second, style, point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::svg::{read_from_str, ParseOptions};
+
+    fn hits(contents: &str, x: f32, y: f32) -> Vec<ElementPath> {
+        let svg = read_from_str(contents, &ParseOptions::STRICT).unwrap();
+        svg.hit_test(&[x, y].into())
+    }
+
+    #[test]
+    fn overlapping_shapes_are_returned_topmost_first() {
+        let document = r#"<svg>
+  <rect width="10" height="10"/>
+  <g><ellipse cx="5" cy="5" rx="2" ry="2"/></g>
+  <rect x="20" width="10" height="10"/>
+</svg>"#;
+
+        assert_eq!(hits(document, 5.0, 5.0), vec![vec![1, 0], vec![0]]);
+        assert_eq!(hits(document, 1.0, 1.0), vec![vec![0]]);
+        assert_eq!(hits(document, 15.0, 5.0), Vec::<ElementPath>::new());
+    }
+
+    #[test]
+    fn transforms_are_applied() {
+        let document = r#"<svg><g transform="translate(100 0)"><rect width="10" height="10" transform="scale(2)" stroke="none"/></g></svg>"#;
+
+        assert_eq!(hits(document, 115.0, 15.0), vec![vec![0, 0]]);
+        assert!(hits(document, 5.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn stroke_reaches_half_its_width_outside_the_fill() {
+        let document = r#"<svg><rect width="10" height="10" stroke-width="4" fill="none"/></svg>"#;
+
+        assert_eq!(hits(document, -1.5, 5.0), vec![vec![0]]);
+        assert_eq!(hits(document, 1.5, 5.0), vec![vec![0]]);
+        assert!(hits(document, -2.5, 5.0).is_empty());
+        // The fill has no paint so the middle of the rect is not part of it.
+        assert!(hits(document, 5.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn rounded_corners_are_cut_off() {
+        let document = r#"<svg><rect width="10" height="10" rx="5" stroke="none"/></svg>"#;
+
+        assert!(hits(document, 0.5, 0.5).is_empty());
+        assert_eq!(hits(document, 5.0, 0.5), vec![vec![0]]);
+    }
+
+    #[test]
+    fn fill_rule_decides_if_overlaps_are_inside() {
+        // Two loops around the middle of the star.
+        let star = "50,0 21,90 98,35 2,35 79,90";
+        let nonzero = format!(r#"<svg><polygon points="{}" stroke="none"/></svg>"#, star);
+        let evenodd = format!(
+            r#"<svg><polygon points="{}" stroke="none" fill-rule="evenodd"/></svg>"#,
+            star
+        );
+
+        assert_eq!(hits(&nonzero, 50.0, 50.0), vec![vec![0]]);
+        assert!(hits(&evenodd, 50.0, 50.0).is_empty());
+        assert_eq!(hits(&evenodd, 50.0, 10.0), vec![vec![0]]);
+    }
+
+    #[test]
+    fn pointer_events_choose_what_can_be_hit() {
+        let rect = |pointer_events: &str| {
+            format!(
+                r#"<svg><rect width="10" height="10" fill="none" stroke="none" stroke-width="2" pointer-events="{}"/></svg>"#,
+                pointer_events
+            )
+        };
+
+        assert!(hits(&rect("visiblePainted"), 5.0, 5.0).is_empty());
+        assert_eq!(hits(&rect("fill"), 5.0, 5.0), vec![vec![0]]);
+        assert_eq!(hits(&rect("fill"), 0.0, 5.0), vec![vec![0]]);
+        assert!(hits(&rect("stroke"), 5.0, 5.0).is_empty());
+        assert_eq!(hits(&rect("stroke"), -0.5, 5.0), vec![vec![0]]);
+        assert_eq!(hits(&rect("all"), -0.5, 5.0), vec![vec![0]]);
+
+        let none = r#"<svg><rect width="10" height="10" pointer-events="none"/></svg>"#;
+        assert!(hits(none, 5.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn polyline_joins_respect_the_miter_limit() {
+        let polyline = |miter_limit: f32| {
+            format!(
+                r#"<svg><polyline points="0,0 10,10 20,0" stroke-width="2" fill="none" stroke-miterlimit="{}"/></svg>"#,
+                miter_limit
+            )
+        };
+        // Just below the join, inside the miter but outside both segments.
+        let (x, y) = (10.0, 10.0 + std::f32::consts::SQRT_2 * 0.9);
+
+        assert_eq!(hits(&polyline(4.0), x, y), vec![vec![0]]);
+        assert!(hits(&polyline(1.0), x, y).is_empty());
+    }
+}
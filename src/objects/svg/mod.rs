@@ -0,0 +1,2855 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    io::BufRead,
+    num::ParseFloatError,
+    ops::{Deref, DerefMut},
+    path::Path,
+    str::FromStr,
+    string::FromUtf8Error,
+    sync::Arc,
+};
+
+use once_cell::sync;
+use quick_xml::{
+    events::{BytesEnd, BytesStart, Event},
+    name::{Namespace, QName, ResolveResult},
+    NsReader, Writer,
+};
+
+use regex::Regex;
+
+use crate::{
+    color::Color,
+    matrix::Matrix3x3,
+    texture::{self, Texture},
+    vector::Vector2D,
+};
+
+mod bounds;
+mod edit;
+mod hit;
+mod query;
+mod visit;
+mod write;
+
+pub use bounds::BoundingBox;
+pub use edit::{Change, EditError};
+pub use query::{ElementPath, Elements};
+pub use visit::{VisitContext, Visitor};
+pub use write::WriteOptions;
+
+pub type Transform = Matrix3x3<f32>;
+
+#[derive(Debug)]
+pub enum ReadError {
+    ImageDecodeError(texture::DecodeError),
+    EndTagBeforeStart,
+    FromUtf8Error(FromUtf8Error),
+    InvalidAttribute { name: String, value: String },
+    LimitExceeded(Limit),
+    MissingSVGTag,
+    ParseFloatError(ParseFloatError),
+    UnrecognizedTag(String),
+    XMLError(quick_xml::errors::Error),
+}
+
+/// Controls how the reader reacts to parts of a document that do not conform to the SVG spec.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParseMode {
+    /// Any invalid attribute, unknown SVG tag or mismatched end tag is an error.
+    Strict,
+    /// Behaves like a browser: invalid attributes and unknown tags are ignored and mismatched
+    /// end tags close the closest matching open tag.
+    Lenient,
+}
+
+/// One of the resource limits a document can run into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit {
+    DocumentBytes,
+    Elements,
+    Depth,
+    Vertices,
+    ImageDimension,
+    ImageBytes,
+}
+
+/// Caps on the resources a document can make the reader use, for documents that are not trusted.
+///
+/// Going over a limit always fails the read, whatever the parse mode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limits {
+    /// Size of the document's markup.
+    pub max_document_bytes: usize,
+    /// Elements in the tree, including groups and svg tags.
+    pub max_elements: usize,
+    /// How deeply groups and svg tags nest, counting the outermost svg tag.
+    pub max_depth: usize,
+    /// Vertices listed by polygons and polylines, summed over the whole document. The other
+    /// shapes use a bounded number of vertices each so they are held in check by `max_elements`.
+    /// The OpenGL renderer also holds the vertices it generates to draw a document to it.
+    pub max_vertices: usize,
+    /// Width and height of each image in pixels.
+    pub max_image_dimension: u32,
+    /// Size of each image, both encoded and decoded.
+    pub max_image_bytes: usize,
+}
+
+impl Limits {
+    pub const NONE: Self = Self {
+        max_document_bytes: usize::MAX,
+        max_elements: usize::MAX,
+        max_depth: usize::MAX,
+        max_vertices: usize::MAX,
+        max_image_dimension: u32::MAX,
+        max_image_bytes: usize::MAX,
+    };
+
+    /// Leaves room for large illustrations while keeping a single document from using more than
+    /// a few hundred megabytes.
+    pub const UNTRUSTED: Self = Self {
+        max_document_bytes: 64 * 1024 * 1024,
+        max_elements: 200_000,
+        max_depth: 64,
+        max_vertices: 2_000_000,
+        max_image_dimension: 8192,
+        max_image_bytes: 64 * 1024 * 1024,
+    };
+
+    fn image_limits(&self) -> texture::DecodeLimits {
+        texture::DecodeLimits {
+            max_dimension: self.max_image_dimension,
+            max_bytes: self.max_image_bytes,
+        }
+    }
+
+    fn check(limit: Limit, value: usize, max: usize) -> Result<(), ReadError> {
+        if value > max {
+            return Err(ReadError::LimitExceeded(limit));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    pub limits: Limits,
+}
+
+impl ParseOptions {
+    pub const STRICT: Self = Self {
+        mode: ParseMode::Strict,
+        limits: Limits::NONE,
+    };
+
+    pub const LENIENT: Self = Self {
+        mode: ParseMode::Lenient,
+        limits: Limits::NONE,
+    };
+
+    /// Returns the error back in strict mode and swallows it in lenient mode.
+    fn recover(&self, err: ReadError) -> Result<(), ReadError> {
+        match self.mode {
+            ParseMode::Strict => Err(err),
+            ParseMode::Lenient => Ok(()),
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::LENIENT
+    }
+}
+
+#[derive(Debug)]
+enum EventStatus {
+    Eof,
+    Error(ReadError),
+    SkippedTag,
+    UnrecognizedTag(String),
+}
+
+impl From<ReadError> for EventStatus {
+    fn from(value: ReadError) -> Self {
+        Self::Error(value)
+    }
+}
+
+impl From<quick_xml::errors::Error> for EventStatus {
+    fn from(value: quick_xml::errors::Error) -> Self {
+        Self::Error(ReadError::XMLError(value))
+    }
+}
+
+impl From<quick_xml::errors::Error> for ReadError {
+    fn from(value: quick_xml::errors::Error) -> Self {
+        Self::XMLError(value)
+    }
+}
+
+impl From<quick_xml::events::attributes::AttrError> for ReadError {
+    fn from(value: quick_xml::events::attributes::AttrError) -> Self {
+        Self::XMLError(value.into())
+    }
+}
+
+impl From<FromUtf8Error> for EventStatus {
+    fn from(value: FromUtf8Error) -> Self {
+        Self::Error(ReadError::FromUtf8Error(value))
+    }
+}
+
+impl From<ParseFloatError> for ReadError {
+    fn from(value: ParseFloatError) -> Self {
+        Self::ParseFloatError(value)
+    }
+}
+
+impl From<texture::DecodeError> for ReadError {
+    fn from(value: texture::DecodeError) -> Self {
+        Self::ImageDecodeError(value)
+    }
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ImageDecodeError(err) => write!(f, "Could not decode image tag: {}", err),
+            Self::EndTagBeforeStart => write!(
+                f,
+                "An end tag was found before it's corresponding start tag"
+            ),
+            Self::FromUtf8Error(err) => write!(f, "Could not convert to UTF-8: {}", err),
+            Self::InvalidAttribute { name, value } => {
+                write!(f, "Invalid value for attribute {}: \"{}\"", name, value)
+            }
+            Self::LimitExceeded(limit) => {
+                let description = match limit {
+                    Limit::DocumentBytes => "size of the document",
+                    Limit::Elements => "number of elements",
+                    Limit::Depth => "nesting depth",
+                    Limit::Vertices => "number of vertices",
+                    Limit::ImageDimension => "dimensions of an image",
+                    Limit::ImageBytes => "size of an image",
+                };
+                write!(f, "Document goes over the limit on the {}", description)
+            }
+            Self::MissingSVGTag => write!(f, "Could not find an svg tag at the top level"),
+            Self::ParseFloatError(err) => write!(f, "Could not parse float: {}", err),
+            Self::UnrecognizedTag(tag) => write!(f, "Unrecognized tag: {}", tag),
+            Self::XMLError(err) => write!(f, "XML Error: {}", err),
+        }
+    }
+}
+
+impl std::fmt::Display for EventStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Eof => write!(f, "Reached end of file"),
+            Self::Error(err) => err.fmt(f),
+            Self::SkippedTag => write!(f, "Tag was not read (skipped)"),
+            Self::UnrecognizedTag(err) => write!(f, "Unrecognized tag: {}", err),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Element {
+    EmptyTag(EmptyTag),
+    EndTag(EndTag),
+    StartTag(StartTag),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EmptyTag {
+    Ellipse(Ellipse),
+    Image(Image),
+    Line(Line),
+    Point(Point),
+    Polygon(Polygon),
+    Polyline(Polyline),
+    Rect(Rect),
+}
+
+impl EmptyTag {
+    fn info_mut(&mut self) -> &mut ElementInfo {
+        match self {
+            EmptyTag::Ellipse(ellipse) => &mut ellipse.info,
+            EmptyTag::Image(image) => &mut image.info,
+            EmptyTag::Line(line) => &mut line.info,
+            EmptyTag::Point(point) => &mut point.info,
+            EmptyTag::Polygon(polygon) => &mut polygon.info,
+            EmptyTag::Polyline(polyline) => &mut polyline.info,
+            EmptyTag::Rect(rect) => &mut rect.info,
+        }
+    }
+
+    fn from_empty_tag_bytes(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<EmptyTag, EventStatus> {
+        match bytes.local_name().into_inner() {
+            b"point" => Ok(EmptyTag::Point(Point::from_bytes_start(
+                bytes,
+                parent_style,
+                styles,
+                options,
+            )?)),
+            b"line" => Ok(EmptyTag::Line(Line::from_bytes_start(
+                bytes,
+                parent_style,
+                styles,
+                options,
+            )?)),
+            b"polyline" => Ok(EmptyTag::Polyline(Polyline::from_bytes_start(
+                bytes,
+                parent_style,
+                styles,
+                options,
+            )?)),
+            b"rect" => Ok(Rect::from_bytes_start(
+                bytes,
+                parent_style,
+                styles,
+                options,
+            )?),
+            b"polygon" => Ok(EmptyTag::Polygon(Polygon::from_bytes_start(
+                bytes,
+                parent_style,
+                styles,
+                options,
+            )?)),
+            b"ellipse" | b"circle" => Ok(EmptyTag::Ellipse(Ellipse::from_bytes_start(
+                bytes,
+                parent_style,
+                styles,
+                options,
+            )?)),
+            b"image" => Ok(EmptyTag::Image(Image::from_bytes_start(
+                bytes,
+                parent_style,
+                styles,
+                options,
+            )?)),
+            unrecognized => Err(EventStatus::UnrecognizedTag(String::from_utf8(
+                unrecognized.to_owned(),
+            )?)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EndTag {
+    Group,
+    SVG,
+}
+
+impl EndTag {
+    fn from_end_tag_bytes(bytes: BytesEnd) -> Result<EndTag, EventStatus> {
+        match bytes.local_name().into_inner() {
+            b"g" => Ok(EndTag::Group),
+            b"svg" => Ok(EndTag::SVG),
+            unrecognized => Err(EventStatus::UnrecognizedTag(String::from_utf8(
+                unrecognized.to_owned(),
+            )?)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StartTag {
+    Group(Group),
+    SVG(SVG),
+}
+
+impl StartTag {
+    fn info_mut(&mut self) -> &mut ElementInfo {
+        match self {
+            StartTag::Group(group) => &mut group.info,
+            StartTag::SVG(svg) => &mut svg.info,
+        }
+    }
+
+    fn get_expected_end_tag(&self) -> EndTag {
+        match self {
+            StartTag::Group(..) => EndTag::Group,
+            StartTag::SVG(..) => EndTag::SVG,
+        }
+    }
+
+    fn add_element(&mut self, element: Element) {
+        match self {
+            StartTag::Group(group) => group.elements.push(element),
+            StartTag::SVG(svg) => svg.elements.push(element),
+        }
+    }
+
+    fn from_start_tag_bytes(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<(Self, Arc<Style>), EventStatus> {
+        match bytes.local_name().into_inner() {
+            b"g" => {
+                let group = Group::from_bytes_start(bytes, parent_style, styles, options)?;
+                let style = group.style.clone();
+                Ok((StartTag::Group(group), style))
+            }
+            b"svg" => Ok((
+                StartTag::SVG(SVG::from_bytes_start(bytes, options)?),
+                styles.default_style(),
+            )),
+            unrecognized => Err(EventStatus::UnrecognizedTag(String::from_utf8(
+                unrecognized.to_owned(),
+            )?)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Attribute<'a> {
+    pub key: &'a [u8],
+    value: Cow<'a, str>,
+}
+
+impl<'a> Attribute<'a> {
+    fn parse(attribute: quick_xml::events::attributes::Attribute<'a>) -> Result<Self, ReadError> {
+        Ok(Attribute {
+            key: attribute.key.local_name().into_inner(),
+            value: attribute.unescape_value()?,
+        })
+    }
+
+    fn invalid(&self) -> ReadError {
+        ReadError::InvalidAttribute {
+            name: String::from_utf8_lossy(self.key).into_owned(),
+            value: self.value.clone().into_owned(),
+        }
+    }
+
+    fn color(&self) -> Result<Color, ReadError> {
+        self.parse_color(self.value.trim())
+    }
+
+    fn parse_color(&self, value: &str) -> Result<Color, ReadError> {
+        Color::from_css(value).ok_or_else(|| self.invalid())
+    }
+
+    fn paint(&self) -> Result<Paint, ReadError> {
+        let value = self.value.trim();
+
+        let Some(reference) = value.strip_prefix("url(") else {
+            return self.plain_paint(value);
+        };
+
+        let (id, fallback) = reference.split_once(')').ok_or_else(|| self.invalid())?;
+        let id = id.trim().strip_prefix('#').ok_or_else(|| self.invalid())?;
+        if id.is_empty() {
+            return Err(self.invalid());
+        }
+
+        let fallback = match fallback.trim() {
+            "" => None,
+            fallback => Some(Box::new(self.plain_paint(fallback)?)),
+        };
+
+        Ok(Paint::Server(id.to_owned(), fallback))
+    }
+
+    // A paint that does not refer to a paint server. These are the only paints allowed as the
+    // fallback of a paint server.
+    fn plain_paint(&self, value: &str) -> Result<Paint, ReadError> {
+        match value {
+            "none" | "" => Ok(Paint::None),
+            "currentColor" => Ok(Paint::CurrentColor),
+            color => Ok(Paint::Color(self.parse_color(color)?)),
+        }
+    }
+
+    // Opacities out of range are clamped rather than rejected.
+    fn opacity(&self) -> Result<f32, ReadError> {
+        Ok(self.number()?.clamp(0.0, 1.0))
+    }
+
+    fn length(&self) -> Result<f32, ReadError> {
+        const SUPPORTED_UNITS: [(&str, f32); 8] = [
+            ("", 1.0),
+            ("cm", 9600.0 / 254.0),
+            ("mm", 960.0 / 254.0),
+            ("Q", 240.0 / 254.0),
+            ("in", 96.0),
+            ("pc", 16.0),
+            ("pt", 96.0 / 72.0),
+            ("px", 1.0),
+        ];
+
+        let trimmed_str = self.value.trim();
+        let number_end = scan_number(trimmed_str.as_bytes(), 0).ok_or_else(|| self.invalid())?;
+        let (numeric_str, unit_str) = trimmed_str.split_at(number_end);
+
+        let modifier = match SUPPORTED_UNITS.iter().find(|(unit, _)| *unit == unit_str) {
+            Some((_, val_to_px)) => *val_to_px,
+            None => return Err(self.invalid()),
+        };
+
+        Ok(f32::from_str(numeric_str)? * modifier)
+    }
+
+    fn keyword<T: Copy>(&self, keywords: &[(&str, T)]) -> Result<T, ReadError> {
+        let value = self.value.trim();
+        keywords
+            .iter()
+            .find(|(keyword, _)| *keyword == value)
+            .map(|(_, result)| *result)
+            .ok_or_else(|| self.invalid())
+    }
+
+    fn number(&self) -> Result<f32, ReadError> {
+        let mut numbers = NumberScanner::new(self.value.as_ref());
+        match (numbers.next(), numbers.next()) {
+            (Some(Ok(number)), None) => Ok(number),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    // An invalid coordinate or an odd number of coordinates is an error. Browsers still render
+    // the points that came before the error, which is what lenient mode does.
+    fn point_list(&self, options: &ParseOptions) -> Result<Vec<Vector2D<f32>>, ReadError> {
+        let mut points = Vec::new();
+
+        let mut x = None;
+        for number in NumberScanner::new(self.value.as_ref()) {
+            let number = match number {
+                Ok(number) => number,
+                Err(InvalidNumber) => {
+                    options.recover(self.invalid())?;
+                    return Ok(points);
+                }
+            };
+
+            match x.take() {
+                None => x = Some(number),
+                Some(x) => points.push([x, number].into()),
+            }
+        }
+
+        if x.is_some() {
+            options.recover(self.invalid())?;
+        }
+
+        Ok(points)
+    }
+
+    // This implements the SVG transformation specification. All the SVG
+    // transformations are supported as documented in the link below:
+    // https://developer.mozilla.org/en-US/docs/Web/SVG/Attribute/transform
+    //
+    // A malformed list invalidates the whole attribute, the same way browsers handle it.
+    fn transform_list(&self) -> Result<Transform, ReadError> {
+        const DEG_TO_RAD: f32 = core::f32::consts::PI / 180.0;
+
+        static RE: sync::Lazy<Regex> =
+            sync::Lazy::new(|| Regex::new(r"\)[,\s]*").expect("Invalid Regex"));
+
+        let mut final_transform = Matrix3x3::IDENTITY3X3;
+
+        for transform_str in RE.split(self.value.as_ref()) {
+            let (transform_type, values) = match transform_str.split_once('(') {
+                None if transform_str.trim().is_empty() => continue,
+                None => return Err(self.invalid()),
+                Some(ret) => ret,
+            };
+
+            const MAX_ARGUMENTS: usize = 6;
+            let mut arguments = [0.0; MAX_ARGUMENTS];
+            let mut num_arguments = 0;
+            for number in NumberScanner::new(values) {
+                if num_arguments == MAX_ARGUMENTS {
+                    return Err(self.invalid());
+                }
+                arguments[num_arguments] = number.map_err(|_| self.invalid())?;
+                num_arguments += 1;
+            }
+            let numbers = &arguments[..num_arguments];
+
+            let transform: Transform = match transform_type.trim() {
+                "matrix" => {
+                    if numbers.len() != 6 {
+                        return Err(self.invalid());
+                    }
+                    [
+                        [numbers[0], numbers[2], numbers[4]],
+                        [numbers[1], numbers[3], numbers[5]],
+                        [0.0, 0.0, 1.0],
+                    ]
+                    .into()
+                }
+                "translate" => {
+                    if numbers.is_empty() || numbers.len() > 2 {
+                        return Err(self.invalid());
+                    }
+                    let x = numbers[0];
+                    let y = *numbers.get(1).unwrap_or(&0.0);
+                    [[1.0, 0.0, x], [0.0, 1.0, y], [0.0, 0.0, 1.0]].into()
+                }
+                "scale" => {
+                    if numbers.is_empty() || numbers.len() > 2 {
+                        return Err(self.invalid());
+                    }
+                    let x = numbers[0];
+                    let y = *numbers.get(1).unwrap_or(&x);
+                    [[x, 0.0, 0.0], [0.0, y, 0.0], [0.0, 0.0, 1.0]].into()
+                }
+                "rotate" => {
+                    if numbers.len() != 1 && numbers.len() != 3 {
+                        return Err(self.invalid());
+                    }
+                    let a = numbers[0] * DEG_TO_RAD;
+                    let x = *numbers.get(1).unwrap_or(&0.0);
+                    let y = *numbers.get(2).unwrap_or(&0.0);
+                    let cx = -x * f32::cos(a) + y * f32::sin(a) + x;
+                    let cy = -x * f32::sin(a) - y * f32::cos(a) + y;
+                    [
+                        [f32::cos(a), -f32::sin(a), cx],
+                        [f32::sin(a), f32::cos(a), cy],
+                        [0.0, 0.0, 1.0],
+                    ]
+                    .into()
+                }
+                "skewX" => {
+                    if numbers.len() != 1 {
+                        return Err(self.invalid());
+                    }
+                    let a = numbers[0] * DEG_TO_RAD;
+                    [[1.0, f32::tan(a), 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]].into()
+                }
+                "skewY" => {
+                    if numbers.len() != 1 {
+                        return Err(self.invalid());
+                    }
+                    let a = numbers[0] * DEG_TO_RAD;
+                    [[1.0, 0.0, 0.0], [f32::tan(a), 1.0, 0.0], [0.0, 0.0, 1.0]].into()
+                }
+                _ => return Err(self.invalid()),
+            };
+
+            final_transform *= transform;
+        }
+
+        Ok(final_transform)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct InvalidNumber;
+
+fn is_wsp(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+// Returns the end of the number starting at `start` according to the SVG number grammar:
+// https://www.w3.org/TR/SVG11/paths.html#PathDataBNF
+//
+// The number is as long as possible, so "3.5.5" ends after "3.5" and "1e-3" is a single number.
+fn scan_number(bytes: &[u8], start: usize) -> Option<usize> {
+    let digits_from = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+
+    let mut i = start;
+    if i < bytes.len() && matches!(bytes[i], b'+' | b'-') {
+        i += 1;
+    }
+
+    let integer_end = digits_from(i);
+    let has_integer = integer_end > i;
+    i = integer_end;
+
+    let mut has_fraction = false;
+    if i < bytes.len() && bytes[i] == b'.' {
+        let fraction_end = digits_from(i + 1);
+        has_fraction = fraction_end > i + 1;
+        if has_integer || has_fraction {
+            i = fraction_end;
+        }
+    }
+
+    if !has_integer && !has_fraction {
+        return None;
+    }
+
+    // An exponent only counts if it has digits, otherwise the 'e' is left for the caller to reject
+    if i < bytes.len() && matches!(bytes[i], b'e' | b'E') {
+        let mut exponent = i + 1;
+        if exponent < bytes.len() && matches!(bytes[exponent], b'+' | b'-') {
+            exponent += 1;
+        }
+        let exponent_end = digits_from(exponent);
+        if exponent_end > exponent {
+            i = exponent_end;
+        }
+    }
+
+    Some(i)
+}
+
+/// Reads the numbers of an SVG number or coordinate list one at a time.
+///
+/// Numbers are separated by whitespace and at most one comma, or not at all when the next number
+/// can't be mistaken for a continuation of the previous one. For example, "10-5 3.5.5" contains
+/// the numbers 10, -5, 3.5 and 0.5. Iteration stops after the first invalid number.
+struct NumberScanner<'a> {
+    source: &'a str,
+    position: usize,
+    is_first: bool,
+}
+
+impl<'a> NumberScanner<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            position: 0,
+            is_first: true,
+        }
+    }
+
+    fn skip_wsp(&mut self) {
+        let bytes = self.source.as_bytes();
+        while self.position < bytes.len() && is_wsp(bytes[self.position]) {
+            self.position += 1;
+        }
+    }
+
+    fn fail(&mut self) -> Option<Result<f32, InvalidNumber>> {
+        self.position = self.source.len();
+        Some(Err(InvalidNumber))
+    }
+}
+
+impl Iterator for NumberScanner<'_> {
+    type Item = Result<f32, InvalidNumber>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.source.as_bytes();
+
+        self.skip_wsp();
+        let mut has_comma = false;
+        if self.position < bytes.len() && bytes[self.position] == b',' {
+            if self.is_first {
+                return self.fail();
+            }
+            has_comma = true;
+            self.position += 1;
+            self.skip_wsp();
+        }
+
+        if self.position == bytes.len() {
+            return if has_comma { self.fail() } else { None };
+        }
+        self.is_first = false;
+
+        let end = match scan_number(bytes, self.position) {
+            Some(end) => end,
+            None => return self.fail(),
+        };
+
+        // The next number must be clearly separated from this one
+        if end < bytes.len()
+            && !matches!(bytes[end], b',' | b'+' | b'-' | b'.')
+            && !is_wsp(bytes[end])
+        {
+            return self.fail();
+        }
+
+        let number = f32::from_str(&self.source[self.position..end]);
+        self.position = end;
+        match number {
+            Ok(number) => Some(Ok(number)),
+            Err(_) => self.fail(),
+        }
+    }
+}
+
+// Hands every attribute of a tag to `parse`. Attributes that fail to parse are either reported or
+// skipped depending on the parse mode, leaving whatever value was there before untouched.
+fn for_each_attribute<'a>(
+    bytes: &'a BytesStart,
+    options: &ParseOptions,
+    mut parse: impl FnMut(Attribute<'a>) -> Result<(), ReadError>,
+) -> Result<(), ReadError> {
+    for attribute in bytes.attributes() {
+        let result = match attribute {
+            Ok(attribute) => Attribute::parse(attribute).and_then(&mut parse),
+            Err(err) => Err(err.into()),
+        };
+
+        if let Err(err) = result {
+            options.recover(err)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// What identifies and describes an element rather than how it is drawn.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ElementInfo {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    /// Text of the element's first `<title>` child.
+    pub title: Option<String>,
+    /// Text of the element's first `<desc>` child.
+    pub description: Option<String>,
+    /// Markup inside of the element's first `<metadata>` child, kept as it was written.
+    pub metadata: Option<String>,
+}
+
+impl ElementInfo {
+    const EMPTY: Self = Self {
+        id: None,
+        classes: Vec::new(),
+        title: None,
+        description: None,
+        metadata: None,
+    };
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|name| name == class)
+    }
+}
+
+/// The `ElementInfo` of an element, only allocated once something is written to it.
+///
+/// Most elements have no id, class or description, so they only hold a null pointer.
+#[derive(Clone, Debug, Default)]
+pub struct BoxedInfo(Option<Box<ElementInfo>>);
+
+impl Deref for BoxedInfo {
+    type Target = ElementInfo;
+
+    fn deref(&self) -> &ElementInfo {
+        static EMPTY: ElementInfo = ElementInfo::EMPTY;
+        self.0.as_deref().unwrap_or(&EMPTY)
+    }
+}
+
+impl DerefMut for BoxedInfo {
+    fn deref_mut(&mut self) -> &mut ElementInfo {
+        self.0.get_or_insert_with(Default::default)
+    }
+}
+
+impl BoxedInfo {
+    // Reads `attribute` if it is one that identifies the element.
+    fn read(&mut self, attribute: &Attribute) {
+        match attribute.key {
+            b"id" => self.id = Some(attribute.value.trim().to_owned()),
+            b"class" => {
+                self.classes = attribute
+                    .value
+                    .split_ascii_whitespace()
+                    .map(str::to_owned)
+                    .collect()
+            }
+            _ => (),
+        };
+    }
+}
+
+impl PartialEq for BoxedInfo {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Point {
+    pub info: BoxedInfo,
+    pub style: Arc<Style>,
+    pub declarations: Declarations,
+    pub position: Vector2D<f32>,
+}
+
+impl Point {
+    fn from_bytes_start(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<Self, ReadError> {
+        let mut x = 0.0;
+        let mut y = 0.0;
+
+        let (style, info, declarations) =
+            styles.read_attributes(&bytes, parent_style, options, |attribute| {
+                match attribute.key {
+                    b"x" => x = attribute.number()?,
+                    b"y" => y = attribute.number()?,
+                    _ => (),
+                };
+                Ok(())
+            })?;
+
+        Ok(Self {
+            info,
+            style,
+            declarations,
+            position: [x, y].into(),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Line {
+    pub info: BoxedInfo,
+    pub style: Arc<Style>,
+    pub declarations: Declarations,
+    pub from: Vector2D<f32>,
+    pub to: Vector2D<f32>,
+}
+
+impl Line {
+    fn from_bytes_start(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<Self, ReadError> {
+        let mut x1 = 0.0;
+        let mut y1 = 0.0;
+        let mut x2 = 0.0;
+        let mut y2 = 0.0;
+
+        let (style, info, declarations) =
+            styles.read_attributes(&bytes, parent_style, options, |attribute| {
+                match attribute.key {
+                    b"x1" => x1 = attribute.length()?,
+                    b"y1" => y1 = attribute.length()?,
+                    b"x2" => x2 = attribute.length()?,
+                    b"y2" => y2 = attribute.length()?,
+                    _ => (),
+                };
+                Ok(())
+            })?;
+
+        Ok(Self {
+            info,
+            style,
+            declarations,
+            from: [x1, y1].into(),
+            to: [x2, y2].into(),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Polyline {
+    pub info: BoxedInfo,
+    pub style: Arc<Style>,
+    pub declarations: Declarations,
+    pub points: Vec<Vector2D<f32>>,
+}
+
+impl Polyline {
+    fn from_bytes_start(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<Self, ReadError> {
+        let mut points = Vec::new();
+
+        let (style, info, declarations) =
+            styles.read_attributes(&bytes, parent_style, options, |attribute| {
+                if attribute.key == b"points" {
+                    points = attribute.point_list(options)?;
+                }
+                Ok(())
+            })?;
+
+        Ok(Self {
+            info,
+            style,
+            declarations,
+            points,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Rect {
+    pub info: BoxedInfo,
+    pub style: Arc<Style>,
+    pub declarations: Declarations,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub rx: f32,
+    pub ry: f32,
+}
+
+impl Rect {
+    fn from_bytes_start(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<EmptyTag, ReadError> {
+        let mut x = 0.0;
+        let mut y = 0.0;
+        let mut rx = None;
+        let mut ry = None;
+        let mut width = 0.0;
+        let mut height = 0.0;
+
+        let (style, info, declarations) =
+            styles.read_attributes(&bytes, parent_style, options, |attribute| {
+                match attribute.key {
+                    b"x" => x = attribute.length()?,
+                    b"y" => y = attribute.length()?,
+                    b"rx" => rx = Some(attribute.length()?),
+                    b"ry" => ry = Some(attribute.length()?),
+                    b"width" => width = attribute.length()?,
+                    b"height" => height = attribute.length()?,
+                    _ => (),
+                };
+                Ok(())
+            })?;
+
+        if width == 0.0 && height == 0.0 {
+            return Ok(EmptyTag::Point(Point {
+                info,
+                style,
+                declarations,
+                position: [x, y].into(),
+            }));
+        }
+
+        let (rx, ry) = match (rx, ry) {
+            (None, None) => (0.0, 0.0),
+            (Some(val), None) | (None, Some(val)) => (val, val),
+            (Some(rx), Some(ry)) => (rx, ry),
+        };
+
+        Ok(EmptyTag::Rect(Rect {
+            info,
+            style,
+            declarations,
+            x,
+            y,
+            width,
+            height,
+            rx,
+            ry,
+        }))
+    }
+
+    /// The radii the corners are rounded with. Rounding only happens when both radii are
+    /// positive, and stops at half of each side.
+    pub fn corner_radii(&self) -> (f32, f32) {
+        if self.rx <= 0.0 || self.ry <= 0.0 {
+            (0.0, 0.0)
+        } else {
+            (
+                self.rx.min(self.width * 0.5),
+                self.ry.min(self.height * 0.5),
+            )
+        }
+    }
+
+    /// The outline of the rect as a polygon. Each rounded corner is a quarter of an ellipse
+    /// flattened into a quarter of `segments_per_turn` segments, or at least one.
+    pub fn to_polygon(&self, segments_per_turn: u32) -> Polygon {
+        if self.width <= 0.0 && self.height <= 0.0 {
+            return Polygon {
+                info: self.info.clone(),
+                style: self.style.clone(),
+                declarations: self.declarations.clone(),
+                points: Vec::new(),
+            };
+        }
+
+        if self.width <= 0.0 {
+            return Polygon {
+                info: self.info.clone(),
+                style: self.style.clone(),
+                declarations: self.declarations.clone(),
+                points: vec![
+                    [self.x, self.y].into(),
+                    [self.x, self.y].into(),
+                    [self.x, self.y + self.height].into(),
+                    [self.x, self.y + self.height].into(),
+                ],
+            };
+        }
+
+        if self.height <= 0.0 {
+            return Polygon {
+                info: self.info.clone(),
+                style: self.style.clone(),
+                declarations: self.declarations.clone(),
+                points: vec![
+                    [self.x, self.y].into(),
+                    [self.x + self.width, self.y].into(),
+                    [self.x + self.width, self.y].into(),
+                    [self.x, self.y].into(),
+                ],
+            };
+        }
+
+        if self.rx <= 0.0 || self.ry <= 0.0 {
+            return Polygon {
+                info: self.info.clone(),
+                style: self.style.clone(),
+                declarations: self.declarations.clone(),
+                points: vec![
+                    [self.x, self.y].into(),
+                    [self.x + self.width, self.y].into(),
+                    [self.x + self.width, self.y + self.height].into(),
+                    [self.x, self.y + self.height].into(),
+                ],
+            };
+        }
+
+        let (rx, ry) = self.corner_radii();
+
+        // The four corners of this rectangle are equivalent to the four corners of an ellipse.
+
+        let segments_per_corner = (segments_per_turn / 4).max(1);
+        let angle_increment = core::f32::consts::PI * 0.5 / segments_per_corner as f32;
+
+        let mut points = Vec::new();
+        points.reserve_exact(4 * (segments_per_corner as usize + 1));
+
+        let do_quarter_elipse =
+            |points: &mut Vec<Vector2D<f32>>, x0: f32, y0: f32, starting_angle: f32| -> () {
+                // Add one point for the final fence post
+                for point in 0..(segments_per_corner + 1) {
+                    let theta = point as f32 * angle_increment + starting_angle;
+                    points.push([x0 + rx * theta.cos(), y0 + ry * theta.sin()].into());
+                }
+            };
+
+        do_quarter_elipse(&mut points, self.x + rx, self.y + ry, core::f32::consts::PI);
+        do_quarter_elipse(
+            &mut points,
+            self.x + self.width - rx,
+            self.y + ry,
+            core::f32::consts::PI * 1.5,
+        );
+        do_quarter_elipse(
+            &mut points,
+            self.x + self.width - rx,
+            self.y + self.height - ry,
+            0.0,
+        );
+        do_quarter_elipse(
+            &mut points,
+            self.x + rx,
+            self.y + self.height - ry,
+            core::f32::consts::PI * 0.5,
+        );
+
+        Polygon {
+            info: self.info.clone(),
+            style: self.style.clone(),
+            declarations: self.declarations.clone(),
+            points,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Polygon {
+    pub info: BoxedInfo,
+    pub style: Arc<Style>,
+    pub declarations: Declarations,
+    pub points: Vec<Vector2D<f32>>,
+}
+
+impl Polygon {
+    fn from_bytes_start(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<Self, ReadError> {
+        let mut points = Vec::new();
+
+        let (style, info, declarations) =
+            styles.read_attributes(&bytes, parent_style, options, |attribute| {
+                if attribute.key == b"points" {
+                    points = attribute.point_list(options)?;
+                }
+                Ok(())
+            })?;
+
+        Ok(Self {
+            info,
+            style,
+            declarations,
+            points,
+        })
+    }
+}
+
+// The outside corner of a stroke where the segments meeting at a point turn.
+struct OuterJoin {
+    // Where the outer edges of the incoming and outgoing strokes end.
+    edges: [Vector2D<f32>; 2],
+    // Joins that go past the miter limit are beveled instead.
+    miter_tip: Option<Vector2D<f32>>,
+}
+
+// Returns None when the segments do not turn or one of them has no length.
+fn outer_join(
+    previous: &Vector2D<f32>,
+    point: &Vector2D<f32>,
+    next: &Vector2D<f32>,
+    half_stroke: f32,
+    miter_limit: f32,
+) -> Option<OuterJoin> {
+    let incoming = (point - previous).unit().ok()?;
+    let outgoing = (next - point).unit().ok()?;
+
+    let turn = incoming.cross(&outgoing);
+    if turn == 0.0 {
+        return None;
+    }
+
+    // The outside of the turn is to the right of a left turn and the other way around.
+    let side = if turn > 0.0 {
+        -half_stroke
+    } else {
+        half_stroke
+    };
+    let incoming_normal = Vector2D::from([-incoming[1], incoming[0]]);
+    let outgoing_normal = Vector2D::from([-outgoing[1], outgoing[0]]);
+
+    let miter = (&incoming_normal + &outgoing_normal).unit().ok();
+    let miter_tip = miter.and_then(|miter| {
+        let cos_half_angle = miter.dot(&incoming_normal);
+        (cos_half_angle > 0.0 && 1.0 / cos_half_angle <= miter_limit)
+            .then(|| point + &(miter * (side / cos_half_angle)))
+    });
+
+    Some(OuterJoin {
+        edges: [
+            point + &(incoming_normal * side),
+            point + &(outgoing_normal * side),
+        ],
+        miter_tip,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Ellipse {
+    pub info: BoxedInfo,
+    pub style: Arc<Style>,
+    pub declarations: Declarations,
+    pub center: Vector2D<f32>,
+    pub radius: Vector2D<f32>,
+}
+
+impl Ellipse {
+    /// Reads an `<ellipse>`, or a `<circle>` whose `r` sets both radii.
+    fn from_bytes_start(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<Self, ReadError> {
+        let mut cx = 0.0;
+        let mut cy = 0.0;
+        let mut rx = 0.0;
+        let mut ry = 0.0;
+        let circle = bytes.local_name().into_inner() == b"circle";
+
+        let (style, info, declarations) =
+            styles.read_attributes(&bytes, parent_style, options, |attribute| {
+                match attribute.key {
+                    b"cx" => cx = attribute.length()?,
+                    b"cy" => cy = attribute.length()?,
+                    b"r" if circle => {
+                        rx = attribute.length()?;
+                        ry = rx;
+                    }
+                    b"rx" if !circle => rx = attribute.length()?,
+                    b"ry" if !circle => ry = attribute.length()?,
+                    _ => (),
+                };
+                Ok(())
+            })?;
+
+        Ok(Self {
+            info,
+            style,
+            declarations,
+            center: [cx, cy].into(),
+            radius: [rx, ry].into(),
+        })
+    }
+
+    /// The outline of the ellipse as a polygon of `segments_per_turn` points.
+    ///
+    /// Renderers that cannot draw curves pick the number of segments from how large the ellipse
+    /// is on screen.
+    pub fn to_polygon(&self, segments_per_turn: u32) -> Polygon {
+        if self.radius[0] <= 0.0 || self.radius[1] <= 0.0 {
+            return Polygon {
+                info: self.info.clone(),
+                style: self.style.clone(),
+                declarations: self.declarations.clone(),
+                points: Vec::new(),
+            };
+        }
+
+        let angle_increment = core::f32::consts::PI * 2.0 / segments_per_turn as f32;
+        let x0 = self.center[0];
+        let y0 = self.center[1];
+        let a = self.radius[0];
+        let b = self.radius[1];
+
+        let mut points = Vec::new();
+        points.reserve_exact(segments_per_turn as usize);
+
+        for point in 0..segments_per_turn {
+            let theta = point as f32 * angle_increment;
+            points.push([x0 + a * theta.cos(), y0 + b * theta.sin()].into());
+        }
+
+        Polygon {
+            info: self.info.clone(),
+            style: self.style.clone(),
+            declarations: self.declarations.clone(),
+            points,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Image {
+    pub info: BoxedInfo,
+    pub style: Arc<Style>,
+    pub declarations: Declarations,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Boxed so the rare images don't widen every `Element`.
+    pub texture: Box<Texture>,
+}
+
+impl Image {
+    fn from_bytes_start(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<Self, ReadError> {
+        let mut x = 0.0;
+        let mut y = 0.0;
+        let mut width = 0.0;
+        let mut height = 0.0;
+        let mut href = Cow::Borrowed("");
+
+        let (style, info, declarations) =
+            styles.read_attributes(&bytes, parent_style, options, |attribute| {
+                match attribute.key {
+                    b"x" => x = attribute.length()?,
+                    b"y" => y = attribute.length()?,
+                    b"width" => width = attribute.length()?,
+                    b"height" => height = attribute.length()?,
+                    b"href" => href = attribute.value,
+                    _ => (),
+                };
+                Ok(())
+            })?;
+
+        Ok(Self {
+            info,
+            style,
+            declarations,
+            x,
+            y,
+            width,
+            height,
+            texture: Box::new(
+                Texture::from_href_with_limits(href.as_ref(), &options.limits.image_limits())
+                    .map_err(|err| match err {
+                        texture::DecodeError::DimensionLimitExceeded { .. } => {
+                            ReadError::LimitExceeded(Limit::ImageDimension)
+                        }
+                        texture::DecodeError::ByteLimitExceeded => {
+                            ReadError::LimitExceeded(Limit::ImageBytes)
+                        }
+                        err => err.into(),
+                    })?,
+            ),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Group {
+    pub info: BoxedInfo,
+    pub style: Arc<Style>,
+    pub declarations: Declarations,
+    pub elements: Vec<Element>,
+}
+
+impl Group {
+    fn from_bytes_start(
+        bytes: BytesStart,
+        parent_style: &Arc<Style>,
+        styles: &mut StyleTable,
+        options: &ParseOptions,
+    ) -> Result<Self, ReadError> {
+        let (style, info, declarations) =
+            styles.read_attributes(&bytes, parent_style, options, |_| Ok(()))?;
+
+        Ok(Self {
+            info,
+            style,
+            declarations,
+            elements: Vec::new(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct SVG {
+    pub info: BoxedInfo,
+    pub dimension: Vector2D<f32>,
+    pub elements: Vec<Element>,
+    changes: Vec<Change>,
+}
+
+impl SVG {
+    pub fn new(dimension: Vector2D<f32>) -> Self {
+        Self {
+            info: BoxedInfo::default(),
+            dimension,
+            elements: Vec::new(),
+            changes: Vec::new(),
+        }
+    }
+
+    fn from_bytes_start(bytes: BytesStart, options: &ParseOptions) -> Result<Self, ReadError> {
+        let mut info = BoxedInfo::default();
+        let mut width = 300.0;
+        let mut height = 150.0;
+
+        // Nested svg tags start over from the default style, so only their info is read.
+        for_each_attribute(&bytes, options, |attribute| {
+            match attribute.key {
+                b"height" => height = attribute.length()?,
+                b"width" => width = attribute.length()?,
+                _ => info.read(&attribute),
+            };
+            Ok(())
+        })?;
+
+        Ok(Self {
+            info,
+            dimension: [width, height].into(),
+            elements: Vec::new(),
+            changes: Vec::new(),
+        })
+    }
+}
+
+// Pending changes are bookkeeping for renderers and do not make two documents different.
+impl PartialEq for SVG {
+    fn eq(&self, other: &Self) -> bool {
+        self.info == other.info
+            && self.dimension == other.dimension
+            && self.elements == other.elements
+    }
+}
+
+/// Decides which parts of a shape are inside it when its outline crosses itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+impl FillRule {
+    const KEYWORDS: [(&'static str, Self); 2] =
+        [("nonzero", Self::NonZero), ("evenodd", Self::EvenOdd)];
+}
+
+/// Decides which parts of an element can be the target of the pointer.
+///
+/// Every element is visible to this crate so the `visible*` values behave like their
+/// counterparts without the prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PointerEvents {
+    VisiblePainted,
+    VisibleFill,
+    VisibleStroke,
+    Visible,
+    Painted,
+    Fill,
+    Stroke,
+    All,
+    None,
+}
+
+impl PointerEvents {
+    const KEYWORDS: [(&'static str, Self); 9] = [
+        ("visiblePainted", Self::VisiblePainted),
+        ("visibleFill", Self::VisibleFill),
+        ("visibleStroke", Self::VisibleStroke),
+        ("visible", Self::Visible),
+        ("painted", Self::Painted),
+        ("fill", Self::Fill),
+        ("stroke", Self::Stroke),
+        ("all", Self::All),
+        ("none", Self::None),
+    ];
+}
+
+/// How the inside or the outline of a shape is painted.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Paint {
+    None,
+    Color(Color),
+    /// The value of the `color` property.
+    CurrentColor,
+    /// A paint server such as a gradient, referred to by its id, and the paint to use when the
+    /// server cannot be used.
+    Server(String, Option<Box<Paint>>),
+}
+
+impl Paint {
+    /// The color this paint draws with, or `None` if nothing is drawn.
+    ///
+    /// Paint servers are not supported yet, so they always use their fallback. A server without
+    /// a fallback draws nothing.
+    pub fn resolve(&self, current_color: Color) -> Option<Color> {
+        match self {
+            Paint::None => None,
+            Paint::Color(color) => Some(*color),
+            Paint::CurrentColor => Some(current_color),
+            Paint::Server(_, fallback) => fallback.as_ref()?.resolve(current_color),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    pub stroke: Paint,
+    pub fill: Paint,
+    pub color: Color,
+    pub stroke_opacity: f32,
+    pub fill_opacity: f32,
+    pub stroke_width: f32,
+    pub miter_limit: f32,
+    pub fill_rule: FillRule,
+    pub pointer_events: PointerEvents,
+    pub transform: Transform,
+}
+
+impl Style {
+    const COLOR_BLACK: Color = Color::RGBA(0, 0, 0, core::u8::MAX);
+
+    pub const DEFAULT: Self = Self {
+        stroke: Paint::Color(Self::COLOR_BLACK),
+        fill: Paint::Color(Self::COLOR_BLACK),
+        color: Self::COLOR_BLACK,
+        stroke_opacity: 1.0,
+        fill_opacity: 1.0,
+        stroke_width: 1.0,
+        miter_limit: 4.0,
+        fill_rule: FillRule::NonZero,
+        pointer_events: PointerEvents::VisiblePainted,
+        transform: Matrix3x3::IDENTITY3X3,
+    };
+
+    /// The color the stroke is drawn with, including the stroke opacity. `None` if the stroke is
+    /// not drawn.
+    pub fn stroke_rgba(&self) -> Option<Color> {
+        self.paint_rgba(&self.stroke, self.stroke_opacity)
+    }
+
+    /// The color the fill is drawn with, including the fill opacity. `None` if the fill is not
+    /// drawn.
+    pub fn fill_rgba(&self) -> Option<Color> {
+        self.paint_rgba(&self.fill, self.fill_opacity)
+    }
+
+    fn paint_rgba(&self, paint: &Paint, opacity: f32) -> Option<Color> {
+        let color = paint.resolve(self.color)?;
+        let alpha = color.a as f32 * opacity.clamp(0.0, 1.0);
+        Some(Color::RGBA(color.r, color.g, color.b, alpha.round() as u8))
+    }
+}
+
+// The declarations with the bits of their numbers, so they can be compared and hashed.
+type DeclarationBits<'a> = (
+    [&'a Option<Paint>; 2],
+    Option<Color>,
+    [Option<u32>; 4],
+    Option<FillRule>,
+    Option<PointerEvents>,
+    Option<[[u32; 3]; 3]>,
+);
+
+/// The style properties an element set itself, kept so that its style can be computed again when
+/// an ancestor's style is edited.
+///
+/// Elements read from a document know their declarations. Elements built by hand have none and
+/// own their whole style, only their transform moves along with their parent's.
+#[derive(Clone, Debug, Default)]
+pub struct Declarations(Option<Arc<StyleDeclarations>>);
+
+// Declarations are bookkeeping for edits and do not make two elements that look alike different.
+impl PartialEq for Declarations {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+// The style properties an element sets itself. Everything else is inherited from its parent.
+#[derive(Debug, Default)]
+struct StyleDeclarations {
+    stroke: Option<Paint>,
+    fill: Option<Paint>,
+    color: Option<Color>,
+    stroke_opacity: Option<f32>,
+    fill_opacity: Option<f32>,
+    stroke_width: Option<f32>,
+    miter_limit: Option<f32>,
+    fill_rule: Option<FillRule>,
+    pointer_events: Option<PointerEvents>,
+    transform: Option<Transform>,
+}
+
+impl StyleDeclarations {
+    // Reads `attribute` if it is a style property, and returns whether it was one.
+    fn read(&mut self, attribute: &Attribute) -> Result<bool, ReadError> {
+        match attribute.key {
+            b"fill" => Self::declare(&mut self.fill, attribute, Attribute::paint)?,
+            b"fill-opacity" => {
+                Self::declare(&mut self.fill_opacity, attribute, Attribute::opacity)?
+            }
+            b"stroke" => Self::declare(&mut self.stroke, attribute, Attribute::paint)?,
+            b"color" => Self::declare(&mut self.color, attribute, Attribute::color)?,
+            b"stroke-opacity" => {
+                Self::declare(&mut self.stroke_opacity, attribute, Attribute::opacity)?
+            }
+            b"stroke-width" => Self::declare(&mut self.stroke_width, attribute, Attribute::number)?,
+            b"stroke-miterlimit" => {
+                Self::declare(&mut self.miter_limit, attribute, Attribute::number)?
+            }
+            b"fill-rule" => Self::declare(&mut self.fill_rule, attribute, |attribute| {
+                attribute.keyword(&FillRule::KEYWORDS)
+            })?,
+            b"pointer-events" => Self::declare(&mut self.pointer_events, attribute, |attribute| {
+                attribute.keyword(&PointerEvents::KEYWORDS)
+            })?,
+            b"transform" => self.transform = Some(attribute.transform_list()?),
+            _ => return Ok(false),
+        };
+        Ok(true)
+    }
+
+    // Every property starts out with the parent's value, so inheriting it explicitly leaves it
+    // alone.
+    fn declare<'a, T>(
+        property: &mut Option<T>,
+        attribute: &Attribute<'a>,
+        parse: impl FnOnce(&Attribute<'a>) -> Result<T, ReadError>,
+    ) -> Result<(), ReadError> {
+        if attribute.value.trim() != "inherit" {
+            *property = Some(parse(attribute)?);
+        }
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn apply(&self, parent_style: &Style) -> Style {
+        Style {
+            stroke: self.stroke.as_ref().unwrap_or(&parent_style.stroke).clone(),
+            fill: self.fill.as_ref().unwrap_or(&parent_style.fill).clone(),
+            color: self.color.unwrap_or(parent_style.color),
+            stroke_opacity: self.stroke_opacity.unwrap_or(parent_style.stroke_opacity),
+            fill_opacity: self.fill_opacity.unwrap_or(parent_style.fill_opacity),
+            stroke_width: self.stroke_width.unwrap_or(parent_style.stroke_width),
+            miter_limit: self.miter_limit.unwrap_or(parent_style.miter_limit),
+            fill_rule: self.fill_rule.unwrap_or(parent_style.fill_rule),
+            pointer_events: self.pointer_events.unwrap_or(parent_style.pointer_events),
+            transform: match &self.transform {
+                Some(transform) => &parent_style.transform * transform,
+                None => parent_style.transform.clone(),
+            },
+        }
+    }
+
+    // The declarations with the bits of their numbers, so they can be compared and hashed.
+    fn bits(&self) -> DeclarationBits<'_> {
+        let numbers = [
+            self.stroke_opacity,
+            self.fill_opacity,
+            self.stroke_width,
+            self.miter_limit,
+        ]
+        .map(|number| number.map(f32::to_bits));
+        let transform = self
+            .transform
+            .as_ref()
+            .map(|t| [0, 1, 2].map(|row| [0, 1, 2].map(|col| t[row][col].to_bits())));
+
+        (
+            [&self.stroke, &self.fill],
+            self.color,
+            numbers,
+            self.fill_rule,
+            self.pointer_events,
+            transform,
+        )
+    }
+}
+
+impl PartialEq for StyleDeclarations {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits()
+    }
+}
+
+impl Eq for StyleDeclarations {}
+
+impl core::hash::Hash for StyleDeclarations {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.bits().hash(state);
+    }
+}
+
+// The bits of every property of a style so equal styles can be found in a hash map.
+#[derive(PartialEq, Eq, Hash)]
+struct StyleKey {
+    paints: [Paint; 2],
+    color: Color,
+    numbers: [u32; 13],
+    fill_rule: FillRule,
+    pointer_events: PointerEvents,
+}
+
+impl StyleKey {
+    fn new(style: &Style) -> Self {
+        let t = &style.transform;
+        let numbers = [
+            style.stroke_opacity,
+            style.fill_opacity,
+            style.stroke_width,
+            style.miter_limit,
+            t[0][0],
+            t[0][1],
+            t[0][2],
+            t[1][0],
+            t[1][1],
+            t[1][2],
+            t[2][0],
+            t[2][1],
+            t[2][2],
+        ]
+        .map(f32::to_bits);
+
+        Self {
+            paints: [style.stroke.clone(), style.fill.clone()],
+            color: style.color,
+            numbers,
+            fill_rule: style.fill_rule,
+            pointer_events: style.pointer_events,
+        }
+    }
+}
+
+// Hands out one shared style for every group of elements with the same computed style. Documents
+// tend to repeat a handful of styles across many elements, so most elements only hold a pointer.
+struct StyleTable {
+    default_style: Arc<Style>,
+    styles: HashMap<StyleKey, Arc<Style>>,
+    // One shared copy of every set of declarations the elements make.
+    declarations: HashSet<Arc<StyleDeclarations>>,
+    // The style that declarations compute to on top of a parent style, found by the addresses of
+    // both. They are always ones the table handed out and still holds, so no two of them share an
+    // address. Elements that look alike under the same parent only compute their style once.
+    computed: HashMap<(usize, usize), Arc<Style>>,
+}
+
+impl StyleTable {
+    fn new() -> Self {
+        let default_style = Arc::new(Style::DEFAULT);
+        let mut styles = HashMap::new();
+        styles.insert(StyleKey::new(&default_style), Arc::clone(&default_style));
+        Self {
+            default_style,
+            styles,
+            declarations: HashSet::new(),
+            computed: HashMap::new(),
+        }
+    }
+
+    fn default_style(&self) -> Arc<Style> {
+        Arc::clone(&self.default_style)
+    }
+
+    // Reads every attribute of an element that has a style in one pass. The style properties it
+    // declares are computed on top of `parent_style`, its id and classes go into its info, and
+    // every other attribute is handed to `parse`.
+    fn read_attributes<'a>(
+        &mut self,
+        bytes: &'a BytesStart,
+        parent_style: &Arc<Style>,
+        options: &ParseOptions,
+        mut parse: impl FnMut(Attribute<'a>) -> Result<(), ReadError>,
+    ) -> Result<(Arc<Style>, BoxedInfo, Declarations), ReadError> {
+        let mut declarations = StyleDeclarations::default();
+        let mut info = BoxedInfo::default();
+        for_each_attribute(bytes, options, |attribute| {
+            if !declarations.read(&attribute)? {
+                info.read(&attribute);
+                parse(attribute)?;
+            }
+            Ok(())
+        })?;
+
+        let declarations = match self.declarations.get(&declarations) {
+            Some(shared) => Arc::clone(shared),
+            None => {
+                let shared = Arc::new(declarations);
+                self.declarations.insert(Arc::clone(&shared));
+                shared
+            }
+        };
+        let style = self.compute(&declarations, parent_style);
+        Ok((style, info, Declarations(Some(declarations))))
+    }
+
+    fn compute(
+        &mut self,
+        declarations: &Arc<StyleDeclarations>,
+        parent_style: &Arc<Style>,
+    ) -> Arc<Style> {
+        if declarations.is_empty() {
+            return Arc::clone(parent_style);
+        }
+
+        let key = (
+            Arc::as_ptr(parent_style) as usize,
+            Arc::as_ptr(declarations) as usize,
+        );
+        if let Some(style) = self.computed.get(&key) {
+            return Arc::clone(style);
+        }
+
+        let style = declarations.apply(parent_style);
+        let shared = if style == **parent_style {
+            Arc::clone(parent_style)
+        } else {
+            let shared = self
+                .styles
+                .entry(StyleKey::new(&style))
+                .or_insert_with(|| Arc::new(style));
+            Arc::clone(shared)
+        };
+        self.computed.insert(key, Arc::clone(&shared));
+        shared
+    }
+}
+
+const SVG_NAMESPACE: &[u8] = b"http://www.w3.org/2000/svg";
+
+// Elements defined by the SVG spec that are not drawn by this crate. Strict mode skips over these
+// instead of rejecting the document since they are still conforming SVG.
+const UNSUPPORTED_SVG_TAGS: [&[u8]; 59] = [
+    b"a",
+    b"altGlyph",
+    b"altGlyphDef",
+    b"altGlyphItem",
+    b"animate",
+    b"animateColor",
+    b"animateMotion",
+    b"animateTransform",
+    b"clipPath",
+    b"color-profile",
+    b"cursor",
+    b"defs",
+    b"desc",
+    b"feBlend",
+    b"feColorMatrix",
+    b"feComponentTransfer",
+    b"feComposite",
+    b"feConvolveMatrix",
+    b"feDiffuseLighting",
+    b"feDisplacementMap",
+    b"feDistantLight",
+    b"feFlood",
+    b"feFuncA",
+    b"feFuncB",
+    b"feFuncG",
+    b"feFuncR",
+    b"feGaussianBlur",
+    b"feImage",
+    b"feMerge",
+    b"feMergeNode",
+    b"feMorphology",
+    b"feOffset",
+    b"fePointLight",
+    b"feSpecularLighting",
+    b"feSpotLight",
+    b"feTile",
+    b"feTurbulence",
+    b"filter",
+    b"font",
+    b"foreignObject",
+    b"glyph",
+    b"linearGradient",
+    b"marker",
+    b"mask",
+    b"metadata",
+    b"path",
+    b"pattern",
+    b"radialGradient",
+    b"script",
+    b"set",
+    b"stop",
+    b"style",
+    b"switch",
+    b"symbol",
+    b"text",
+    b"textPath",
+    b"title",
+    b"tspan",
+    b"use",
+];
+
+fn is_svg_tag<R>(reader: &NsReader<R>, name: QName) -> bool {
+    match reader.resolve_element(name).0 {
+        ResolveResult::Bound(Namespace(namespace)) => namespace == SVG_NAMESPACE,
+        ResolveResult::Unbound => true,
+        ResolveResult::Unknown(_) => false,
+    }
+}
+
+// Hands every event up to the end of the tag that was just started to `handle`, including the
+// events of nested tags.
+fn read_tag_contents<R: BufRead>(
+    reader: &mut NsReader<R>,
+    mut handle: impl FnMut(Event) -> Result<(), ReadError>,
+) -> Result<(), ReadError> {
+    let mut depth = 0;
+    let mut buf = Vec::new();
+
+    loop {
+        let event = reader.read_event_into(&mut buf)?;
+        match event {
+            Event::Start(..) => depth += 1,
+            Event::End(..) if depth == 0 => return Ok(()),
+            Event::End(..) => depth -= 1,
+            // The document is cut short. Whatever reads it next reports it.
+            Event::Eof => return Ok(()),
+            _ => (),
+        }
+
+        handle(event)?;
+        buf.clear();
+    }
+}
+
+fn read_text<R: BufRead>(reader: &mut NsReader<R>) -> Result<String, ReadError> {
+    let mut text = String::new();
+    read_tag_contents(reader, |event| {
+        match event {
+            Event::Text(bytes) => text.push_str(&bytes.unescape()?),
+            Event::CData(bytes) => text.push_str(&String::from_utf8_lossy(&bytes)),
+            _ => (),
+        }
+        Ok(())
+    })?;
+
+    Ok(text.trim().to_owned())
+}
+
+fn read_markup<R: BufRead>(reader: &mut NsReader<R>) -> Result<String, ReadError> {
+    let mut writer = Writer::new(Vec::new());
+    read_tag_contents(reader, |event| {
+        writer
+            .write_event(event)
+            .map_err(|err| ReadError::XMLError(err.into()))
+    })?;
+
+    String::from_utf8(writer.into_inner()).map_err(ReadError::FromUtf8Error)
+}
+
+// Reads a title, desc or metadata tag that was just started into the info of the element it
+// describes. Only the first of each kind is kept, as in browsers. Returns false for other tags.
+fn read_description<R: BufRead>(
+    reader: &mut NsReader<R>,
+    name: QName,
+    info: Option<&mut ElementInfo>,
+) -> Result<bool, ReadError> {
+    if !is_svg_tag(reader, name) {
+        return Ok(false);
+    }
+
+    let local_name = name.local_name().into_inner();
+    let value = match local_name {
+        b"title" | b"desc" => read_text(reader)?,
+        b"metadata" => read_markup(reader)?,
+        _ => return Ok(false),
+    };
+
+    // Descriptions outside of the svg tag have nothing to describe.
+    let Some(info) = info else {
+        return Ok(true);
+    };
+
+    let field = match local_name {
+        b"title" => &mut info.title,
+        b"desc" => &mut info.description,
+        _ => &mut info.metadata,
+    };
+    if field.is_none() && !value.is_empty() {
+        *field = Some(value);
+    }
+
+    Ok(true)
+}
+
+// Shapes cannot hold other elements so only their descriptions are read out of them.
+fn read_shape_contents<R: BufRead>(
+    reader: &mut NsReader<R>,
+    info: &mut ElementInfo,
+) -> Result<(), ReadError> {
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(bytes) => {
+                let name = bytes.name().into_inner().to_owned();
+                if !read_description(reader, QName(&name), Some(info))? {
+                    reader.read_to_end_into(QName(&name), &mut Vec::new())?;
+                }
+            }
+            Event::End(..) | Event::Eof => return Ok(()),
+            _ => (),
+        }
+
+        buf.clear();
+    }
+}
+
+// Decides what to do with a tag this crate does not draw. Tags from other namespaces (e.g.
+// Inkscape's) and SVG tags that are simply unsupported are always skipped. Tags that claim to be
+// SVG but are not part of the spec are only tolerated in lenient mode.
+fn check_unrecognized_tag<R>(
+    reader: &NsReader<R>,
+    name: QName,
+    options: &ParseOptions,
+) -> Result<(), ReadError> {
+    let local_name = name.local_name();
+    if is_svg_tag(reader, name) && !UNSUPPORTED_SVG_TAGS.contains(&local_name.into_inner()) {
+        options.recover(ReadError::UnrecognizedTag(
+            String::from_utf8_lossy(local_name.into_inner()).into_owned(),
+        ))?;
+    }
+
+    Ok(())
+}
+
+struct ReadContext<'a> {
+    options: &'a ParseOptions,
+    // Holds the markup of the event being read. Every event is turned into owned elements before
+    // the next one is read, so one buffer serves the whole document.
+    buf: Vec<u8>,
+    style_lifo: Vec<Arc<Style>>,
+    styles: StyleTable,
+    tag_lifo: Vec<StartTag>,
+    num_elements: usize,
+    num_vertices: usize,
+}
+
+impl<'a> ReadContext<'a> {
+    fn new(options: &'a ParseOptions) -> Self {
+        Self {
+            options,
+            buf: Vec::new(),
+            style_lifo: Vec::new(),
+            styles: StyleTable::new(),
+            tag_lifo: Vec::new(),
+            num_elements: 0,
+            num_vertices: 0,
+        }
+    }
+
+    fn count_element(&mut self, element: &Element) -> Result<(), ReadError> {
+        let limits = &self.options.limits;
+
+        self.num_elements += 1;
+        Limits::check(Limit::Elements, self.num_elements, limits.max_elements)?;
+
+        match element {
+            Element::EmptyTag(EmptyTag::Polygon(Polygon { points, .. }))
+            | Element::EmptyTag(EmptyTag::Polyline(Polyline { points, .. })) => {
+                self.num_vertices += points.len();
+                Limits::check(Limit::Vertices, self.num_vertices, limits.max_vertices)
+            }
+            // The start tag is not on the stack yet.
+            Element::StartTag(..) => {
+                Limits::check(Limit::Depth, self.tag_lifo.len() + 1, limits.max_depth)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn read_next_event<R: BufRead>(
+    reader: &mut NsReader<R>,
+    ctx: &mut ReadContext,
+) -> Result<Element, EventStatus> {
+    let parent_style = match ctx.style_lifo.last() {
+        None => ctx.styles.default_style(),
+        Some(style) => Arc::clone(style),
+    };
+
+    ctx.buf.clear();
+    let next_event = reader.read_event_into(&mut ctx.buf)?;
+    match next_event {
+        Event::Start(start_tag_bytes) => {
+            let name = start_tag_bytes.name().into_inner().to_owned();
+            match StartTag::from_start_tag_bytes(
+                start_tag_bytes.borrow(),
+                &parent_style,
+                &mut ctx.styles,
+                ctx.options,
+            ) {
+                Ok((tag, style)) => {
+                    ctx.style_lifo.push(style);
+                    Ok(Element::StartTag(tag))
+                }
+                Err(EventStatus::UnrecognizedTag(tag)) => {
+                    // Shapes written with an end tag can hold their own descriptions.
+                    match EmptyTag::from_empty_tag_bytes(
+                        start_tag_bytes,
+                        &parent_style,
+                        &mut ctx.styles,
+                        ctx.options,
+                    ) {
+                        Ok(mut shape) => {
+                            read_shape_contents(reader, shape.info_mut())?;
+                            return Ok(Element::EmptyTag(shape));
+                        }
+                        Err(EventStatus::UnrecognizedTag(..)) => (),
+                        Err(status) => return Err(status),
+                    }
+
+                    let info = ctx.tag_lifo.last_mut().map(StartTag::info_mut);
+                    if read_description(reader, QName(&name), info)? {
+                        return Err(EventStatus::SkippedTag);
+                    }
+
+                    check_unrecognized_tag(reader, QName(&name), ctx.options)?;
+                    // Nothing inside of an unrecognized tag can be drawn
+                    reader.read_to_end_into(QName(&name), &mut Vec::new())?;
+                    Err(EventStatus::UnrecognizedTag(tag))
+                }
+                Err(status) => Err(status),
+            }
+        }
+        // Event::Text(event) => unimplemented!(),
+        Event::End(end_tag_bytes) => {
+            Ok(Element::EndTag(EndTag::from_end_tag_bytes(end_tag_bytes)?))
+        }
+        Event::Empty(empty_tag_bytes) => {
+            match EmptyTag::from_empty_tag_bytes(
+                empty_tag_bytes.borrow(),
+                &parent_style,
+                &mut ctx.styles,
+                ctx.options,
+            ) {
+                Ok(tag) => Ok(Element::EmptyTag(tag)),
+                Err(EventStatus::UnrecognizedTag(tag)) => {
+                    check_unrecognized_tag(reader, empty_tag_bytes.name(), ctx.options)?;
+                    Err(EventStatus::UnrecognizedTag(tag))
+                }
+                Err(status) => Err(status),
+            }
+        }
+        Event::Eof => Err(EventStatus::Eof),
+        _ => Err(EventStatus::SkippedTag),
+    }
+}
+
+// Pops the innermost open tag and adds it to its parent.
+// Returns the document once the outermost svg tag is closed.
+fn close_last_tag(ctx: &mut ReadContext) -> Result<Option<SVG>, ReadError> {
+    let completed_element = match ctx.tag_lifo.pop() {
+        None => return Err(ReadError::EndTagBeforeStart),
+        Some(last) => last,
+    };
+    ctx.style_lifo.pop();
+
+    match ctx.tag_lifo.last_mut() {
+        None => match completed_element {
+            StartTag::Group(..) => Err(ReadError::MissingSVGTag),
+            StartTag::SVG(svg) => Ok(Some(svg)),
+        },
+        Some(last) => {
+            last.add_element(Element::StartTag(completed_element));
+            Ok(None)
+        }
+    }
+}
+
+fn handle_next_element(ctx: &mut ReadContext, element: Element) -> Result<Option<SVG>, ReadError> {
+    if !matches!(element, Element::EndTag(..)) {
+        ctx.count_element(&element)?;
+    }
+
+    match element {
+        Element::EmptyTag(..) => match ctx.tag_lifo.last_mut() {
+            None => {
+                ctx.options.recover(ReadError::MissingSVGTag)?;
+                Ok(None)
+            }
+            Some(last) => {
+                last.add_element(element);
+                Ok(None)
+            }
+        },
+        Element::StartTag(start_tag) => {
+            ctx.tag_lifo.push(start_tag);
+            Ok(None)
+        }
+        Element::EndTag(end_tag) => {
+            let matching_tag = ctx
+                .tag_lifo
+                .iter()
+                .rposition(|tag| tag.get_expected_end_tag() == end_tag);
+
+            let num_tags_to_close = match matching_tag {
+                Some(position) if position + 1 == ctx.tag_lifo.len() => 1,
+                // In lenient mode, an end tag closes the closest matching open tag along with
+                // every tag that was left open inside of it. An end tag without any matching open
+                // tag is dropped.
+                Some(position) => {
+                    ctx.options.recover(ReadError::EndTagBeforeStart)?;
+                    ctx.tag_lifo.len() - position
+                }
+                None => {
+                    ctx.options.recover(ReadError::EndTagBeforeStart)?;
+                    0
+                }
+            };
+
+            for _ in 0..num_tags_to_close {
+                if let Some(svg) = close_last_tag(ctx)? {
+                    return Ok(Some(svg));
+                }
+            }
+
+            Ok(None)
+        }
+    }
+}
+
+fn read<R: BufRead>(mut reader: NsReader<R>, options: &ParseOptions) -> Result<SVG, ReadError> {
+    // Mismatched end tags are handled by handle_next_element in lenient mode
+    let config = reader.config_mut();
+    config.check_end_names = options.mode == ParseMode::Strict;
+    config.allow_unmatched_ends = options.mode == ParseMode::Lenient;
+
+    let mut ctx = ReadContext::new(options);
+
+    loop {
+        match read_next_event(&mut reader, &mut ctx) {
+            Ok(element) => {
+                if let Some(svg) = handle_next_element(&mut ctx, element)? {
+                    return Ok(svg);
+                }
+            }
+            Err(status) => match status {
+                EventStatus::Error(err) => return Err(err),
+                EventStatus::UnrecognizedTag(_) => println!("{}", status),
+                EventStatus::SkippedTag => (),
+                EventStatus::Eof => break,
+            },
+        };
+    }
+
+    // The document ended with tags that were never closed
+    if !ctx.tag_lifo.is_empty() && options.mode == ParseMode::Lenient {
+        while !ctx.tag_lifo.is_empty() {
+            if let Some(svg) = close_last_tag(&mut ctx)? {
+                return Ok(svg);
+            }
+        }
+    }
+
+    Err(ReadError::MissingSVGTag)
+}
+
+pub fn read_from_file(path: &Path) -> Result<SVG, ReadError> {
+    read_from_file_with_options(path, &ParseOptions::default())
+}
+
+pub fn read_from_file_with_options(path: &Path, options: &ParseOptions) -> Result<SVG, ReadError> {
+    let reader = NsReader::from_file(path)?;
+
+    let file_size = reader
+        .get_ref()
+        .get_ref()
+        .metadata()
+        .map_err(|err| ReadError::XMLError(err.into()))?
+        .len();
+    Limits::check(
+        Limit::DocumentBytes,
+        usize::try_from(file_size).unwrap_or(usize::MAX),
+        options.limits.max_document_bytes,
+    )?;
+
+    read(reader, options)
+}
+
+pub fn read_from_str(contents: &str, options: &ParseOptions) -> Result<SVG, ReadError> {
+    Limits::check(
+        Limit::DocumentBytes,
+        contents.len(),
+        options.limits.max_document_bytes,
+    )?;
+
+    read(NsReader::from_str(contents), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_numbers(source: &str) -> Result<Vec<f32>, InvalidNumber> {
+        NumberScanner::new(source).collect()
+    }
+
+    #[test]
+    fn scan_numbers_separated_by_commas_and_whitespace() {
+        assert_eq!(scan_numbers(" 1, 2 ,3\t4\n"), Ok(vec![1.0, 2.0, 3.0, 4.0]));
+    }
+
+    #[test]
+    fn scan_numbers_without_separators() {
+        assert_eq!(scan_numbers("10-5 3.5.5"), Ok(vec![10.0, -5.0, 3.5, 0.5]));
+    }
+
+    #[test]
+    fn scan_numbers_with_exponents_next_to_signs() {
+        assert_eq!(scan_numbers("1e-3-2E+2+.5e1"), Ok(vec![0.001, -200.0, 5.0]));
+    }
+
+    #[test]
+    fn scan_numbers_with_trailing_decimal_point() {
+        assert_eq!(scan_numbers("5. .5"), Ok(vec![5.0, 0.5]));
+    }
+
+    #[test]
+    fn scan_empty_number_list() {
+        assert_eq!(scan_numbers(""), Ok(vec![]));
+        assert_eq!(scan_numbers("  "), Ok(vec![]));
+    }
+
+    #[test]
+    fn scan_numbers_rejects_repeated_commas() {
+        assert_eq!(scan_numbers("1,,2"), Err(InvalidNumber));
+    }
+
+    #[test]
+    fn scan_numbers_rejects_leading_and_trailing_commas() {
+        assert_eq!(scan_numbers(",1"), Err(InvalidNumber));
+        assert_eq!(scan_numbers("1,"), Err(InvalidNumber));
+    }
+
+    #[test]
+    fn scan_numbers_rejects_garbage() {
+        assert_eq!(scan_numbers("1 abc"), Err(InvalidNumber));
+        assert_eq!(scan_numbers("1e"), Err(InvalidNumber));
+        assert_eq!(scan_numbers("inf"), Err(InvalidNumber));
+    }
+
+    #[test]
+    fn points_can_be_packed_without_separators() {
+        let svg = read_from_str(
+            r#"<svg><polygon points="10-5 3.5.5 0,0"/></svg>"#,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        match svg.elements.first() {
+            Some(Element::EmptyTag(EmptyTag::Polygon(polygon))) => assert_eq!(
+                polygon.points,
+                vec![
+                    Vector2D::from([10.0, -5.0]),
+                    Vector2D::from([3.5, 0.5]),
+                    Vector2D::from([0.0, 0.0])
+                ]
+            ),
+            other => panic!("Expected a polygon but found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lengths_accept_exponents_and_units() {
+        let svg = read_from_str(
+            r#"<svg><rect x="1e1px" y="1in" width="3" height="4"/></svg>"#,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        let rect = first_rect(&svg);
+        assert_eq!((rect.x, rect.y), (10.0, 96.0));
+    }
+
+    fn first_rect(svg: &SVG) -> &Rect {
+        match svg.elements.first() {
+            Some(Element::EmptyTag(EmptyTag::Rect(rect))) => rect,
+            other => panic!("Expected a rect but found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lenient_mode_skips_invalid_numbers() {
+        let svg = read_from_str(
+            r#"<svg><rect x="abc" y="2" width="3" height="4"/></svg>"#,
+            &ParseOptions::LENIENT,
+        )
+        .unwrap();
+
+        let rect = first_rect(&svg);
+        assert_eq!(
+            (rect.x, rect.y, rect.width, rect.height),
+            (0.0, 2.0, 3.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn strict_mode_rejects_invalid_numbers() {
+        let result = read_from_str(
+            r#"<svg><rect x="abc" y="2" width="3" height="4"/></svg>"#,
+            &ParseOptions::STRICT,
+        );
+
+        assert!(matches!(result, Err(ReadError::InvalidAttribute { .. })));
+    }
+
+    #[test]
+    fn lenient_mode_keeps_inherited_style_for_invalid_attributes() {
+        let svg = read_from_str(
+            r##"<svg><rect fill="#00ff00" stroke="bogus" transform="scale(1, 2, 3)" width="3" height="4"/></svg>"##,
+            &ParseOptions::LENIENT,
+        )
+        .unwrap();
+
+        let rect = first_rect(&svg);
+        assert_eq!(rect.style.fill, Paint::Color(Color::RGBA(0, 255, 0, 255)));
+        assert_eq!(rect.style.stroke, Style::DEFAULT.stroke);
+        assert_eq!(rect.style.transform, Matrix3x3::IDENTITY3X3);
+    }
+
+    #[test]
+    fn strict_mode_rejects_invalid_transforms() {
+        let result = read_from_str(
+            r#"<svg><rect transform="scale(1, 2, 3)" width="3" height="4"/></svg>"#,
+            &ParseOptions::STRICT,
+        );
+
+        assert!(matches!(result, Err(ReadError::InvalidAttribute { .. })));
+    }
+
+    #[test]
+    fn keyword_properties_are_inherited() {
+        let svg = read_from_str(
+            r#"<svg><g fill-rule="evenodd" pointer-events="stroke"><rect width="3" height="4" pointer-events="none"/></g></svg>"#,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        let rect = svg.get(&[0, 0]).unwrap().style().unwrap();
+        assert_eq!(rect.fill_rule, FillRule::EvenOdd);
+        assert_eq!(rect.pointer_events, PointerEvents::None);
+
+        let result = read_from_str(
+            r#"<svg><rect fill-rule="sideways" width="3" height="4"/></svg>"#,
+            &ParseOptions::STRICT,
+        );
+        assert!(matches!(result, Err(ReadError::InvalidAttribute { .. })));
+    }
+
+    #[test]
+    fn opacity_is_inherited_separately_from_color() {
+        let svg = read_from_str(
+            r##"<svg>
+                <g fill-opacity="0.5" stroke="#00ff00" stroke-opacity="0.25">
+                    <rect fill="#ff0000" width="1" height="1"/>
+                    <rect fill-opacity="inherit" stroke="inherit" stroke-opacity="1" width="1" height="1"/>
+                </g>
+            </svg>"##,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        let red = svg.get(&[0, 0]).unwrap().style().unwrap();
+        assert_eq!(red.fill, Paint::Color(Color::RGB(255, 0, 0)));
+        assert_eq!(red.fill_opacity, 0.5);
+        assert_eq!(red.fill_rgba(), Some(Color::RGBA(255, 0, 0, 128)));
+        assert_eq!(red.stroke_rgba(), Some(Color::RGBA(0, 255, 0, 64)));
+
+        let black = svg.get(&[0, 1]).unwrap().style().unwrap();
+        assert_eq!(black.fill_rgba(), Some(Color::RGBA(0, 0, 0, 128)));
+        assert_eq!(black.stroke_rgba(), Some(Color::RGB(0, 255, 0)));
+    }
+
+    #[test]
+    fn paints_are_read() {
+        let svg = read_from_str(
+            r##"<svg><g color="#0000ff">
+                <rect fill="none" stroke="#00000000" width="1" height="1"/>
+                <rect fill="currentColor" stroke="url(#gradient)" width="1" height="1"/>
+                <rect fill="url(#gradient) #ff0000" stroke="url( #gradient ) currentColor" width="1" height="1"/>
+                <rect fill="url(#g) red" stroke="url(#g) rgb(0, 128, 0)" width="1" height="1"/>
+            </g></svg>"##,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        let style = |index| svg.get(&[0, index]).unwrap().style().unwrap();
+
+        assert_eq!(style(0).fill, Paint::None);
+        assert_eq!(style(0).fill_rgba(), None);
+        assert_eq!(style(0).stroke, Paint::Color(Color::RGBA(0, 0, 0, 0)));
+        assert_eq!(style(0).stroke_rgba(), Some(Color::RGBA(0, 0, 0, 0)));
+
+        assert_eq!(style(1).fill, Paint::CurrentColor);
+        assert_eq!(style(1).fill_rgba(), Some(Color::RGB(0, 0, 255)));
+        assert_eq!(style(1).stroke, Paint::Server("gradient".to_owned(), None));
+        assert_eq!(style(1).stroke_rgba(), None);
+
+        let red = Paint::Color(Color::RGB(255, 0, 0));
+        assert_eq!(
+            style(2).fill,
+            Paint::Server("gradient".to_owned(), Some(Box::new(red)))
+        );
+        assert_eq!(style(2).fill_rgba(), Some(Color::RGB(255, 0, 0)));
+        assert_eq!(style(2).stroke_rgba(), Some(Color::RGB(0, 0, 255)));
+
+        let red = Paint::Color(Color::RGB(255, 0, 0));
+        assert_eq!(
+            style(3).fill,
+            Paint::Server("g".to_owned(), Some(Box::new(red)))
+        );
+        assert_eq!(style(3).fill_rgba(), Some(Color::RGB(255, 0, 0)));
+        assert_eq!(style(3).stroke_rgba(), Some(Color::RGB(0, 128, 0)));
+
+        for paint in [
+            "url(gradient)",
+            "url(#)",
+            "url(#a) url(#b)",
+            "#ff000",
+            "reddish",
+        ] {
+            let result = read_from_str(
+                &format!(
+                    r#"<svg><rect fill="{}" width="1" height="1"/></svg>"#,
+                    paint
+                ),
+                &ParseOptions::STRICT,
+            );
+            assert!(
+                matches!(result, Err(ReadError::InvalidAttribute { .. })),
+                "{}",
+                paint
+            );
+        }
+    }
+
+    #[test]
+    fn identical_styles_are_shared() {
+        let svg = read_from_str(
+            r##"<svg>
+                <g fill="#ff0000"><rect width="1" height="1"/><rect width="2" height="2"/></g>
+                <rect fill="#ff0000" width="3" height="3"/>
+                <rect fill="#0000ff" width="4" height="4"/>
+                <rect width="5" height="5"/>
+            </svg>"##,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        let style = |path: &[usize]| svg.get(path).unwrap().style().unwrap();
+        assert!(std::ptr::eq(style(&[0]), style(&[0, 0])));
+        assert!(std::ptr::eq(style(&[0, 0]), style(&[0, 1])));
+        assert!(std::ptr::eq(style(&[0, 0]), style(&[1])));
+        assert!(!std::ptr::eq(style(&[1]), style(&[2])));
+        assert_eq!(style(&[3]), &Style::DEFAULT);
+    }
+
+    #[test]
+    fn the_same_declarations_compute_against_each_parent() {
+        let svg = read_from_str(
+            r##"<svg>
+                <g stroke-width="2"><rect stroke-width="2" fill="#ff0000" width="1" height="1"/></g>
+                <g stroke-width="3"><rect stroke-width="2" fill="#ff0000" width="1" height="1"/></g>
+                <g transform="scale(2)"><rect fill="#ff0000" width="1" height="1"/></g>
+            </svg>"##,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        let style = |path: &[usize]| svg.get(path).unwrap().style().unwrap();
+        assert!(std::ptr::eq(style(&[0, 0]), style(&[1, 0])));
+        assert_eq!(style(&[0, 0]).stroke_width, 2.0);
+        assert!(!std::ptr::eq(style(&[0, 0]), style(&[2, 0])));
+        assert_eq!(style(&[2, 0]).transform[0][0], 2.0);
+    }
+
+    #[test]
+    fn lenient_mode_skips_unknown_tags_and_their_contents() {
+        let svg = read_from_str(
+            r#"<svg><blob><rect width="1" height="1"/></blob><rect width="3" height="4"/></svg>"#,
+            &ParseOptions::LENIENT,
+        )
+        .unwrap();
+
+        assert_eq!(svg.elements.len(), 1);
+        assert_eq!(first_rect(&svg).width, 3.0);
+    }
+
+    #[test]
+    fn strict_mode_rejects_unknown_svg_tags() {
+        let result = read_from_str(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><blob/></svg>"#,
+            &ParseOptions::STRICT,
+        );
+
+        assert!(matches!(result, Err(ReadError::UnrecognizedTag(tag)) if tag == "blob"));
+    }
+
+    #[test]
+    fn strict_mode_skips_unsupported_and_foreign_tags() {
+        let svg = read_from_str(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:ink="urn:ink">
+                <metadata><ink:thing/></metadata>
+                <ink:namedview/>
+                <rect width="3" height="4"/>
+            </svg>"#,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        assert_eq!(svg.elements.len(), 1);
+    }
+
+    #[test]
+    fn circles_are_read_as_ellipses() {
+        let svg = read_from_str(
+            r#"<svg><circle cx="3" cy="4" r="5" rx="1"/></svg>"#,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        match &svg.elements[..] {
+            [Element::EmptyTag(EmptyTag::Ellipse(ellipse))] => {
+                assert_eq!(ellipse.center, [3.0, 4.0].into());
+                assert_eq!(ellipse.radius, [5.0, 5.0].into());
+            }
+            elements => panic!("expected one ellipse, got {elements:?}"),
+        }
+    }
+
+    #[test]
+    fn titles_descriptions_and_metadata_are_read() {
+        let svg = read_from_str(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:rdf="urn:rdf">
+                <title> Gauges </title>
+                <title>Ignored</title>
+                <metadata><rdf:RDF><rdf:Description about="a &amp; b"/></rdf:RDF></metadata>
+                <g>
+                    <desc>Fuel &amp; speed</desc>
+                    <rect width="3" height="4">
+                        <title><![CDATA[Needle]]></title>
+                        <animate/>
+                    </rect>
+                </g>
+            </svg>"#,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        assert_eq!(svg.title(), Some("Gauges"));
+        assert_eq!(
+            svg.info.metadata.as_deref(),
+            Some(r#"<rdf:RDF><rdf:Description about="a &amp; b"/></rdf:RDF>"#)
+        );
+
+        let group = svg.get(&[0]).unwrap().info().unwrap();
+        assert_eq!(group.title, None);
+        assert_eq!(group.description.as_deref(), Some("Fuel & speed"));
+
+        let rect = svg.get(&[0, 0]).unwrap();
+        assert!(matches!(rect, Element::EmptyTag(EmptyTag::Rect(..))));
+        assert_eq!(rect.info().unwrap().title.as_deref(), Some("Needle"));
+    }
+
+    fn read_with_limits(contents: &str, limits: Limits) -> Result<SVG, ReadError> {
+        let options = ParseOptions {
+            limits,
+            ..ParseOptions::LENIENT
+        };
+        read_from_str(contents, &options)
+    }
+
+    fn exceeded_limit(result: Result<SVG, ReadError>) -> Option<Limit> {
+        match result {
+            Err(ReadError::LimitExceeded(limit)) => Some(limit),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn documents_within_the_limits_are_read() {
+        let contents = r#"<svg><g><polygon points="0,0 1,0 1,1"/></g></svg>"#;
+        let limits = Limits {
+            max_document_bytes: contents.len(),
+            max_elements: 3,
+            max_depth: 2,
+            max_vertices: 3,
+            ..Limits::NONE
+        };
+
+        assert!(read_with_limits(contents, limits).is_ok());
+    }
+
+    #[test]
+    fn documents_over_a_limit_are_rejected() {
+        let contents =
+            r#"<svg><g><g><rect width="1" height="1"/></g></g><polyline points="0,0 1,1"/></svg>"#;
+
+        let cases = [
+            (
+                Limits {
+                    max_document_bytes: 10,
+                    ..Limits::NONE
+                },
+                Limit::DocumentBytes,
+            ),
+            (
+                Limits {
+                    max_elements: 4,
+                    ..Limits::NONE
+                },
+                Limit::Elements,
+            ),
+            (
+                Limits {
+                    max_depth: 2,
+                    ..Limits::NONE
+                },
+                Limit::Depth,
+            ),
+            (
+                Limits {
+                    max_vertices: 1,
+                    ..Limits::NONE
+                },
+                Limit::Vertices,
+            ),
+        ];
+
+        for (limits, limit) in cases {
+            assert_eq!(
+                exceeded_limit(read_with_limits(contents, limits)),
+                Some(limit)
+            );
+        }
+    }
+
+    #[test]
+    fn images_over_a_limit_are_rejected() {
+        // A 2x1 png.
+        let contents = r#"<svg><image width="2" height="1" href="data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAYAAAD0In+KAAAADklEQVR4nGP4z8AAQg0AD3oDfnfpf5cAAAAASUVORK5CYII="/></svg>"#;
+
+        let small_images = Limits {
+            max_image_dimension: 1,
+            ..Limits::NONE
+        };
+        assert_eq!(
+            exceeded_limit(read_with_limits(contents, small_images)),
+            Some(Limit::ImageDimension)
+        );
+
+        let few_bytes = Limits {
+            max_image_bytes: 7,
+            ..Limits::NONE
+        };
+        assert_eq!(
+            exceeded_limit(read_with_limits(contents, few_bytes)),
+            Some(Limit::ImageBytes)
+        );
+
+        assert!(read_with_limits(contents, Limits::UNTRUSTED).is_ok());
+    }
+
+    #[test]
+    fn lenient_mode_recovers_from_mismatched_end_tags() {
+        let svg = read_from_str(
+            r#"<svg><g><rect width="1" height="1"/></svg>"#,
+            &ParseOptions::LENIENT,
+        )
+        .unwrap();
+
+        match svg.elements.first() {
+            Some(Element::StartTag(StartTag::Group(group))) => assert_eq!(group.elements.len(), 1),
+            other => panic!("Expected a group but found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lenient_mode_drops_end_tags_without_a_start_tag() {
+        let svg = read_from_str(
+            r#"<svg></g><rect width="3" height="4"/></svg>"#,
+            &ParseOptions::LENIENT,
+        )
+        .unwrap();
+
+        assert_eq!(first_rect(&svg).width, 3.0);
+    }
+
+    #[test]
+    fn strict_mode_rejects_mismatched_end_tags() {
+        let result = read_from_str(
+            r#"<svg><g><rect width="1" height="1"/></svg>"#,
+            &ParseOptions::STRICT,
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use std::{iter::Enumerate, slice};
+
+use super::{Element, ElementInfo, EmptyTag, StartTag, Style, SVG};
+
+/// The position of an element in a document.
+///
+/// Each entry indexes into the children of the element found by the entries before it, starting
+/// with the elements of the root svg tag.
+pub type ElementPath = Vec<usize>;
+
+impl EmptyTag {
+    pub fn info(&self) -> &ElementInfo {
+        match self {
+            EmptyTag::Ellipse(ellipse) => &ellipse.info,
+            EmptyTag::Image(image) => &image.info,
+            EmptyTag::Line(line) => &line.info,
+            EmptyTag::Point(point) => &point.info,
+            EmptyTag::Polygon(polygon) => &polygon.info,
+            EmptyTag::Polyline(polyline) => &polyline.info,
+            EmptyTag::Rect(rect) => &rect.info,
+        }
+    }
+
+    pub fn style(&self) -> &Style {
+        match self {
+            EmptyTag::Ellipse(ellipse) => &ellipse.style,
+            EmptyTag::Image(image) => &image.style,
+            EmptyTag::Line(line) => &line.style,
+            EmptyTag::Point(point) => &point.style,
+            EmptyTag::Polygon(polygon) => &polygon.style,
+            EmptyTag::Polyline(polyline) => &polyline.style,
+            EmptyTag::Rect(rect) => &rect.style,
+        }
+    }
+}
+
+impl Element {
+    /// Returns None for end tags, which never end up in a parsed document.
+    pub fn info(&self) -> Option<&ElementInfo> {
+        match self {
+            Element::EmptyTag(tag) => Some(tag.info()),
+            Element::EndTag(..) => None,
+            Element::StartTag(StartTag::Group(group)) => Some(&*group.info),
+            Element::StartTag(StartTag::SVG(svg)) => Some(&*svg.info),
+        }
+    }
+
+    /// Returns the style computed from this element's attributes and all of its ancestors.
+    ///
+    /// Nested svg tags do not inherit any style so they always have the default style.
+    pub fn style(&self) -> Option<&Style> {
+        match self {
+            Element::EmptyTag(tag) => Some(tag.style()),
+            Element::EndTag(..) => None,
+            Element::StartTag(StartTag::Group(group)) => Some(&group.style),
+            Element::StartTag(StartTag::SVG(..)) => Some(&Style::DEFAULT),
+        }
+    }
+
+    pub fn children(&self) -> &[Element] {
+        match self {
+            Element::StartTag(StartTag::Group(group)) => &group.elements,
+            Element::StartTag(StartTag::SVG(svg)) => &svg.elements,
+            _ => &[],
+        }
+    }
+}
+
+impl SVG {
+    /// Returns the text of the document's `<title>`.
+    pub fn title(&self) -> Option<&str> {
+        self.info.title.as_deref()
+    }
+
+    pub fn get(&self, path: &[usize]) -> Option<&Element> {
+        let (last, parents) = path.split_last()?;
+        let mut elements = self.elements.as_slice();
+        for index in parents {
+            elements = elements.get(*index)?.children();
+        }
+        elements.get(*last)
+    }
+
+    /// Returns the elements that contain the element at `path`, outermost first.
+    ///
+    /// The root svg tag is not part of the chain. Returns None if there is no element at `path`.
+    pub fn ancestors(&self, path: &[usize]) -> Option<Vec<&Element>> {
+        let (last, parents) = path.split_last()?;
+
+        let mut ancestors = Vec::with_capacity(parents.len());
+        let mut elements = self.elements.as_slice();
+        for index in parents {
+            let parent = elements.get(*index)?;
+            elements = parent.children();
+            ancestors.push(parent);
+        }
+
+        elements.get(*last)?;
+        Some(ancestors)
+    }
+
+    /// Iterates over every element of the document in document order, parents before children.
+    pub fn iter(&self) -> Elements<'_> {
+        Elements {
+            parents: Vec::new(),
+            stack: vec![self.elements.iter().enumerate()],
+        }
+    }
+
+    /// Returns the first element in document order with the given id.
+    pub fn find_by_id(&self, id: &str) -> Option<(ElementPath, &Element)> {
+        self.iter().find(|(_, element)| {
+            element
+                .info()
+                .is_some_and(|info| info.id.as_deref() == Some(id))
+        })
+    }
+
+    /// Returns every element with the given class in document order.
+    pub fn find_by_class<'a>(
+        &'a self,
+        class: &'a str,
+    ) -> impl Iterator<Item = (ElementPath, &'a Element)> + 'a {
+        self.iter()
+            .filter(move |(_, element)| element.info().is_some_and(|info| info.has_class(class)))
+    }
+}
+
+/// Depth first iterator over the elements of an SVG document.
+pub struct Elements<'a> {
+    parents: ElementPath,
+    stack: Vec<Enumerate<slice::Iter<'a, Element>>>,
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = (ElementPath, &'a Element);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (index, element) = match self.stack.last_mut()?.next() {
+                Some(next) => next,
+                None => {
+                    self.stack.pop();
+                    self.parents.pop();
+                    continue;
+                }
+            };
+
+            let mut path = self.parents.clone();
+            path.push(index);
+
+            let children = element.children();
+            if !children.is_empty() {
+                self.parents.push(index);
+                self.stack.push(children.iter().enumerate());
+            }
+
+            return Some((path, element));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+
+    use super::*;
+    use crate::objects::svg::{read_from_str, Paint, ParseOptions};
+
+    const DOCUMENT: &str = r##"<svg>
+  <g id="gauges" class="panel" fill="#ff0000">
+    <rect id="speed" class="gauge  dial" width="1" height="1"/>
+    <g transform="translate(5)">
+      <ellipse id="fuel" class="gauge" rx="1" ry="1"/>
+    </g>
+  </g>
+  <line id="divider"/>
+</svg>"##;
+
+    fn read_document() -> SVG {
+        read_from_str(DOCUMENT, &ParseOptions::STRICT).unwrap()
+    }
+
+    fn ids<'a>(elements: impl IntoIterator<Item = &'a Element>) -> Vec<Option<&'a str>> {
+        elements
+            .into_iter()
+            .map(|element| element.info().unwrap().id.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn iterate_in_document_order() {
+        let svg = read_document();
+
+        let paths: Vec<ElementPath> = svg.iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![vec![0], vec![0, 0], vec![0, 1], vec![0, 1, 0], vec![1]]
+        );
+        assert_eq!(
+            ids(svg.iter().map(|(_, element)| element)),
+            vec![
+                Some("gauges"),
+                Some("speed"),
+                None,
+                Some("fuel"),
+                Some("divider")
+            ]
+        );
+    }
+
+    #[test]
+    fn find_element_by_id() {
+        let svg = read_document();
+
+        let (path, element) = svg.find_by_id("fuel").unwrap();
+        assert_eq!(path, vec![0, 1, 0]);
+        assert!(matches!(element, Element::EmptyTag(EmptyTag::Ellipse(..))));
+        assert_eq!(svg.get(&path), Some(element));
+
+        assert!(svg.find_by_id("missing").is_none());
+    }
+
+    #[test]
+    fn find_elements_by_class() {
+        let svg = read_document();
+
+        assert_eq!(
+            ids(svg.find_by_class("gauge").map(|(_, element)| element)),
+            vec![Some("speed"), Some("fuel")]
+        );
+        assert_eq!(
+            ids(svg.find_by_class("dial").map(|(_, element)| element)),
+            vec![Some("speed")]
+        );
+    }
+
+    #[test]
+    fn ancestor_chain_starts_at_the_outermost_element() {
+        let svg = read_document();
+
+        let (path, _) = svg.find_by_id("fuel").unwrap();
+        assert_eq!(
+            ids(svg.ancestors(&path).unwrap()),
+            vec![Some("gauges"), None]
+        );
+        assert_eq!(svg.ancestors(&[1]), Some(vec![]));
+        assert_eq!(svg.ancestors(&[0, 5]), None);
+    }
+
+    #[test]
+    fn elements_expose_their_computed_style() {
+        let svg = read_document();
+
+        let (_, fuel) = svg.find_by_id("fuel").unwrap();
+        let style = fuel.style().unwrap();
+        assert_eq!(style.fill, Paint::Color(Color::RGB(255, 0, 0)));
+        assert_eq!(style.transform[0][2], 5.0);
+    }
+}
@@ -0,0 +1,201 @@
+use super::{
+    Element, ElementPath, Ellipse, EmptyTag, Group, Image, Line, Point, Polygon, Polyline, Rect,
+    StartTag, Style, SVG,
+};
+
+/// Where the walker is in the document when it calls a visitor.
+pub struct VisitContext<'a> {
+    pub path: &'a [usize],
+    /// The style computed for the element, including the transform accumulated from all of its
+    /// ancestors.
+    pub style: &'a Style,
+}
+
+/// Callbacks for walking an SVG document with `SVG::walk`.
+///
+/// Every callback does nothing by default so visitors only implement what they care about.
+/// Elements are visited in document order. Container tags are entered before their children and
+/// left after them.
+#[allow(unused_variables)]
+pub trait Visitor {
+    fn enter_svg(&mut self, svg: &SVG, ctx: &VisitContext) {}
+    fn leave_svg(&mut self, svg: &SVG, ctx: &VisitContext) {}
+
+    fn enter_group(&mut self, group: &Group, ctx: &VisitContext) {}
+    fn leave_group(&mut self, group: &Group, ctx: &VisitContext) {}
+
+    fn visit_ellipse(&mut self, ellipse: &Ellipse, ctx: &VisitContext) {}
+    fn visit_image(&mut self, image: &Image, ctx: &VisitContext) {}
+    fn visit_line(&mut self, line: &Line, ctx: &VisitContext) {}
+    fn visit_point(&mut self, point: &Point, ctx: &VisitContext) {}
+    fn visit_polygon(&mut self, polygon: &Polygon, ctx: &VisitContext) {}
+    fn visit_polyline(&mut self, polyline: &Polyline, ctx: &VisitContext) {}
+    fn visit_rect(&mut self, rect: &Rect, ctx: &VisitContext) {}
+}
+
+impl SVG {
+    /// Walks the whole document, starting with this svg tag.
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+        walk_svg(self, &mut Vec::new(), visitor);
+    }
+
+    /// Walks the subtree of the element at `path`.
+    ///
+    /// Returns false if there is no element at `path`.
+    pub fn walk_from<V: Visitor + ?Sized>(&self, path: &[usize], visitor: &mut V) -> bool {
+        match self.get(path) {
+            Some(element) => {
+                walk_element(element, &mut path.to_vec(), visitor);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn walk_svg<V: Visitor + ?Sized>(svg: &SVG, path: &mut ElementPath, visitor: &mut V) {
+    // Nested svg tags do not inherit any style.
+    let style = &Style::DEFAULT;
+
+    visitor.enter_svg(svg, &VisitContext { path, style });
+    walk_elements(&svg.elements, path, visitor);
+    visitor.leave_svg(svg, &VisitContext { path, style });
+}
+
+fn walk_elements<V: Visitor + ?Sized>(
+    elements: &[Element],
+    path: &mut ElementPath,
+    visitor: &mut V,
+) {
+    for (index, element) in elements.iter().enumerate() {
+        path.push(index);
+        walk_element(element, path, visitor);
+        path.pop();
+    }
+}
+
+fn walk_element<V: Visitor + ?Sized>(element: &Element, path: &mut ElementPath, visitor: &mut V) {
+    match element {
+        Element::EmptyTag(empty_tag) => {
+            let ctx = VisitContext {
+                path,
+                style: empty_tag.style(),
+            };
+
+            match empty_tag {
+                EmptyTag::Ellipse(ellipse) => visitor.visit_ellipse(ellipse, &ctx),
+                EmptyTag::Image(image) => visitor.visit_image(image, &ctx),
+                EmptyTag::Line(line) => visitor.visit_line(line, &ctx),
+                EmptyTag::Point(point) => visitor.visit_point(point, &ctx),
+                EmptyTag::Polygon(polygon) => visitor.visit_polygon(polygon, &ctx),
+                EmptyTag::Polyline(polyline) => visitor.visit_polyline(polyline, &ctx),
+                EmptyTag::Rect(rect) => visitor.visit_rect(rect, &ctx),
+            }
+        }
+        Element::StartTag(StartTag::Group(group)) => {
+            let style = &group.style;
+
+            visitor.enter_group(group, &VisitContext { path, style });
+            walk_elements(&group.elements, path, visitor);
+            visitor.leave_group(group, &VisitContext { path, style });
+        }
+        Element::StartTag(StartTag::SVG(svg)) => walk_svg(svg, path, visitor),
+        Element::EndTag(..) => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::svg::{read_from_str, ParseOptions};
+
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<String>,
+    }
+
+    impl Recorder {
+        fn record(&mut self, name: &str, ctx: &VisitContext) {
+            self.calls.push(format!(
+                "{} {:?} {}",
+                name, ctx.path, ctx.style.transform[0][2]
+            ));
+        }
+    }
+
+    impl Visitor for Recorder {
+        fn enter_svg(&mut self, _: &SVG, ctx: &VisitContext) {
+            self.record("enter svg", ctx);
+        }
+
+        fn leave_svg(&mut self, _: &SVG, ctx: &VisitContext) {
+            self.record("leave svg", ctx);
+        }
+
+        fn enter_group(&mut self, _: &Group, ctx: &VisitContext) {
+            self.record("enter g", ctx);
+        }
+
+        fn leave_group(&mut self, _: &Group, ctx: &VisitContext) {
+            self.record("leave g", ctx);
+        }
+
+        fn visit_line(&mut self, _: &Line, ctx: &VisitContext) {
+            self.record("line", ctx);
+        }
+
+        fn visit_rect(&mut self, _: &Rect, ctx: &VisitContext) {
+            self.record("rect", ctx);
+        }
+    }
+
+    const DOCUMENT: &str = r#"<svg>
+  <g transform="translate(1)">
+    <rect width="1" height="1" transform="translate(2)"/>
+    <svg><line/></svg>
+  </g>
+  <ellipse/>
+  <line/>
+</svg>"#;
+
+    #[test]
+    fn walk_visits_elements_in_document_order() {
+        let svg = read_from_str(DOCUMENT, &ParseOptions::STRICT).unwrap();
+
+        let mut recorder = Recorder::default();
+        svg.walk(&mut recorder);
+
+        assert_eq!(
+            recorder.calls,
+            vec![
+                "enter svg [] 0",
+                "enter g [0] 1",
+                "rect [0, 0] 3",
+                "enter svg [0, 1] 0",
+                "line [0, 1, 0] 0",
+                "leave svg [0, 1] 0",
+                "leave g [0] 1",
+                "line [2] 0",
+                "leave svg [] 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn walk_from_visits_a_subtree() {
+        let svg = read_from_str(DOCUMENT, &ParseOptions::STRICT).unwrap();
+
+        let mut recorder = Recorder::default();
+        assert!(svg.walk_from(&[0, 1], &mut recorder));
+        assert_eq!(
+            recorder.calls,
+            vec![
+                "enter svg [0, 1] 0",
+                "line [0, 1, 0] 0",
+                "leave svg [0, 1] 0",
+            ]
+        );
+
+        assert!(!svg.walk_from(&[3], &mut recorder));
+    }
+}
@@ -0,0 +1,418 @@
+use std::io::{self, Write};
+
+use crate::{color::Color, vector::Vector2D};
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event},
+    Writer,
+};
+
+use super::{Element, ElementInfo, EmptyTag, FillRule, Paint, PointerEvents, StartTag, Style, SVG};
+
+#[derive(Clone, Debug, Default)]
+pub struct WriteOptions {
+    /// Puts every element on its own line, indented by its depth in the tree.
+    pub pretty: bool,
+}
+
+impl WriteOptions {
+    pub const COMPACT: Self = Self { pretty: false };
+
+    pub const PRETTY: Self = Self { pretty: true };
+}
+
+impl SVG {
+    /// Writes the document out as compact SVG markup.
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        self.write_to_with_options(out, &WriteOptions::default())
+    }
+
+    /// Writes the document out as SVG markup.
+    ///
+    /// Every element only lists the style properties that differ from its parent so that reading
+    /// the output back gives an equivalent tree.
+    pub fn write_to_with_options(
+        &self,
+        out: &mut impl Write,
+        options: &WriteOptions,
+    ) -> io::Result<()> {
+        let mut writer = if options.pretty {
+            Writer::new_with_indent(out, b' ', 2)
+        } else {
+            Writer::new(out)
+        };
+
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        write_svg(&mut writer, self, true)?;
+
+        if options.pretty {
+            writer.get_mut().write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_svg<W: Write>(writer: &mut Writer<W>, svg: &SVG, is_root: bool) -> io::Result<()> {
+    let mut start = BytesStart::new("svg");
+    if is_root {
+        start.push_attribute(("xmlns", "http://www.w3.org/2000/svg"));
+    }
+    push_info(&mut start, &svg.info);
+    push_number(&mut start, "width", svg.dimension[0]);
+    push_number(&mut start, "height", svg.dimension[1]);
+
+    // The reader starts every svg tag from the default style, no matter what it is nested in.
+    write_children(writer, start, &svg.info, &svg.elements, &Style::DEFAULT)
+}
+
+// Children are always wrapped in a start and end tag since the reader does not accept container
+// tags written as empty tags.
+fn write_children<W: Write>(
+    writer: &mut Writer<W>,
+    start: BytesStart,
+    info: &ElementInfo,
+    elements: &[Element],
+    style: &Style,
+) -> io::Result<()> {
+    let end = BytesEnd::new(String::from_utf8_lossy(start.name().into_inner()).into_owned());
+
+    writer.write_event(Event::Start(start))?;
+    write_descriptions(writer, info)?;
+    for element in elements {
+        write_element(writer, element, style)?;
+    }
+    writer.write_event(Event::End(end))
+}
+
+fn has_descriptions(info: &ElementInfo) -> bool {
+    info.title.is_some() || info.description.is_some() || info.metadata.is_some()
+}
+
+fn write_descriptions<W: Write>(writer: &mut Writer<W>, info: &ElementInfo) -> io::Result<()> {
+    if let Some(title) = &info.title {
+        writer
+            .create_element("title")
+            .write_text_content(BytesText::new(title))?;
+    }
+
+    if let Some(description) = &info.description {
+        writer
+            .create_element("desc")
+            .write_text_content(BytesText::new(description))?;
+    }
+
+    // Metadata is kept as markup so it goes out without being escaped again.
+    if let Some(metadata) = &info.metadata {
+        writer
+            .create_element("metadata")
+            .write_text_content(BytesText::from_escaped(metadata.as_str()))?;
+    }
+
+    Ok(())
+}
+
+fn write_element<W: Write>(
+    writer: &mut Writer<W>,
+    element: &Element,
+    parent_style: &Style,
+) -> io::Result<()> {
+    match element {
+        Element::EmptyTag(tag) => write_empty_tag(writer, tag, parent_style),
+        Element::StartTag(StartTag::Group(group)) => {
+            let mut start = BytesStart::new("g");
+            push_info(&mut start, &group.info);
+            push_style(&mut start, &group.style, parent_style);
+            write_children(writer, start, &group.info, &group.elements, &group.style)
+        }
+        Element::StartTag(StartTag::SVG(svg)) => write_svg(writer, svg, false),
+        // End tags only exist while reading and are never stored in the tree.
+        Element::EndTag(..) => Ok(()),
+    }
+}
+
+fn write_empty_tag<W: Write>(
+    writer: &mut Writer<W>,
+    tag: &EmptyTag,
+    parent_style: &Style,
+) -> io::Result<()> {
+    let mut start = match tag {
+        EmptyTag::Ellipse(ellipse) => {
+            let mut start = BytesStart::new("ellipse");
+            push_number(&mut start, "cx", ellipse.center[0]);
+            push_number(&mut start, "cy", ellipse.center[1]);
+            push_number(&mut start, "rx", ellipse.radius[0]);
+            push_number(&mut start, "ry", ellipse.radius[1]);
+            start
+        }
+        EmptyTag::Image(image) => {
+            let mut start = BytesStart::new("image");
+            push_number(&mut start, "x", image.x);
+            push_number(&mut start, "y", image.y);
+            push_number(&mut start, "width", image.width);
+            push_number(&mut start, "height", image.height);
+            let href = image.texture.to_href().map_err(io::Error::other)?;
+            start.push_attribute(("href", href.as_str()));
+            start
+        }
+        EmptyTag::Line(line) => {
+            let mut start = BytesStart::new("line");
+            push_number(&mut start, "x1", line.from[0]);
+            push_number(&mut start, "y1", line.from[1]);
+            push_number(&mut start, "x2", line.to[0]);
+            push_number(&mut start, "y2", line.to[1]);
+            start
+        }
+        // Point is not a standard SVG tag. An empty rect is read back as a point.
+        EmptyTag::Point(point) => {
+            let mut start = BytesStart::new("rect");
+            push_number(&mut start, "x", point.position[0]);
+            push_number(&mut start, "y", point.position[1]);
+            push_number(&mut start, "width", 0.0);
+            push_number(&mut start, "height", 0.0);
+            start
+        }
+        EmptyTag::Polygon(polygon) => {
+            let mut start = BytesStart::new("polygon");
+            push_points(&mut start, &polygon.points);
+            start
+        }
+        EmptyTag::Polyline(polyline) => {
+            let mut start = BytesStart::new("polyline");
+            push_points(&mut start, &polyline.points);
+            start
+        }
+        EmptyTag::Rect(rect) => {
+            let mut start = BytesStart::new("rect");
+            push_number(&mut start, "x", rect.x);
+            push_number(&mut start, "y", rect.y);
+            push_number(&mut start, "width", rect.width);
+            push_number(&mut start, "height", rect.height);
+            if rect.rx != 0.0 || rect.ry != 0.0 {
+                push_number(&mut start, "rx", rect.rx);
+                push_number(&mut start, "ry", rect.ry);
+            }
+            start
+        }
+    };
+
+    push_info(&mut start, tag.info());
+    push_style(&mut start, tag.style(), parent_style);
+
+    if has_descriptions(tag.info()) {
+        write_children(writer, start, tag.info(), &[], parent_style)
+    } else {
+        writer.write_event(Event::Empty(start))
+    }
+}
+
+// f32's Display impl prints the shortest decimal that reads back to the same value and never
+// uses exponents, so its output is always a valid SVG number.
+fn push_number(start: &mut BytesStart, name: &str, value: f32) {
+    start.push_attribute((name, value.to_string().as_str()));
+}
+
+fn push_points(start: &mut BytesStart, points: &[Vector2D<f32>]) {
+    let points = points
+        .iter()
+        .map(|point| format!("{},{}", point[0], point[1]))
+        .collect::<Vec<_>>()
+        .join(" ");
+    start.push_attribute(("points", points.as_str()));
+}
+
+fn push_info(start: &mut BytesStart, info: &ElementInfo) {
+    if let Some(id) = &info.id {
+        start.push_attribute(("id", id.as_str()));
+    }
+
+    if !info.classes.is_empty() {
+        start.push_attribute(("class", info.classes.join(" ").as_str()));
+    }
+}
+
+fn push_style(start: &mut BytesStart, style: &Style, parent_style: &Style) {
+    if style.fill != parent_style.fill {
+        push_paint(start, "fill", &style.fill);
+    }
+
+    if style.fill_opacity != parent_style.fill_opacity {
+        push_number(start, "fill-opacity", style.fill_opacity);
+    }
+
+    if style.stroke != parent_style.stroke {
+        push_paint(start, "stroke", &style.stroke);
+    }
+
+    if style.color != parent_style.color {
+        start.push_attribute(("color", color_value(style.color).as_str()));
+    }
+
+    if style.stroke_opacity != parent_style.stroke_opacity {
+        push_number(start, "stroke-opacity", style.stroke_opacity);
+    }
+
+    if style.stroke_width != parent_style.stroke_width {
+        push_number(start, "stroke-width", style.stroke_width);
+    }
+
+    if style.miter_limit != parent_style.miter_limit {
+        push_number(start, "stroke-miterlimit", style.miter_limit);
+    }
+
+    if style.fill_rule != parent_style.fill_rule {
+        push_keyword(start, "fill-rule", style.fill_rule, &FillRule::KEYWORDS);
+    }
+
+    if style.pointer_events != parent_style.pointer_events {
+        push_keyword(
+            start,
+            "pointer-events",
+            style.pointer_events,
+            &PointerEvents::KEYWORDS,
+        );
+    }
+
+    if style.transform != parent_style.transform {
+        // The style holds the transform accumulated from all the ancestors. The element itself
+        // only needs the part that is not already applied by its parent. A singular parent
+        // cannot be undone so the full transform is written instead.
+        let transform = match parent_style.transform.inverse() {
+            Some(inverse) => &inverse * &style.transform,
+            None => style.transform.clone(),
+        };
+        let matrix = format!(
+            "matrix({} {} {} {} {} {})",
+            transform[0][0],
+            transform[1][0],
+            transform[0][1],
+            transform[1][1],
+            transform[0][2],
+            transform[1][2],
+        );
+        start.push_attribute(("transform", matrix.as_str()));
+    }
+}
+
+fn push_keyword<T: PartialEq>(
+    start: &mut BytesStart,
+    name: &str,
+    value: T,
+    keywords: &[(&str, T)],
+) {
+    if let Some((keyword, _)) = keywords
+        .iter()
+        .find(|(_, keyword_value)| *keyword_value == value)
+    {
+        start.push_attribute((name, *keyword));
+    }
+}
+
+fn push_paint(start: &mut BytesStart, name: &str, paint: &Paint) {
+    start.push_attribute((name, paint_value(paint).as_str()));
+}
+
+fn paint_value(paint: &Paint) -> String {
+    match paint {
+        Paint::None => "none".to_owned(),
+        Paint::Color(color) => color_value(*color),
+        Paint::CurrentColor => "currentColor".to_owned(),
+        Paint::Server(id, None) => format!("url(#{})", id),
+        Paint::Server(id, Some(fallback)) => format!("url(#{}) {}", id, paint_value(fallback)),
+    }
+}
+
+fn color_value(color: Color) -> String {
+    if color.a == u8::MAX {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    } else {
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            color.r, color.g, color.b, color.a
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::svg::{read_from_str, ParseOptions};
+
+    const DOCUMENT: &str = r##"<svg width="120" height="80" xmlns="http://www.w3.org/2000/svg">
+  <title>Shapes &amp; more</title>
+  <metadata><rdf:RDF xmlns:rdf="urn:rdf"><rdf:Description about="a &lt; b"/></rdf:RDF></metadata>
+  <g id="group" class="a b" fill="#ff0000" fill-opacity="0.5" stroke="none" pointer-events="stroke" transform="translate(10 20) scale(2)">
+    <rect x="1" y="2" width="3" height="4" rx="1"><title>Rounded</title><desc>A rect with rounded corners</desc></rect>
+    <ellipse cx="5" cy="6" rx="7" ry="8" stroke="#00ff00" stroke-width="3"/>
+    <g transform="scale(0.5)" fill="none">
+      <line x1="0" y1="0" x2="1" y2="1" stroke="#0000ff80"/>
+    </g>
+    <g></g>
+  </g>
+  <polygon points="0,0 10,0 5,5" stroke-miterlimit="10" fill-opacity="0" fill-rule="evenodd"/>
+  <polyline points="0,0 10,10 20,0" fill="currentColor" stroke="url(#paint) #00ff0080" color="#123456" transform="translate(4)"/>
+  <rect x="7" y="8" width="0" height="0"/>
+  <image x="1" y="1" width="2" height="1" href="data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAYAAAD0In+KAAAADklEQVR4nGP4z8AAQg0AD3oDfnfpf5cAAAAASUVORK5CYII="/>
+  <svg id="nested" width="10" height="10">
+    <rect width="1" height="1"/>
+  </svg>
+</svg>"##;
+
+    fn round_trip(svg: &SVG, options: &WriteOptions) -> SVG {
+        let mut out = Vec::new();
+        svg.write_to_with_options(&mut out, options).unwrap();
+        read_from_str(&String::from_utf8(out).unwrap(), &ParseOptions::STRICT).unwrap()
+    }
+
+    #[test]
+    fn written_document_reads_back_the_same() {
+        let svg = read_from_str(DOCUMENT, &ParseOptions::STRICT).unwrap();
+        assert_eq!(round_trip(&svg, &WriteOptions::COMPACT), svg);
+    }
+
+    #[test]
+    fn pretty_document_reads_back_the_same() {
+        let svg = read_from_str(DOCUMENT, &ParseOptions::STRICT).unwrap();
+        assert_eq!(round_trip(&svg, &WriteOptions::PRETTY), svg);
+    }
+
+    #[test]
+    fn every_alpha_value_reads_back_the_same() {
+        for alpha in 0..=u8::MAX {
+            let mut svg = read_from_str(
+                r#"<svg><rect width="1" height="1"/></svg>"#,
+                &ParseOptions::STRICT,
+            )
+            .unwrap();
+            if let Some(Element::EmptyTag(EmptyTag::Rect(rect))) = svg.elements.first_mut() {
+                let style = std::sync::Arc::make_mut(&mut rect.style);
+                style.fill = Paint::Color(Color::RGBA(0, 0, 0, alpha));
+                style.stroke = Paint::Color(Color::RGBA(1, 2, 3, alpha));
+                style.stroke_opacity = alpha as f32 / u8::MAX as f32;
+            }
+
+            assert_eq!(round_trip(&svg, &WriteOptions::COMPACT), svg);
+        }
+    }
+
+    #[test]
+    fn only_changed_style_properties_are_written() {
+        let svg = read_from_str(
+            r##"<svg><g fill="#ff0000"><rect width="1" height="1" fill="#ff0000"/></g></svg>"##,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        svg.write_to(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="300" height="150">"#,
+                r##"<g fill="#ff0000"><rect x="0" y="0" width="1" height="1"/></g>"##,
+                r#"</svg>"#,
+            )
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::ops::Range;
+
+use crate::objects::svg::BoundingBox;
+
+/// A bounding volume hierarchy over a list of boxes, for finding the ones that overlap an area
+/// without looking at all of them.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    boxes: Vec<BoundingBox>,
+    // Indices of the boxes, ordered so that the boxes under any node are next to each other.
+    items: Vec<usize>,
+}
+
+struct Node {
+    bounds: BoundingBox,
+    // The part of `items` under this node.
+    items: Range<usize>,
+    // Indices of the two children in `nodes`, or None for a leaf.
+    children: Option<(usize, usize)>,
+}
+
+impl Bvh {
+    // Splitting nodes smaller than this costs more than testing each of their boxes.
+    const MAX_LEAF_SIZE: usize = 4;
+
+    /// Indexes `boxes`. Queries return positions in this slice.
+    pub fn new(boxes: &[BoundingBox]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            boxes: boxes.to_vec(),
+            items: (0..boxes.len()).collect(),
+        };
+
+        if !boxes.is_empty() {
+            bvh.nodes.reserve(2 * boxes.len() / Self::MAX_LEAF_SIZE + 1);
+            bvh.build(boxes, 0..boxes.len());
+        }
+
+        bvh
+    }
+
+    // Adds the node over `range` of the items and everything under it. Returns its index.
+    fn build(&mut self, boxes: &[BoundingBox], range: Range<usize>) -> usize {
+        let items = &mut self.items[range.clone()];
+        let bounds = items
+            .iter()
+            .skip(1)
+            .fold(boxes[items[0]].clone(), |bounds, item| {
+                bounds.union(&boxes[*item])
+            });
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds,
+            items: range.clone(),
+            children: None,
+        });
+
+        if range.len() <= Self::MAX_LEAF_SIZE {
+            return index;
+        }
+
+        // Split the longer side at the median center.
+        let size = self.nodes[index].bounds.size();
+        let axis = if size[0] >= size[1] { 0 } else { 1 };
+        let middle = range.len() / 2;
+        items.select_nth_unstable_by(middle, |a, b| {
+            boxes[*a].center()[axis].total_cmp(&boxes[*b].center()[axis])
+        });
+
+        let left = self.build(boxes, range.start..range.start + middle);
+        let right = self.build(boxes, range.start + middle..range.end);
+        self.nodes[index].children = Some((left, right));
+
+        index
+    }
+
+    /// Adds the position of every box that overlaps `area` to `found`, in no particular order.
+    pub fn query(&self, area: &BoundingBox, found: &mut Vec<usize>) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.intersects(area) {
+                continue;
+            }
+
+            // Everything under a node that is inside the area overlaps it.
+            if encloses(area, &node.bounds) {
+                found.extend_from_slice(&self.items[node.items.clone()]);
+                continue;
+            }
+
+            match node.children {
+                Some((left, right)) => stack.extend_from_slice(&[left, right]),
+                None => found.extend(
+                    self.items[node.items.clone()]
+                        .iter()
+                        .filter(|item| self.boxes[**item].intersects(area)),
+                ),
+            }
+        }
+    }
+}
+
+fn encloses(outer: &BoundingBox, inner: &BoundingBox) -> bool {
+    outer.contains(&inner.min) && outer.contains(&inner.max)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::svg::BoundingBox;
+
+    use super::Bvh;
+
+    fn bounds(x: f32, y: f32, width: f32, height: f32) -> BoundingBox {
+        BoundingBox {
+            min: [x, y].into(),
+            max: [x + width, y + height].into(),
+        }
+    }
+
+    #[test]
+    fn finds_the_same_boxes_as_testing_every_one() {
+        let boxes: Vec<BoundingBox> = (0..500)
+            .map(|index| {
+                let x = (index * 37 % 101) as f32;
+                let y = (index * 61 % 97) as f32;
+                bounds(x, y, (index % 7) as f32, (index % 5) as f32)
+            })
+            .collect();
+        let bvh = Bvh::new(&boxes);
+
+        for area in [
+            bounds(10.0, 10.0, 20.0, 5.0),
+            bounds(-50.0, -50.0, 300.0, 300.0),
+            bounds(50.0, 50.0, 0.0, 0.0),
+            bounds(200.0, 200.0, 10.0, 10.0),
+        ] {
+            let mut found = Vec::new();
+            bvh.query(&area, &mut found);
+            found.sort();
+
+            let expected: Vec<usize> = (0..boxes.len())
+                .filter(|index| boxes[*index].intersects(&area))
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn an_empty_index_finds_nothing() {
+        let mut found = Vec::new();
+        Bvh::new(&[]).query(&bounds(0.0, 0.0, 1.0, 1.0), &mut found);
+        assert!(found.is_empty());
+    }
+}
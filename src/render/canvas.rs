@@ -1,226 +1,352 @@
-use num_traits::ConstZero;
-use sdl2::{pixels::Color, render::WindowCanvas, video::Window, IntegerOrSdlError};
+use std::{ffi::c_int, ptr};
+
+use sdl2::{
+    pixels::Color,
+    rect,
+    render::{BlendMode, TextureAccess, WindowCanvas},
+    sys::{SDL_Color, SDL_FPoint, SDL_Vertex},
+    video::Window,
+    IntegerOrSdlError,
+};
 
 use crate::{
     matrix::Matrix3x3,
     objects::{
-        svg::{Element, EmptyTag, Line, Point, StartTag, SVG},
-        Object, ObjectMgr,
+        svg::{
+            Ellipse, Image, Line, Point, Polygon, Polyline, Rect, Style, Transform, VisitContext,
+            Visitor,
+        },
+        ObjectMgr,
+    },
+    render::{
+        element_cache::ElementCache, pixel_viewer::PixelViewer, sdl_texture::CanvasTexture,
+        stroke::stroke_outline, tessellation::CurveCache, triangulation, Renderer, Viewer,
     },
-    render::{Renderer, Viewer},
-    vector::{Vector2D, Vector3D},
+    vector::Vector2D,
 };
 
-struct CanvasViewer {
-    width_px: u32,
-    height_px: u32,
-    center: Vector2D<f32>,
-    zoom: f32,
-    norm_to_self_transform: Matrix3x3<f32>,
+pub struct CanvasRenderer {
+    // The textures come first so they are dropped before the canvas that made them.
+    //
+    // The texture of each image, or None if it could not be made.
+    images: ElementCache<Option<CanvasTexture>>,
+    // What translucent strokes are drawn into before they are blended into the window. It is as
+    // large as the window and made again when the window is resized.
+    stroke_layer: Option<CanvasTexture>,
+    canvas: WindowCanvas,
+    viewer: PixelViewer,
+    // Maps the world coordinates of the object being drawn to pixels.
+    object_to_pixel: Transform,
+    curves: CurveCache,
+    // The index of the object being drawn in the `ObjectMgr`.
+    object: usize,
 }
 
-impl Viewer for CanvasViewer {
-    fn center_on_object(&mut self, object: &Object) {
-        let object_radius = object.svg_inst.dimension.clone() * 0.5;
-        self.center[0] = object.position[0] + object_radius[0];
-        self.center[1] = object.position[1] + object_radius[1];
+impl CanvasRenderer {
+    pub fn new(window: Window) -> Result<Self, IntegerOrSdlError> {
+        let window_size = window.size();
+        let mut canvas = window.into_canvas().present_vsync().build()?;
+        println!("Drawing with the {} SDL renderer.", canvas.info().name);
+        canvas.set_blend_mode(BlendMode::Blend);
 
-        let zoom_x = self.width_px as f32 / object.svg_inst.dimension[0];
-        let zoom_y = self.height_px as f32 / object.svg_inst.dimension[1];
+        Ok(Self {
+            images: ElementCache::default(),
+            stroke_layer: None,
+            canvas,
+            viewer: PixelViewer::new(window_size.0, window_size.1),
+            object_to_pixel: Matrix3x3::IDENTITY3X3,
+            curves: CurveCache::default(),
+            object: 0,
+        })
+    }
 
-        self.zoom = zoom_x.min(zoom_y);
+    fn local_to_pixel(&self, style: &Style) -> Transform {
+        &self.object_to_pixel * &style.transform
+    }
 
-        if self.zoom.is_infinite() {
-            self.zoom = 1.0;
+    fn to_pixel(&self, style: &Style, x: f32, y: f32) -> SDL_FPoint {
+        let t = self.local_to_pixel(style);
+        SDL_FPoint {
+            x: t[0][0] * x + t[0][1] * y + t[0][2],
+            y: t[1][0] * x + t[1][1] * y + t[1][2],
         }
-
-        self.update_norm_to_self_transform();
     }
 
-    fn move_to_world_coords(&mut self, new_center: Vector2D<f32>) {
-        self.center = new_center;
-        self.update_norm_to_self_transform();
-    }
+    fn render_point(&mut self, point: &Point) {
+        let Some(color) = point.style.fill_rgba() else {
+            return;
+        };
+        self.canvas.set_draw_color(color);
 
-    fn move_by_world_coords(&mut self, delta_x: f32, delta_y: f32) {
-        let delta_center = Vector2D::from([delta_x, delta_y]);
-        self.center += delta_center * (1.0 / self.zoom);
-        self.update_norm_to_self_transform();
+        let draw_position = self.to_pixel(&point.style, point.position[0], point.position[1]);
+        self.canvas
+            .draw_fpoint(sdl2::rect::FPoint::new(draw_position.x, draw_position.y))
+            .unwrap();
     }
 
-    fn move_by_pixels(&mut self, delta_x: f32, delta_y: f32) {
-        self.move_by_world_coords(delta_x, delta_y)
+    fn render_line(&mut self, line: &Line) {
+        self.render_stroke(
+            &line.style,
+            &[[line.from[0], line.from[1]], [line.to[0], line.to[1]]],
+            false,
+        );
     }
 
-    fn zoom_to(&mut self, new_zoom: f32) {
-        self.zoom = new_zoom;
-        self.update_norm_to_self_transform();
-    }
+    fn render_shape(&mut self, style: &Style, points: &[Vector2D<f32>], closed: bool) {
+        if let Some(color) = style.fill_rgba() {
+            // Shapes that cross themselves cannot be triangulated and are only outlined.
+            if let Some(triangles) = triangulation::triangulate(points) {
+                let vertices: Vec<SDL_Vertex> = points
+                    .iter()
+                    .map(|point| vertex(self.to_pixel(style, point[0], point[1]), color))
+                    .collect();
+                let indices: Vec<c_int> = triangles
+                    .iter()
+                    .flatten()
+                    .map(|index| *index as c_int)
+                    .collect();
+                render_geometry(&mut self.canvas, None, &vertices, &indices).unwrap();
+            }
+        }
 
-    fn zoom_by(&mut self, zoom_modifier: f32) {
-        self.zoom *= zoom_modifier;
-        self.update_norm_to_self_transform();
+        let points: Vec<[f32; 2]> = points.iter().map(|point| [point[0], point[1]]).collect();
+        self.render_stroke(style, &points, closed);
     }
-}
 
-impl CanvasViewer {
-    fn new(width_px: u32, height_px: u32) -> Self {
-        const DEFAULT_CENTER: Vector2D<f32> = Vector2D::ZERO;
-        const DEFAULT_ZOOM: f32 = 1.0;
-        Self {
-            width_px,
-            height_px,
-            center: DEFAULT_CENTER,
-            zoom: DEFAULT_ZOOM,
-            norm_to_self_transform: Self::generate_norm_to_self_transform(
-                &DEFAULT_CENTER,
-                DEFAULT_ZOOM,
-                width_px,
-                height_px,
-            ),
+    // The pieces of a stroke overlap, and a translucent stroke would show darker where they do.
+    // Like the software renderer, which fills all the pieces as one path, the pieces are drawn
+    // opaque into a layer which is then blended into the window once.
+    fn render_stroke(&mut self, style: &Style, points: &[[f32; 2]], closed: bool) {
+        let Some(stroke_color) = style.stroke_rgba() else {
+            return;
+        };
+        let color = Color::RGBA(stroke_color.r, stroke_color.g, stroke_color.b, u8::MAX);
+
+        let mut vertices: Vec<SDL_Vertex> = Vec::new();
+        let mut indices: Vec<c_int> = Vec::new();
+        for piece in stroke_outline(points, closed, style) {
+            // Every piece is convex so it can be drawn as a fan around its first point.
+            let first = vertices.len() as c_int;
+            for index in 1..piece.len() as c_int - 1 {
+                indices.extend_from_slice(&[first, first + index, first + index + 1]);
+            }
+            vertices.extend(
+                piece
+                    .iter()
+                    .map(|point| vertex(self.to_pixel(style, point[0], point[1]), color)),
+            );
         }
-    }
 
-    fn resize(&mut self, new_width: u32, new_height: u32) {
-        self.width_px = new_width;
-        self.height_px = new_height;
-        self.update_norm_to_self_transform();
-    }
+        if indices.is_empty() {
+            return;
+        }
 
-    fn norm_to_viewer(&self, position: &Vector2D<f32>) -> Vector2D<f32> {
-        let transformed = Vector3D::from_vector(position) * &self.norm_to_self_transform;
-        Vector2D::from_vector(&transformed)
+        let alpha = stroke_color.a;
+        if alpha == u8::MAX || self.render_layered(&vertices, &indices, alpha).is_err() {
+            // Renderers that cannot draw into textures get the darker overlaps.
+            for vertex in vertices.iter_mut() {
+                vertex.color.a = alpha;
+            }
+            render_geometry(&mut self.canvas, None, &vertices, &indices).unwrap();
+        }
     }
 
-    fn generate_norm_to_self_transform(
-        center: &Vector2D<f32>,
-        zoom: f32,
-        width_px: u32,
-        height_px: u32,
-    ) -> Matrix3x3<f32> {
-        // Translate to viewer position
-        let mut position_matrix = Matrix3x3::IDENTITY3X3;
-        position_matrix[2][0] = -center[0];
-        position_matrix[2][1] = -center[1];
-
-        // Zoom the appropriate amount
-        let mut zoom_matrix = Matrix3x3::IDENTITY3X3;
-        zoom_matrix[0][0] = zoom;
-        zoom_matrix[1][1] = zoom;
-
-        // Move origin to center of the viewer
-        let mut center_matrix = Matrix3x3::IDENTITY3X3;
-        center_matrix[2][0] = width_px as f32 / 2.0;
-        center_matrix[2][1] = height_px as f32 / 2.0;
-
-        &position_matrix * &zoom_matrix * &center_matrix
-    }
+    // Draws the opaque triangles into the stroke layer and blends the layer into the window with
+    // `alpha`, so every pixel they cover is drawn once.
+    fn render_layered(
+        &mut self,
+        vertices: &[SDL_Vertex],
+        indices: &[c_int],
+        alpha: u8,
+    ) -> Result<(), String> {
+        let (width, height) = (self.viewer.width(), self.viewer.height());
+        let (mut left, mut top) = (f32::INFINITY, f32::INFINITY);
+        let (mut right, mut bottom) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for vertex in vertices {
+            left = left.min(vertex.position.x);
+            top = top.min(vertex.position.y);
+            right = right.max(vertex.position.x);
+            bottom = bottom.max(vertex.position.y);
+        }
+        let left = left.floor().max(0.0) as i32;
+        let top = top.floor().max(0.0) as i32;
+        let right = right.ceil().min(width as f32) as i32;
+        let bottom = bottom.ceil().min(height as f32) as i32;
+        if left >= right || top >= bottom {
+            return Ok(());
+        }
+        let area = rect::Rect::new(left, top, (right - left) as u32, (bottom - top) as u32);
 
-    fn update_norm_to_self_transform(&mut self) {
-        self.norm_to_self_transform = Self::generate_norm_to_self_transform(
-            &self.center,
-            self.zoom,
-            self.width_px,
-            self.height_px,
-        );
+        if self.stroke_layer.is_none() {
+            let mut layer = CanvasTexture::new(&self.canvas, TextureAccess::Target, width, height)?;
+            layer.set_blending(true)?;
+            self.stroke_layer = Some(layer);
+        }
+        let layer = self.stroke_layer.as_mut().unwrap();
+
+        layer.draw_into(&mut self.canvas, |canvas| {
+            canvas.set_blend_mode(BlendMode::None);
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, 0));
+            let cleared = canvas.fill_rect(area);
+            canvas.set_blend_mode(BlendMode::Blend);
+            cleared?;
+            render_geometry(canvas, None, vertices, indices)
+        })?;
+        layer.set_alpha_mod(alpha)?;
+        layer.copy(&mut self.canvas, Some(area))
+    }
+
+    fn render_image(&mut self, image: &Image, path: &[usize]) {
+        let texture = &image.texture;
+        if image.width <= 0.0
+            || image.height <= 0.0
+            || texture.width() == 0
+            || texture.height() == 0
+        {
+            return;
+        }
+
+        let corners = [
+            (image.x, image.y, 0.0, 0.0),
+            (image.x + image.width, image.y, 1.0, 0.0),
+            (image.x + image.width, image.y + image.height, 1.0, 1.0),
+            (image.x, image.y + image.height, 0.0, 1.0),
+        ];
+        let vertices: Vec<SDL_Vertex> = corners
+            .iter()
+            .map(|(x, y, u, v)| SDL_Vertex {
+                tex_coord: SDL_FPoint { x: *u, y: *v },
+                ..vertex(self.to_pixel(&image.style, *x, *y), Color::WHITE)
+            })
+            .collect();
+
+        // Images are uploaded the first time they are drawn and kept until they are edited.
+        let canvas = &self.canvas;
+        let cached = self.images.get_or_insert_with(self.object, path, || {
+            let mut sdl_texture = CanvasTexture::new(
+                canvas,
+                TextureAccess::Static,
+                texture.width(),
+                texture.height(),
+            )
+            .ok()?;
+            sdl_texture.set_blending(true).ok()?;
+            sdl_texture.update(&texture.to_rgba8()).ok()?;
+            Some(sdl_texture)
+        });
+        let Some(sdl_texture) = cached.as_ref() else {
+            return;
+        };
+
+        render_geometry(
+            &mut self.canvas,
+            Some(sdl_texture),
+            &vertices,
+            &[0, 1, 2, 0, 2, 3],
+        )
+        .unwrap();
     }
 }
 
-pub struct CanvasRenderer<'a> {
-    canvas: WindowCanvas,
-    object_mgr: &'a ObjectMgr,
-    viewer: CanvasViewer,
+fn vertex(position: SDL_FPoint, color: impl Into<Color>) -> SDL_Vertex {
+    let color: Color = color.into();
+    SDL_Vertex {
+        position,
+        color: SDL_Color {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        },
+        tex_coord: SDL_FPoint { x: 0.0, y: 0.0 },
+    }
 }
 
-impl<'a> CanvasRenderer<'a> {
-    pub fn new(window: Window, object_mgr: &'a ObjectMgr) -> Result<Self, IntegerOrSdlError> {
-        let window_size = window.size();
-        Ok(Self {
-            canvas: window.into_canvas().present_vsync().build()?,
-            object_mgr,
-            viewer: CanvasViewer::new(window_size.0, window_size.1),
-        })
+// Draws triangles made of the vertices at every three indices. The sdl2 crate does not wrap
+// SDL_RenderGeometry yet.
+fn render_geometry(
+    canvas: &mut WindowCanvas,
+    texture: Option<&CanvasTexture>,
+    vertices: &[SDL_Vertex],
+    indices: &[c_int],
+) -> Result<(), String> {
+    let result = unsafe {
+        sdl2::sys::SDL_RenderGeometry(
+            canvas.raw(),
+            texture.map_or(ptr::null_mut(), |texture| texture.raw()),
+            vertices.as_ptr(),
+            vertices.len() as c_int,
+            indices.as_ptr(),
+            indices.len() as c_int,
+        )
+    };
+
+    if result != 0 {
+        return Err(sdl2::get_error());
     }
+    Ok(())
+}
 
-    fn render_svg(&mut self, svg_object: &SVG) {
-        for element in svg_object.elements.iter() {
-            self.render_element(element);
-        }
+impl Visitor for CanvasRenderer {
+    fn visit_ellipse(&mut self, ellipse: &Ellipse, ctx: &VisitContext) {
+        let local_to_pixel = self.local_to_pixel(&ellipse.style);
+        let outline = self
+            .curves
+            .ellipse(self.object, ctx.path, ellipse, &local_to_pixel);
+        self.render_shape(&ellipse.style, &outline, true);
     }
 
-    fn render_element(&mut self, element: &Element) {
-        match element {
-            Element::StartTag(start_tag) => self.render_tag_group(start_tag),
-            Element::EmptyTag(empty_tag) => self.render_empty_tag(empty_tag),
-            Element::EndTag(_) => (),
-        }
+    fn visit_image(&mut self, image: &Image, ctx: &VisitContext) {
+        self.render_image(image, ctx.path);
     }
 
-    fn render_tag_group(&mut self, tag_group: &StartTag) {
-        match tag_group {
-            StartTag::Group(group) => {
-                for element in group.elements.iter() {
-                    self.render_element(element);
-                }
-            }
-            StartTag::SVG(svg_object) => self.render_svg(svg_object),
-        }
+    fn visit_line(&mut self, line: &Line, _ctx: &VisitContext) {
+        self.render_line(line);
     }
 
-    fn render_empty_tag(&mut self, empty_tag: &EmptyTag) {
-        match empty_tag {
-            EmptyTag::Ellipse(_ellipse) => unimplemented!(),
-            EmptyTag::Image(_image) => unimplemented!(),
-            EmptyTag::Line(line) => self.render_line(line),
-            EmptyTag::Point(point) => self.render_point(point),
-            EmptyTag::Polygon(_polygon) => unimplemented!(),
-            EmptyTag::Polyline(_polyline) => unimplemented!(),
-            EmptyTag::Rect(_rect) => unimplemented!(),
-        }
+    fn visit_point(&mut self, point: &Point, _ctx: &VisitContext) {
+        self.render_point(point);
     }
 
-    fn render_point(&mut self, point: &Point) {
-        self.canvas.set_draw_color(point.style.fill_color);
+    fn visit_polygon(&mut self, polygon: &Polygon, _ctx: &VisitContext) {
+        self.render_shape(&polygon.style, &polygon.points, true);
+    }
 
-        let draw_position = self.viewer.norm_to_viewer(&point.position);
-        self.canvas
-            .draw_fpoint(sdl2::rect::FPoint::new(
-                draw_position[0] as f32,
-                draw_position[1] as f32,
-            ))
-            .unwrap();
+    fn visit_polyline(&mut self, polyline: &Polyline, _ctx: &VisitContext) {
+        self.render_shape(&polyline.style, &polyline.points, false);
     }
 
-    fn render_line(&mut self, line: &Line) {
-        self.canvas.set_draw_color(line.style.fill_color);
+    fn visit_rect(&mut self, rect: &Rect, ctx: &VisitContext) {
+        // A rect without an area is not drawn at all, not even its stroke.
+        if rect.width <= 0.0 || rect.height <= 0.0 {
+            return;
+        }
 
-        let from_position = self.viewer.norm_to_viewer(&line.from);
-        let to_position = self.viewer.norm_to_viewer(&line.to);
-        self.canvas
-            .draw_fline(
-                sdl2::rect::FPoint::new(from_position[0] as f32, from_position[1] as f32),
-                sdl2::rect::FPoint::new(to_position[0] as f32, to_position[1] as f32),
-            )
-            .unwrap();
+        let local_to_pixel = self.local_to_pixel(&rect.style);
+        let outline = self
+            .curves
+            .rect(self.object, ctx.path, rect, &local_to_pixel);
+        self.render_shape(&rect.style, &outline, true);
     }
 }
 
-impl<'a> Renderer for CanvasRenderer<'a> {
+impl Renderer for CanvasRenderer {
     fn get_viewer(&mut self) -> &mut dyn Viewer {
         &mut self.viewer
     }
 
     fn height(&self) -> u32 {
-        self.viewer.height_px
+        self.viewer.height()
     }
 
     fn width(&self) -> u32 {
-        self.viewer.width_px
+        self.viewer.width()
     }
 
     fn resize_window(&mut self, mut new_width: u32, mut new_height: u32) {
         super::bound_window_size(&mut new_width, &mut new_height);
         self.viewer.resize(new_width, new_height);
+        self.stroke_layer = None;
         self.canvas
             .window_mut()
             .set_size(new_width, new_height)
@@ -232,163 +358,35 @@ impl<'a> Renderer for CanvasRenderer<'a> {
         self.canvas.clear();
     }
 
-    fn render_objects(&mut self) {
-        for object in self.object_mgr.get_objects() {
-            self.render_svg(&object.svg_inst);
+    // The canvas draws straight from the documents, only the outlines of curves and the textures
+    // of images are kept.
+    fn update_objects(&mut self, object_mgr: &mut ObjectMgr) {
+        for (index, object) in object_mgr.get_objects_mut().iter_mut().enumerate() {
+            let changes = object.svg_inst.take_changes();
+            self.curves.forget_changes(index, &changes);
+            self.images.forget_changes(index, &changes);
         }
     }
 
-    fn present(&mut self) {
-        self.canvas.present();
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::{
-        objects::{svg::SVG, Object},
-        render::Viewer,
-        vector::{Vector2D, Vector3D},
-    };
-
-    use super::CanvasViewer;
-
-    fn new_viewer() -> CanvasViewer {
-        CanvasViewer::new(100, 100)
-    }
-
-    #[test]
-    fn init_at_origin() {
-        let viewer = new_viewer();
-        assert_eq!(viewer.center, Vector2D::from([0.0, 0.0]));
-    }
-
-    #[test]
-    fn pixels_at_viewer_center_map_to_the_screen_center() {
-        let viewer = CanvasViewer::new(100, 100);
-        assert_eq!(
-            viewer.norm_to_viewer(&viewer.center),
-            Vector2D::from([50.0, 50.0])
-        );
-    }
-
-    #[test]
-    fn pixels_at_screen_center_are_unaffected_by_zoom() {
-        let mut viewer = new_viewer();
-        let pixel_mapping_before_zoom = viewer.norm_to_viewer(&viewer.center);
-        viewer.zoom_by(2.0);
-        let pixel_mapping_after_zoom = viewer.norm_to_viewer(&viewer.center);
-        assert_eq!(pixel_mapping_before_zoom, pixel_mapping_after_zoom);
-    }
-
-    #[test]
-    fn zoom_value_of_1_does_not_change_position_norm() {
-        const ZOOM_AMOUNT: f32 = 1.0;
-
-        let mut viewer = new_viewer();
-        let screen_center = viewer.norm_to_viewer(&viewer.center);
-        viewer.zoom_to(ZOOM_AMOUNT);
-
-        let pixel = Vector2D::from([3.0, 4.0]);
-        let position_norm_before_mapping = pixel.get_norm();
-        let position_norm_after_mapping =
-            (viewer.norm_to_viewer(&pixel) - screen_center).get_norm();
-
-        assert_eq!(position_norm_before_mapping, position_norm_after_mapping);
-    }
-
-    #[test]
-    fn zooming_moves_pixels_away_from_the_screen_center_by_the_same_amount() {
-        const ZOOM_AMOUNT: f32 = 3.77;
-
-        let mut viewer = new_viewer();
-        let screen_center = viewer.norm_to_viewer(&viewer.center);
-
-        let pixel = Vector2D::from([3.0, 4.0]);
-        let position_norm_before_zoom = (viewer.norm_to_viewer(&pixel) - &screen_center).get_norm();
-
-        viewer.zoom_by(ZOOM_AMOUNT);
+    fn render_objects(&mut self, object_mgr: &ObjectMgr) {
+        let world_to_pixel = self
+            .viewer
+            .get_norm_to_viewer()
+            .clone()
+            .transpose_symmetric();
 
-        let position_norm_after_zoom = (viewer.norm_to_viewer(&pixel) - &screen_center).get_norm();
+        for (index, object) in object_mgr.get_objects().iter().enumerate() {
+            let mut position = Matrix3x3::IDENTITY3X3;
+            position[0][2] = object.position[0];
+            position[1][2] = object.position[1];
 
-        assert_eq!(
-            position_norm_before_zoom * ZOOM_AMOUNT,
-            position_norm_after_zoom
-        );
-    }
-
-    #[test]
-    fn viewer_centers_on_a_given_object() {
-        let mut viewer = new_viewer();
-        let object = Object {
-            position: Vector3D::from([4.0, -3.0, 1.0]),
-            svg_inst: SVG {
-                dimension: Vector2D::from([20.0, 20.0]),
-                elements: Vec::new(),
-            },
-        };
-
-        viewer.center_on_object(&object);
-
-        assert_eq!(
-            viewer.center,
-            Vector2D::from([(20.0 / 2.0) + 4.0, (20.0 / 2.0) - 3.0])
-        );
-    }
-
-    #[test]
-    fn viewer_zooms_to_largest_dimension_of_object() {
-        let mut viewer = CanvasViewer::new(100, 100);
-        let object = Object {
-            position: Vector3D::from([4.0, -3.0, 1.0]),
-            svg_inst: SVG {
-                dimension: Vector2D::from([10.0, 25.0]),
-                elements: Vec::new(),
-            },
-        };
-
-        viewer.center_on_object(&object);
-
-        assert_eq!(viewer.zoom, 100.0 / 25.0)
-    }
-
-    #[test]
-    fn viewer_shouldnt_zoom_infinitely_when_object_size_is_zero() {
-        let mut viewer = new_viewer();
-        let object = Object {
-            position: Vector3D::from([4.0, -3.0, 1.0]),
-            svg_inst: SVG {
-                dimension: Vector2D::from([0.0, 0.0]),
-                elements: Vec::new(),
-            },
-        };
-
-        viewer.center_on_object(&object);
-
-        assert_ne!(viewer.zoom, f32::INFINITY)
-    }
-
-    #[test]
-    fn viewer_centers_itself_on_position_to_move_to() {
-        let mut viewer = new_viewer();
-        let new_center = Vector2D::from([5.0, -5.0]);
-
-        viewer.move_to_world_coords(new_center.clone());
-
-        assert_eq!(viewer.center, new_center);
+            self.object_to_pixel = &world_to_pixel * &position;
+            self.object = index;
+            object.svg_inst.walk(self);
+        }
     }
 
-    #[test]
-    fn viewer_moves_by_amount_specified_divided_by_zoom() {
-        const ZOOM_AMOUNT: f32 = 5.0;
-        let delta_position = Vector2D::from([5.0, -5.0]);
-
-        let mut viewer = new_viewer();
-        viewer.zoom_to(ZOOM_AMOUNT);
-
-        viewer.move_by_world_coords(delta_position[0], delta_position[1]);
-        let center_after_move = viewer.center.clone();
-
-        assert_eq!(delta_position * (1.0 / ZOOM_AMOUNT), center_after_move);
+    fn present(&mut self) {
+        self.canvas.present();
     }
 }
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use crate::objects::svg::{Change, ElementPath};
+
+/// Something kept for each element of every document, until the element is edited.
+pub struct ElementCache<T> {
+    // The values of each object in the `ObjectMgr`, by the path of their element.
+    objects: Vec<HashMap<ElementPath, T>>,
+}
+
+impl<T> Default for ElementCache<T> {
+    fn default() -> Self {
+        Self {
+            objects: Vec::new(),
+        }
+    }
+}
+
+impl<T> ElementCache<T> {
+    /// Forgets the values of the elements that `changes` touched in object number `object`.
+    pub fn forget_changes(&mut self, object: usize, changes: &[Change]) {
+        let Some(values) = self.objects.get_mut(object) else {
+            return;
+        };
+
+        for change in changes {
+            // The siblings that follow an inserted or removed element move to another path, so
+            // everything under its parent is forgotten.
+            let changed = match change {
+                Change::Modified(path) => path.as_slice(),
+                Change::Inserted(path) | Change::Removed(path) => {
+                    &path[..path.len().saturating_sub(1)]
+                }
+            };
+            values.retain(|path, _| !path.starts_with(changed));
+        }
+    }
+
+    /// The value of the element at `path` in object number `object`, made with `make` if there is
+    /// none yet.
+    pub fn get_or_insert_with(
+        &mut self,
+        object: usize,
+        path: &[usize],
+        make: impl FnOnce() -> T,
+    ) -> &mut T {
+        if self.objects.len() <= object {
+            self.objects.resize_with(object + 1, HashMap::new);
+        }
+
+        let values = &mut self.objects[object];
+        if !values.contains_key(path) {
+            values.insert(path.to_vec(), make());
+        }
+        values.get_mut(path).unwrap()
+    }
+}
@@ -0,0 +1,110 @@
+//! The few EGL functions needed to make an OpenGL context without a window.
+
+use core::ffi::{c_char, c_void};
+use std::ffi::{CStr, CString};
+
+pub type EGLBoolean = u32;
+pub type EGLenum = u32;
+pub type EGLint = i32;
+pub type EGLAttrib = isize;
+pub type EGLDisplay = *mut c_void;
+pub type EGLConfig = *mut c_void;
+pub type EGLContext = *mut c_void;
+pub type EGLSurface = *mut c_void;
+
+pub const EGL_FALSE: EGLBoolean = 0;
+pub const EGL_NONE: EGLint = 0x3038;
+pub const EGL_EXTENSIONS: EGLint = 0x3055;
+
+pub const EGL_ALPHA_SIZE: EGLint = 0x3021;
+pub const EGL_BLUE_SIZE: EGLint = 0x3022;
+pub const EGL_GREEN_SIZE: EGLint = 0x3023;
+pub const EGL_RED_SIZE: EGLint = 0x3024;
+pub const EGL_SURFACE_TYPE: EGLint = 0x3033;
+pub const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+pub const EGL_HEIGHT: EGLint = 0x3056;
+pub const EGL_WIDTH: EGLint = 0x3057;
+pub const EGL_PBUFFER_BIT: EGLint = 0x0001;
+pub const EGL_OPENGL_BIT: EGLint = 0x0008;
+
+pub const EGL_OPENGL_API: EGLenum = 0x30A2;
+pub const EGL_CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+pub const EGL_CONTEXT_MINOR_VERSION: EGLint = 0x30FB;
+pub const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30FD;
+pub const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x0001;
+
+/// From `EGL_MESA_platform_surfaceless`. Renders on the GPU, or on the CPU with llvmpipe, without
+/// any display server.
+pub const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31DD;
+
+#[link(name = "EGL")]
+extern "C" {
+    pub fn eglGetDisplay(display_id: *mut c_void) -> EGLDisplay;
+    pub fn eglGetPlatformDisplay(
+        platform: EGLenum,
+        native_display: *mut c_void,
+        attrib_list: *const EGLAttrib,
+    ) -> EGLDisplay;
+    pub fn eglInitialize(display: EGLDisplay, major: *mut EGLint, minor: *mut EGLint)
+        -> EGLBoolean;
+    pub fn eglQueryString(display: EGLDisplay, name: EGLint) -> *const c_char;
+    pub fn eglBindAPI(api: EGLenum) -> EGLBoolean;
+    pub fn eglChooseConfig(
+        display: EGLDisplay,
+        attrib_list: *const EGLint,
+        configs: *mut EGLConfig,
+        config_size: EGLint,
+        num_config: *mut EGLint,
+    ) -> EGLBoolean;
+    pub fn eglCreateContext(
+        display: EGLDisplay,
+        config: EGLConfig,
+        share_context: EGLContext,
+        attrib_list: *const EGLint,
+    ) -> EGLContext;
+    pub fn eglDestroyContext(display: EGLDisplay, context: EGLContext) -> EGLBoolean;
+    pub fn eglCreatePbufferSurface(
+        display: EGLDisplay,
+        config: EGLConfig,
+        attrib_list: *const EGLint,
+    ) -> EGLSurface;
+    pub fn eglDestroySurface(display: EGLDisplay, surface: EGLSurface) -> EGLBoolean;
+    pub fn eglMakeCurrent(
+        display: EGLDisplay,
+        draw: EGLSurface,
+        read: EGLSurface,
+        context: EGLContext,
+    ) -> EGLBoolean;
+    pub fn eglGetProcAddress(procname: *const c_char) -> *const c_void;
+    pub fn eglGetError() -> EGLint;
+}
+
+/// Whether `display` lists `extension` among its extensions.
+///
+/// # Safety
+///
+/// `display` has to be initialized.
+pub unsafe fn has_extension(display: EGLDisplay, extension: &str) -> bool {
+    let extensions = eglQueryString(display, EGL_EXTENSIONS);
+    if extensions.is_null() {
+        return false;
+    }
+
+    CStr::from_ptr(extensions)
+        .to_string_lossy()
+        .split_ascii_whitespace()
+        .any(|name| name == extension)
+}
+
+/// Looks up an OpenGL function for `gl::load_with`.
+pub fn get_proc_address(name: &str) -> *const c_void {
+    match CString::new(name) {
+        Ok(name) => unsafe { eglGetProcAddress(name.as_ptr()) },
+        Err(_) => std::ptr::null(),
+    }
+}
+
+/// Describes the last EGL error.
+pub fn last_error() -> String {
+    format!("EGL error {:#x}", unsafe { eglGetError() })
+}
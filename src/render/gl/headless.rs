@@ -0,0 +1,580 @@
+use std::ptr;
+
+use gl::types::{GLsizei, GLuint};
+
+use crate::{
+    objects::{svg::Limits, ObjectMgr},
+    render::{
+        gl::{
+            egl::{self, EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLint},
+            GLSceneRenderer, GLViewer, GLViewport,
+        },
+        Renderer, Viewer,
+    },
+};
+
+// Same as the windows the viewer opens.
+const MULTISAMPLE_SAMPLES: GLsizei = 4;
+
+/// An OpenGL 3.3 core context made through EGL, without a window or a display server.
+///
+/// It prefers Mesa's surfaceless platform and otherwise uses the default display with a tiny
+/// pbuffer. Either way, drawing has to go into a framebuffer object. The context is made current
+/// on the thread that creates it and the GL functions are loaded from it.
+///
+/// EGL hands out the same display to everything in the process that asks for it, so the display
+/// is left initialized for the others. Only the context and surface are destroyed.
+pub struct HeadlessGLContext {
+    display: EGLDisplay,
+    context: EGLContext,
+    surface: EGLSurface,
+}
+
+impl HeadlessGLContext {
+    pub fn new() -> Result<Self, String> {
+        unsafe {
+            let display = Self::initialize_display()?;
+            let (context, surface) = Self::create_context(display)?;
+
+            gl::load_with(egl::get_proc_address);
+            Ok(Self {
+                display,
+                context,
+                surface,
+            })
+        }
+    }
+
+    unsafe fn initialize_display() -> Result<EGLDisplay, String> {
+        let surfaceless = egl::eglGetPlatformDisplay(
+            egl::EGL_PLATFORM_SURFACELESS_MESA,
+            ptr::null_mut(),
+            ptr::null(),
+        );
+        if !surfaceless.is_null()
+            && egl::eglInitialize(surfaceless, ptr::null_mut(), ptr::null_mut()) != egl::EGL_FALSE
+        {
+            return Ok(surfaceless);
+        }
+
+        let display = egl::eglGetDisplay(ptr::null_mut());
+        if display.is_null() {
+            return Err(String::from("No EGL display is available"));
+        }
+        if egl::eglInitialize(display, ptr::null_mut(), ptr::null_mut()) == egl::EGL_FALSE {
+            return Err(format!(
+                "Could not initialize the EGL display: {}",
+                egl::last_error()
+            ));
+        }
+
+        Ok(display)
+    }
+
+    unsafe fn create_context(display: EGLDisplay) -> Result<(EGLContext, EGLSurface), String> {
+        if egl::eglBindAPI(egl::EGL_OPENGL_API) == egl::EGL_FALSE {
+            return Err(format!("OpenGL is not supported: {}", egl::last_error()));
+        }
+
+        let surfaceless = egl::has_extension(display, "EGL_KHR_surfaceless_context");
+        // Without a surface type, EGL only looks for configs that can draw to windows.
+        let config_attributes = [
+            egl::EGL_SURFACE_TYPE,
+            egl::EGL_PBUFFER_BIT,
+            egl::EGL_RENDERABLE_TYPE,
+            egl::EGL_OPENGL_BIT,
+            egl::EGL_RED_SIZE,
+            8,
+            egl::EGL_GREEN_SIZE,
+            8,
+            egl::EGL_BLUE_SIZE,
+            8,
+            egl::EGL_ALPHA_SIZE,
+            8,
+            egl::EGL_NONE,
+        ];
+
+        let mut config: EGLConfig = ptr::null_mut();
+        let mut num_configs: EGLint = 0;
+        if egl::eglChooseConfig(
+            display,
+            config_attributes.as_ptr(),
+            &mut config,
+            1,
+            &mut num_configs,
+        ) == egl::EGL_FALSE
+            || num_configs < 1
+        {
+            return Err(String::from("No EGL config supports OpenGL"));
+        }
+
+        let context_attributes = [
+            egl::EGL_CONTEXT_MAJOR_VERSION,
+            3,
+            egl::EGL_CONTEXT_MINOR_VERSION,
+            3,
+            egl::EGL_CONTEXT_OPENGL_PROFILE_MASK,
+            egl::EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+            egl::EGL_NONE,
+        ];
+        let context = egl::eglCreateContext(
+            display,
+            config,
+            ptr::null_mut(),
+            context_attributes.as_ptr(),
+        );
+        if context.is_null() {
+            return Err(format!(
+                "Could not create an OpenGL 3.3 core context: {}",
+                egl::last_error()
+            ));
+        }
+
+        let surface = if surfaceless {
+            ptr::null_mut()
+        } else {
+            let surface_attributes = [egl::EGL_WIDTH, 1, egl::EGL_HEIGHT, 1, egl::EGL_NONE];
+            let surface =
+                egl::eglCreatePbufferSurface(display, config, surface_attributes.as_ptr());
+            if surface.is_null() {
+                egl::eglDestroyContext(display, context);
+                return Err(format!(
+                    "Could not create a pbuffer surface: {}",
+                    egl::last_error()
+                ));
+            }
+            surface
+        };
+
+        if egl::eglMakeCurrent(display, surface, surface, context) == egl::EGL_FALSE {
+            if !surface.is_null() {
+                egl::eglDestroySurface(display, surface);
+            }
+            egl::eglDestroyContext(display, context);
+            return Err(format!(
+                "Could not make the context current: {}",
+                egl::last_error()
+            ));
+        }
+
+        Ok((context, surface))
+    }
+}
+
+impl Drop for HeadlessGLContext {
+    fn drop(&mut self) {
+        unsafe {
+            egl::eglMakeCurrent(
+                self.display,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            if !self.surface.is_null() {
+                egl::eglDestroySurface(self.display, self.surface);
+            }
+            egl::eglDestroyContext(self.display, self.context);
+        }
+    }
+}
+
+// A framebuffer object with a single color renderbuffer.
+struct Framebuffer {
+    framebuffer: GLuint,
+    renderbuffer: GLuint,
+}
+
+impl Framebuffer {
+    unsafe fn new(width: u32, height: u32, samples: GLsizei) -> Result<Self, String> {
+        let mut framebuffer = 0;
+        let mut renderbuffer = 0;
+        gl::GenFramebuffers(1, &mut framebuffer);
+        gl::GenRenderbuffers(1, &mut renderbuffer);
+
+        gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+        gl::RenderbufferStorageMultisample(
+            gl::RENDERBUFFER,
+            samples,
+            gl::RGBA8,
+            width as GLsizei,
+            height as GLsizei,
+        );
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+        gl::FramebufferRenderbuffer(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            gl::RENDERBUFFER,
+            renderbuffer,
+        );
+
+        let framebuffer = Self {
+            framebuffer,
+            renderbuffer,
+        };
+        let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(format!(
+                "Could not make a {}x{} framebuffer with {} samples (status {:#x})",
+                width, height, samples, status
+            ));
+        }
+
+        Ok(framebuffer)
+    }
+
+    // The framebuffer to draw into and the one its samples are resolved to.
+    unsafe fn new_pair(width: u32, height: u32) -> Result<(Self, Self), String> {
+        Ok((
+            Self::new(width, height, MULTISAMPLE_SAMPLES)?,
+            Self::new(width, height, 0)?,
+        ))
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+            gl::DeleteRenderbuffers(1, &self.renderbuffer);
+        }
+    }
+}
+
+/// Runs the same shaders as the `GLRenderer` without a window, drawing into framebuffer objects
+/// of a `HeadlessGLContext` that it owns.
+///
+/// The renderer has to stay on the thread that created it, since that is where its context is
+/// current.
+pub struct HeadlessGLRenderer {
+    viewer: GLViewer,
+    // Multisampled like a window so edges match what the viewer shows.
+    multisampled: Framebuffer,
+    // Where `present` resolves the samples to, so that the pixels can be read back.
+    resolved: Framebuffer,
+    scene: GLSceneRenderer,
+    // Dropped last since everything above holds GL objects that belong to it.
+    _context: HeadlessGLContext,
+}
+
+impl HeadlessGLRenderer {
+    pub fn new(width: u32, height: u32, object_mgr: &ObjectMgr) -> Result<Self, String> {
+        Self::with_limits(width, height, object_mgr, &Limits::NONE)
+    }
+
+    /// Like `new`, for documents that are not trusted. See `GLSceneRenderer::with_limits`.
+    pub fn with_limits(
+        width: u32,
+        height: u32,
+        object_mgr: &ObjectMgr,
+        limits: &Limits,
+    ) -> Result<Self, String> {
+        let context = HeadlessGLContext::new()?;
+        let scene = GLSceneRenderer::with_limits(object_mgr, limits)?;
+
+        let (multisampled, resolved) = unsafe { Framebuffer::new_pair(width, height)? };
+
+        Ok(Self {
+            viewer: GLViewer::new(width, height),
+            multisampled,
+            resolved,
+            scene,
+            _context: context,
+        })
+    }
+
+    /// The image as of the last `present`, as 8-bit RGBA with straight alpha, row by row from the
+    /// top left.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let (width, height) = (
+            self.viewer.width_px as usize,
+            self.viewer.height_px as usize,
+        );
+        let mut pixels = vec![0; width * height * 4];
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.resolved.framebuffer);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                width as GLsizei,
+                height as GLsizei,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr().cast(),
+            );
+        }
+
+        // OpenGL reads from the bottom row up.
+        let row_size = width * 4;
+        let mut rows: Vec<&[u8]> = pixels.chunks(row_size.max(1)).collect();
+        rows.reverse();
+        rows.concat()
+    }
+}
+
+impl Renderer for HeadlessGLRenderer {
+    fn get_viewer(&mut self) -> &mut dyn Viewer {
+        &mut self.viewer
+    }
+
+    fn height(&self) -> u32 {
+        self.viewer.height_px
+    }
+
+    fn width(&self) -> u32 {
+        self.viewer.width_px
+    }
+
+    // Framebuffers have no window to keep usable so any size GL can make is allowed. Otherwise
+    // the renderer keeps its old size.
+    fn resize_window(&mut self, new_width: u32, new_height: u32) {
+        match unsafe { Framebuffer::new_pair(new_width, new_height) } {
+            Ok((multisampled, resolved)) => {
+                self.viewer.resize(new_width, new_height);
+                self.multisampled = multisampled;
+                self.resolved = resolved;
+            }
+            Err(err) => println!("Error while resizing the framebuffers: {}", err),
+        }
+    }
+
+    fn clear(&mut self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.multisampled.framebuffer);
+            gl::ClearColor(1.0, 1.0, 1.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+
+    fn update_objects(&mut self, object_mgr: &mut ObjectMgr) {
+        if let Err(err) = self.scene.update_objects(object_mgr) {
+            println!("Error while updating the scene: {}", err);
+        }
+    }
+
+    fn render_objects(&mut self, _object_mgr: &ObjectMgr) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.multisampled.framebuffer);
+        }
+
+        let viewport = GLViewport::full(self.viewer.width_px, self.viewer.height_px);
+        self.scene
+            .render(&viewport, self.viewer.get_norm_to_viewer());
+    }
+
+    fn present(&mut self) {
+        let (width, height) = (self.viewer.width_px as i32, self.viewer.height_px as i32);
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.multisampled.framebuffer);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.resolved.framebuffer);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                width,
+                height,
+                0,
+                0,
+                width,
+                height,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+            gl::Finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        objects::{
+            svg::{read_from_str, Element, EmptyTag, Limits, ParseOptions},
+            ObjectMgr,
+        },
+        render::{gl::StrokeMode, Renderer},
+    };
+
+    use super::HeadlessGLRenderer;
+
+    fn document(document: &str) -> ObjectMgr {
+        let mut object_mgr = ObjectMgr::new();
+        object_mgr.add_object(
+            read_from_str(document, &ParseOptions::STRICT)
+                .unwrap()
+                .into(),
+        );
+        object_mgr
+    }
+
+    fn render(document_text: &str) -> Vec<u8> {
+        render_strokes_as(document_text, StrokeMode::default())
+    }
+
+    // Draws the document to fill a 64x64 image. The `egl` feature is for machines that can make
+    // an OpenGL 3.3 context, if only through Mesa's llvmpipe, so failing to make one is an error.
+    fn render_strokes_as(document_text: &str, stroke_mode: StrokeMode) -> Vec<u8> {
+        let mut object_mgr = document(document_text);
+
+        let mut renderer = HeadlessGLRenderer::new(64, 64, &object_mgr)
+            .expect("Could not make an OpenGL 3.3 context through EGL");
+        renderer.scene.set_stroke_mode(stroke_mode);
+
+        renderer
+            .get_viewer()
+            .center_on_object(&object_mgr.get_objects()[0]);
+        renderer.clear();
+        renderer.update_objects(&mut object_mgr);
+        renderer.render_objects(&object_mgr);
+        renderer.present();
+        renderer.to_rgba8()
+    }
+
+    fn pixel(pixels: &[u8], x: usize, y: usize) -> [u8; 4] {
+        let index = (y * 64 + x) * 4;
+        pixels[index..index + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn fills_are_read_back_the_right_way_up() {
+        let pixels = render(
+            r##"<svg width="64" height="64"><rect width="64" height="32" fill="#ff0000" stroke="none"/></svg>"##,
+        );
+
+        assert_eq!(pixel(&pixels, 32, 8), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 32, 56), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn strokes_go_through_the_geometry_shaders() {
+        let pixels = render_strokes_as(
+            r##"<svg width="64" height="64"><polyline points="8,32 56,32 56,8" fill="none" stroke="#0000ff" stroke-width="8"/><line x1="8" y1="56" x2="56" y2="56" stroke="#ff0000" stroke-width="4"/></svg>"##,
+            StrokeMode::GeometryShaders,
+        );
+
+        assert_eq!(pixel(&pixels, 32, 32), [0, 0, 255, 255]);
+        assert_eq!(pixel(&pixels, 56, 20), [0, 0, 255, 255]);
+        assert_eq!(pixel(&pixels, 32, 56), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 32, 16), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn strokes_are_drawn_as_triangles() {
+        let pixels = render_strokes_as(
+            r##"<svg width="64" height="64"><polyline points="8,32 56,32" fill="none" stroke="#0000ff" stroke-width="8"/></svg>"##,
+            StrokeMode::Triangles,
+        );
+
+        assert_eq!(pixel(&pixels, 32, 32), [0, 0, 255, 255]);
+        assert_eq!(pixel(&pixels, 32, 16), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn shapes_are_stroked_over_their_fill() {
+        let pixels = render(
+            r##"<svg width="64" height="64"><ellipse cx="32" cy="32" rx="24" ry="24" fill="#ff0000" stroke="#0000ff" stroke-width="8"/></svg>"##,
+        );
+
+        assert_eq!(pixel(&pixels, 32, 32), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 32, 8), [0, 0, 255, 255]);
+        assert_eq!(pixel(&pixels, 56, 32), [0, 0, 255, 255]);
+        assert_eq!(pixel(&pixels, 32, 2), [255, 255, 255, 255]);
+        assert_eq!(pixel(&pixels, 6, 6), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn circles_are_drawn_as_ellipses() {
+        let pixels = render(
+            r##"<svg width="64" height="64"><circle cx="32" cy="32" r="24" fill="#00ff00" stroke="none"/></svg>"##,
+        );
+
+        assert_eq!(pixel(&pixels, 32, 32), [0, 255, 0, 255]);
+        assert_eq!(pixel(&pixels, 32, 10), [0, 255, 0, 255]);
+        assert_eq!(pixel(&pixels, 54, 32), [0, 255, 0, 255]);
+        assert_eq!(pixel(&pixels, 32, 4), [255, 255, 255, 255]);
+        assert_eq!(pixel(&pixels, 12, 12), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn rounded_corners_are_cut_out_of_rects() {
+        let pixels = render(
+            r##"<svg width="64" height="64"><rect width="64" height="64" rx="16" fill="#00ff00" stroke="none"/></svg>"##,
+        );
+
+        assert_eq!(pixel(&pixels, 1, 1), [255, 255, 255, 255]);
+        assert_eq!(pixel(&pixels, 62, 62), [255, 255, 255, 255]);
+        assert_eq!(pixel(&pixels, 32, 1), [0, 255, 0, 255]);
+        assert_eq!(pixel(&pixels, 32, 32), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn edits_are_drawn_from_the_repacked_batches() {
+        let mut object_mgr = document(
+            r##"<svg width="64" height="64"><rect width="32" height="32" fill="#ff0000" stroke="none"/><rect x="32" width="32" height="32" fill="#00ff00" stroke="none"/></svg>"##,
+        );
+        let mut renderer = HeadlessGLRenderer::new(64, 64, &object_mgr).unwrap();
+        renderer
+            .get_viewer()
+            .center_on_object(&object_mgr.get_objects()[0]);
+        let mut draw = |object_mgr: &mut ObjectMgr| {
+            renderer.clear();
+            renderer.update_objects(object_mgr);
+            renderer.render_objects(object_mgr);
+            renderer.present();
+            renderer.to_rgba8()
+        };
+        draw(&mut object_mgr);
+
+        // Moving the red rect keeps its size, so it is written over its old instance.
+        let svg = &mut object_mgr.get_objects_mut()[0].svg_inst;
+        svg.modify(&[0], |element| {
+            if let Element::EmptyTag(EmptyTag::Rect(rect)) = element {
+                rect.y = 32.0;
+            }
+        })
+        .unwrap();
+        let pixels = draw(&mut object_mgr);
+        assert_eq!(pixel(&pixels, 16, 16), [255, 255, 255, 255]);
+        assert_eq!(pixel(&pixels, 16, 48), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 48, 16), [0, 255, 0, 255]);
+
+        // Removing the green one packs what is left of the batch again.
+        object_mgr.get_objects_mut()[0]
+            .svg_inst
+            .remove(&[1])
+            .unwrap();
+        let pixels = draw(&mut object_mgr);
+        assert_eq!(pixel(&pixels, 16, 48), [255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 48, 16), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn documents_over_the_vertex_limit_are_refused() {
+        let object_mgr = document(
+            r##"<svg width="64" height="64"><polyline points="8,32 56,32 56,8" fill="none" stroke="#0000ff" stroke-width="8"/></svg>"##,
+        );
+        let limits = Limits {
+            max_vertices: 2,
+            ..Limits::NONE
+        };
+
+        let Err(err) = HeadlessGLRenderer::with_limits(64, 64, &object_mgr, &limits) else {
+            panic!("Expected the document to go over the limit");
+        };
+        assert!(err.contains("vertices"), "{}", err);
+    }
+
+    #[test]
+    fn framebuffers_that_cannot_be_made_are_refused() {
+        let object_mgr =
+            document(r#"<svg width="64" height="64"><rect width="8" height="8"/></svg>"#);
+
+        assert!(HeadlessGLRenderer::new(0, 0, &object_mgr).is_err());
+
+        let mut renderer = HeadlessGLRenderer::new(64, 64, &object_mgr).unwrap();
+        renderer.resize_window(0, 64);
+        assert_eq!((renderer.width(), renderer.height()), (64, 64));
+    }
+}
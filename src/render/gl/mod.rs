@@ -1,21 +1,27 @@
 use core::ffi::c_void;
-use std::cell::RefCell;
+use std::{ops::Range, rc::Rc};
 
 use gl::types::{GLenum, GLint, GLsizei, GLuint};
 use num_traits::ConstZero;
-use sdl2::{
-    pixels::Color,
-    video::{GLContext, Window},
-};
+#[cfg(feature = "sdl")]
+use sdl2::video::{GLContext, Window};
 
 use crate::{
+    color::Color,
     matrix::Matrix3x3,
     objects::{svg::*, Object, ObjectMgr},
-    render::{gl::shaders::ShaderMgr, Renderer, Viewer},
+    render::{bvh::Bvh, gl::shaders::ShaderMgr, stroke::stroke_outline, Viewer},
     texture::Texture,
-    vector::Vector2D,
+    vector::{Vector2D, Vector3D},
 };
 
+#[cfg(feature = "sdl")]
+use crate::render::Renderer;
+
+#[cfg(feature = "egl")]
+mod egl;
+#[cfg(feature = "egl")]
+pub mod headless;
 mod shaders;
 
 struct GLColor(f32, f32, f32, f32);
@@ -32,47 +38,103 @@ impl From<Color> for GLColor {
     }
 }
 
-enum RawOperationData {
-    DrawPoints(PointVertexData),
-    DrawLines(LineVertexData),
-    DrawAdjacentLines(LineVertexData),
-    FillPolygon(PolygonFillData),
-    FillConvexPolygon(TriangleFanFillData),
-    DrawImage(TextureData),
+impl GLColor {
+    const TRANSPARENT: Self = Self(0.0, 0.0, 0.0, 0.0);
+
+    // Paint that is not drawn becomes transparent, which the loaders already skip.
+    fn from_paint(color: Option<Color>) -> Self {
+        match color {
+            Some(color) => color.into(),
+            None => Self::TRANSPARENT,
+        }
+    }
 }
 
-#[derive(PartialEq)]
-struct DrawPointParams {
-    transform: Matrix3x3<f32>,
+enum RawOperationData {
+    Draw(BatchKind, VertexData),
+    DrawShape(ShapeInstance),
+    DrawImage(TextureData),
 }
 
-struct PointVertexData {
+/// Vertices laid out for the basic shader, and the order to draw them in.
+#[derive(Default)]
+struct VertexData {
     data: Vec<f32>,
-    sequence: Vec<(DrawPointParams, u32)>,
+    elements: Vec<GLuint>,
 }
 
-#[derive(PartialEq)]
-struct DrawLineParams {
-    draw_type: GLenum,
-    transform: Matrix3x3<f32>,
-    thickness: f32,
+impl VertexData {
+    fn append(&mut self, other: &VertexData) {
+        let first_vertex = (self.data.len() / shaders::BASIC_VERTEX_SIZE as usize) as GLuint;
+        self.data.extend_from_slice(&other.data);
+        self.elements
+            .extend(other.elements.iter().map(|element| element + first_vertex));
+    }
+
+    fn push_vertex(&mut self, transform: &Matrix3x3<f32>, point: [f32; 2], color: &GLColor) {
+        self.push_line_vertex(transform, point, color, 0.0);
+    }
+
+    // Documents only use affine transforms, so vertices are moved into world coordinates here
+    // rather than carrying their transform to the shader. Each vertex carries the thickness, in
+    // world units, the geometry shaders widen its line to, so lines of any thickness can share a
+    // batch.
+    fn push_line_vertex(
+        &mut self,
+        transform: &Matrix3x3<f32>,
+        point: [f32; 2],
+        color: &GLColor,
+        thickness: f32,
+    ) {
+        let x = transform[0][0] * point[0] + transform[0][1] * point[1] + transform[0][2];
+        let y = transform[1][0] * point[0] + transform[1][1] * point[1] + transform[1][2];
+        self.data
+            .extend_from_slice(&[x, y, color.0, color.1, color.2, color.3, thickness]);
+    }
 }
 
-struct LineVertexData {
-    data: Vec<f32>,
-    sequence: Vec<(DrawLineParams, u32)>,
+/// The operations that can share a draw call when they follow each other in paint order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BatchKind {
+    Points,
+    Triangles,
+    // Lines and polylines widened by the geometry shaders.
+    Lines,
+    LinesAdjacency,
 }
 
-struct PolygonFillData {
-    data: Vec<f32>,
-    fill_sequence: Vec<GLuint>,
-    transform: Matrix3x3<f32>,
+impl BatchKind {
+    fn draw_type(self) -> GLenum {
+        match self {
+            BatchKind::Points => gl::POINTS,
+            BatchKind::Triangles => gl::TRIANGLES,
+            BatchKind::Lines => gl::LINES,
+            BatchKind::LinesAdjacency => gl::LINES_ADJACENCY,
+        }
+    }
+
+    fn shader(self) -> shaders::Shader {
+        match self {
+            BatchKind::Points | BatchKind::Triangles => shaders::Shader::Basic,
+            BatchKind::Lines => shaders::Shader::Line,
+            BatchKind::LinesAdjacency => shaders::Shader::LineAdjacency,
+        }
+    }
 }
 
-struct TriangleFanFillData {
-    data: Vec<f32>,
-    num_vertices: u32,
-    transform: Matrix3x3<f32>,
+/// How the OpenGL renderer draws the strokes of lines, polylines and polygons.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StrokeMode {
+    /// Widened on the GPU by geometry shaders. Segments are joined by filling the gap between
+    /// them, without miters or caps.
+    ///
+    /// Strokes go through other shaders than fills, so a document that fills and strokes each of
+    /// its elements takes a draw call per fill and per stroke.
+    GeometryShaders,
+    /// Turned into triangles on the CPU with the same joins and caps as the software renderer.
+    /// Fills and strokes that follow each other are drawn with one call.
+    #[default]
+    Triangles,
 }
 
 struct TextureData {
@@ -83,582 +145,319 @@ struct TextureData {
 
 struct OperationExtractor {
     data: Vec<RawOperationData>,
+    // How many more vertices the document may generate. Elements that would go over it are left
+    // out and `over_limit` is set.
+    vertices_left: usize,
+    over_limit: bool,
+    stroke_mode: StrokeMode,
 }
 
 impl OperationExtractor {
-    fn from_svg_vertices(svg_object: &SVG) -> Self {
-        let mut extractor = Self { data: Vec::new() };
-
-        for element in svg_object.elements.iter() {
-            extractor.load_element_vertices(&element);
-        }
-
-        extractor
-    }
-
-    fn load_svg_vertices(&mut self, svg_object: &SVG) {
-        for element in svg_object.elements.iter() {
-            self.load_element_vertices(&element);
+    fn new(vertices_left: usize, stroke_mode: StrokeMode) -> Self {
+        Self {
+            data: Vec::new(),
+            vertices_left,
+            over_limit: false,
+            stroke_mode,
         }
     }
 
-    fn load_element_vertices(&mut self, element: &Element) {
-        match element {
-            Element::StartTag(start_tag) => self.load_tag_group_vertices(start_tag),
-            Element::EmptyTag(empty_tag) => self.load_empty_tag_vertices(empty_tag),
-            Element::EndTag(_) => (),
+    // Takes `count` vertices out of the budget, or returns false if there are not that many left.
+    fn reserve_vertices(&mut self, count: usize) -> bool {
+        if count > self.vertices_left {
+            self.over_limit = true;
+            return false;
         }
-    }
 
-    fn load_tag_group_vertices(&mut self, tag_group: &StartTag) {
-        match tag_group {
-            StartTag::Group(group) => {
-                for element in group.elements.iter() {
-                    self.load_element_vertices(element);
-                }
-            }
-            StartTag::SVG(svg_object) => self.load_svg_vertices(svg_object),
-        }
-    }
-
-    fn load_empty_tag_vertices(&mut self, empty_tag: &EmptyTag) {
-        match empty_tag {
-            EmptyTag::Ellipse(ellipse) => self.load_ellipse(ellipse),
-            EmptyTag::Image(image) => self.load_image(image),
-            EmptyTag::Line(line) => self.load_line(line),
-            EmptyTag::Point(point) => self.load_point(point),
-            EmptyTag::Polygon(polygon) => self.load_polygon(polygon),
-            EmptyTag::Polyline(polyline) => self.load_polyline(polyline),
-            EmptyTag::Rect(rect) => self.load_rect(rect),
-        }
+        self.vertices_left -= count;
+        true
     }
 
     fn load_point(&mut self, point: &Point) {
-        let position = &point.position;
-
-        let color: GLColor = if point.style.fill_color == Style::DEFAULT.fill_color {
-            point.style.stroke_color
+        let color = GLColor::from_paint(if point.style.fill == Style::DEFAULT.fill {
+            point.style.stroke_rgba()
         } else {
-            point.style.fill_color
-        }
-        .into();
+            point.style.fill_rgba()
+        });
 
-        if color.3 == 0.0 {
+        if color.3 == 0.0 || !self.reserve_vertices(1) {
             return;
         }
 
-        self.extend_point_data(
-            &[position[0], position[1], color.0, color.1, color.2, color.3],
-            DrawPointParams {
-                transform: point.style.transform.clone().transpose_symmetric(),
-            },
+        let mut vertices = VertexData::default();
+        vertices.push_vertex(
+            &point.style.transform,
+            [point.position[0], point.position[1]],
+            &color,
         );
-    }
-
-    fn extend_point_data(&mut self, new_data: &[f32], params: DrawPointParams) {
-        let point_data = match self.data.last_mut() {
-            Some(RawOperationData::DrawPoints(point_data)) => point_data,
-            _ => {
-                self.data
-                    .push(RawOperationData::DrawPoints(PointVertexData {
-                        data: Vec::new(),
-                        sequence: Vec::new(),
-                    }));
-                match self.data.last_mut() {
-                    Some(RawOperationData::DrawPoints(point_data)) => point_data,
-                    _ => panic!("Expected a DrawPoints operation."),
-                }
-            }
-        };
-
-        point_data.data.extend_from_slice(new_data);
-
-        match point_data.sequence.last_mut() {
-            Some((last_params, num_vertices)) if last_params == &params => {
-                *num_vertices += 1;
-            }
-            _ => {
-                point_data.sequence.push((params, 1));
-            }
-        }
+        vertices.elements.push(0);
+        self.data
+            .push(RawOperationData::Draw(BatchKind::Points, vertices));
     }
 
     fn load_line(&mut self, line: &Line) {
-        let p1 = &line.from;
-        let p2 = &line.to;
-
-        let color: GLColor = if line.style.fill_color == Style::DEFAULT.fill_color {
-            line.style.stroke_color
+        let color = GLColor::from_paint(if line.style.fill == Style::DEFAULT.fill {
+            line.style.stroke_rgba()
         } else {
-            line.style.fill_color
-        }
-        .into();
+            line.style.fill_rgba()
+        });
 
         if color.3 == 0.0 {
             return;
         }
 
-        self.extend_line_data(
-            &[
-                p1[0], p1[1], color.0, color.1, color.2, color.3, p2[0], p2[1], color.0, color.1,
-                color.2, color.3,
-            ],
-            DrawLineParams {
-                draw_type: gl::LINES,
-                transform: line.style.transform.clone().transpose_symmetric(),
-                thickness: line.style.stroke_width,
-            },
-            2,
+        self.load_outline(
+            &[[line.from[0], line.from[1]], [line.to[0], line.to[1]]],
+            false,
+            &line.style,
+            &color,
         );
     }
 
-    fn extend_line_data(&mut self, new_data: &[f32], params: DrawLineParams, num_vertices: u32) {
-        let line_data = match self.data.last_mut() {
-            Some(RawOperationData::DrawLines(line_data)) => line_data,
-            _ => {
-                self.data.push(RawOperationData::DrawLines(LineVertexData {
-                    data: Vec::new(),
-                    sequence: Vec::new(),
-                }));
-                match self.data.last_mut() {
-                    Some(RawOperationData::DrawLines(line_data)) => line_data,
-                    _ => panic!("Expected a DrawLines operation."),
-                }
-            }
-        };
+    fn load_fill(&mut self, points: &[Vector2D<f32>], style: &Style, elements: Vec<GLuint>) {
+        if !self.reserve_vertices(points.len()) {
+            return;
+        }
 
-        line_data.data.extend_from_slice(new_data);
+        let color = GLColor::from_paint(style.fill_rgba());
+        let mut vertices = VertexData {
+            data: Vec::with_capacity(points.len() * shaders::BASIC_VERTEX_SIZE as usize),
+            elements,
+        };
 
-        match line_data.sequence.last_mut() {
-            Some((last_params, last_num_vertices)) if last_params == &params => {
-                *last_num_vertices += num_vertices;
-            }
-            _ => {
-                line_data.sequence.push((params, num_vertices));
-            }
+        for point in points.iter() {
+            vertices.push_vertex(&style.transform, [point[0], point[1]], &color);
         }
+
+        self.data
+            .push(RawOperationData::Draw(BatchKind::Triangles, vertices));
     }
 
-    fn load_polygon(&mut self, polygon: &Polygon) {
-        if polygon.points.len() < 3 {
+    // Strokes are turned into triangles too so that they can be drawn along with the fills.
+    fn load_outline(&mut self, points: &[[f32; 2]], closed: bool, style: &Style, color: &GLColor) {
+        if self.stroke_mode == StrokeMode::GeometryShaders {
+            self.load_outline_lines(points, closed, style, color);
             return;
         }
 
-        let mut fill_vertex_data: Vec<f32> = Vec::new();
-        let mut fill_element_data: Vec<GLuint> = Vec::new();
-        let mut stroke_vertex_data: Vec<f32> = Vec::new();
-        let num_stroke_vertices = polygon.points.len() + 3; // Add space for adjacency information
-        let fill_color: GLColor = polygon.style.fill_color.into();
-        let stroke_color: GLColor = polygon.style.stroke_color.into();
-        let do_outline = stroke_color.3 > 0.0 && polygon.style.stroke_width > 0.0;
-        let mut do_fill = fill_color.3 > 0.0;
-
-        let triangles = if do_fill {
-            crate::render::triangulation::triangulate(&polygon.points)
-        } else {
-            None
-        };
-        do_fill &= triangles.is_some();
-
-        if !do_outline && !do_fill {
+        let pieces = stroke_outline(points, closed, style);
+        if !self.reserve_vertices(pieces.iter().map(|piece| piece.len()).sum()) {
             return;
         }
 
-        if do_fill {
-            fill_vertex_data.reserve_exact(
-                polygon.points.len() * (shaders::POS_SIZE + shaders::COLOR_SIZE) as usize,
-            );
-            let triangles = triangles.unwrap();
-            fill_element_data.reserve_exact(triangles.len() * 3);
-            for triangle in triangles.iter() {
-                fill_element_data.push(triangle[0] as GLuint);
-                fill_element_data.push(triangle[1] as GLuint);
-                fill_element_data.push(triangle[2] as GLuint);
+        let mut vertices = VertexData::default();
+        for piece in pieces {
+            // Every piece is convex so it can be drawn as a fan around its first point.
+            let first = (vertices.data.len() / shaders::BASIC_VERTEX_SIZE as usize) as GLuint;
+            for index in 1..piece.len() as GLuint - 1 {
+                vertices
+                    .elements
+                    .extend_from_slice(&[first, first + index, first + index + 1]);
             }
-        }
-
-        if do_outline {
-            stroke_vertex_data.reserve_exact(
-                num_stroke_vertices * (shaders::POS_SIZE + shaders::COLOR_SIZE) as usize,
-            );
-
-            // Push a copy of the last point to the front to give adjacency information for the first edge
-            let last_point = polygon.points.last().unwrap();
-            stroke_vertex_data.extend_from_slice(&[
-                last_point[0],
-                last_point[1],
-                stroke_color.0,
-                stroke_color.1,
-                stroke_color.2,
-                stroke_color.3,
-            ]);
-        }
-
-        for point in polygon.points.iter() {
-            if do_fill {
-                fill_vertex_data.extend_from_slice(&[
-                    point[0],
-                    point[1],
-                    fill_color.0,
-                    fill_color.1,
-                    fill_color.2,
-                    fill_color.3,
-                ]);
-            }
-
-            if do_outline {
-                stroke_vertex_data.extend_from_slice(&[
-                    point[0],
-                    point[1],
-                    stroke_color.0,
-                    stroke_color.1,
-                    stroke_color.2,
-                    stroke_color.3,
-                ]);
+            for point in piece {
+                vertices.push_vertex(&style.transform, point, color);
             }
         }
 
-        if do_fill {
+        if !vertices.elements.is_empty() {
             self.data
-                .push(RawOperationData::FillPolygon(PolygonFillData {
-                    data: fill_vertex_data,
-                    fill_sequence: fill_element_data,
-                    transform: polygon.style.transform.clone().transpose_symmetric(),
-                }));
-        }
-
-        if do_outline {
-            // Wrap around to include enough information to close the loop
-            let first_point = &polygon.points[0];
-            stroke_vertex_data.extend_from_slice(&[
-                first_point[0],
-                first_point[1],
-                stroke_color.0,
-                stroke_color.1,
-                stroke_color.2,
-                stroke_color.3,
-            ]);
-
-            let second_point = &polygon.points[1];
-            stroke_vertex_data.extend_from_slice(&[
-                second_point[0],
-                second_point[1],
-                stroke_color.0,
-                stroke_color.1,
-                stroke_color.2,
-                stroke_color.3,
-            ]);
-
-            match self.data.last_mut() {
-                Some(RawOperationData::DrawAdjacentLines(line_data)) => {
-                    line_data.data.extend(stroke_vertex_data);
-                    line_data.sequence.push((
-                        DrawLineParams {
-                            draw_type: gl::LINE_STRIP_ADJACENCY,
-                            transform: polygon.style.transform.clone().transpose_symmetric(),
-                            thickness: polygon.style.stroke_width,
-                        },
-                        num_stroke_vertices as u32,
-                    ));
-                }
-                _ => {
-                    self.data
-                        .push(RawOperationData::DrawAdjacentLines(LineVertexData {
-                            data: stroke_vertex_data,
-                            sequence: vec![(
-                                DrawLineParams {
-                                    draw_type: gl::LINE_STRIP_ADJACENCY,
-                                    transform: polygon
-                                        .style
-                                        .transform
-                                        .clone()
-                                        .transpose_symmetric(),
-                                    thickness: polygon.style.stroke_width,
-                                },
-                                num_stroke_vertices as u32,
-                            )],
-                        }));
-                }
-            };
+                .push(RawOperationData::Draw(BatchKind::Triangles, vertices));
         }
     }
 
-    // Convex polygons can use a triangle-fan instead of triangulation
-    fn load_convex_polygon(&mut self, polygon: &Polygon) {
-        if polygon.points.len() < 3 {
-            return;
-        }
-
-        let mut fill_vertex_data: Vec<f32> = Vec::new();
-        let mut stroke_vertex_data: Vec<f32> = Vec::new();
-        let num_stroke_vertices = polygon.points.len() + 3; // Add space for adjacency information
-        let fill_color: GLColor = polygon.style.fill_color.into();
-        let stroke_color: GLColor = polygon.style.stroke_color.into();
-        let do_outline = stroke_color.3 > 0.0 && polygon.style.stroke_width > 0.0;
-        let do_fill = fill_color.3 > 0.0;
-
-        if !do_outline && !do_fill {
+    // Each segment is drawn with the points around it so that the shader can join it to the next.
+    fn load_outline_lines(
+        &mut self,
+        points: &[[f32; 2]],
+        closed: bool,
+        style: &Style,
+        color: &GLColor,
+    ) {
+        let transform = &style.transform;
+        let scale = (transform[0][0] * transform[1][1] - transform[0][1] * transform[1][0])
+            .abs()
+            .sqrt();
+        let thickness = style.stroke_width * scale;
+        if thickness <= 0.0 || !self.reserve_vertices(points.len()) {
             return;
         }
 
-        if do_fill {
-            fill_vertex_data.reserve_exact(
-                polygon.points.len() * (shaders::POS_SIZE + shaders::COLOR_SIZE) as usize,
-            );
+        let mut vertices = VertexData::default();
+        for point in points {
+            vertices.push_line_vertex(transform, *point, color, thickness);
         }
 
-        if do_outline {
-            stroke_vertex_data.reserve_exact(
-                num_stroke_vertices * (shaders::POS_SIZE + shaders::COLOR_SIZE) as usize,
-            );
-
-            // Push a copy of the last point to the front to give adjacency information for the first edge
-            let last_point = polygon.points.last().unwrap();
-            stroke_vertex_data.extend_from_slice(&[
-                last_point[0],
-                last_point[1],
-                stroke_color.0,
-                stroke_color.1,
-                stroke_color.2,
-                stroke_color.3,
-            ]);
-        }
-
-        for point in polygon.points.iter() {
-            if do_fill {
-                fill_vertex_data.extend_from_slice(&[
-                    point[0],
-                    point[1],
-                    fill_color.0,
-                    fill_color.1,
-                    fill_color.2,
-                    fill_color.3,
+        let count = points.len() as GLuint;
+        let kind = if count == 2 && !closed {
+            vertices.elements.extend_from_slice(&[0, 1]);
+            BatchKind::Lines
+        } else if closed {
+            for index in 0..count {
+                vertices.elements.extend_from_slice(&[
+                    (index + count - 1) % count,
+                    index,
+                    (index + 1) % count,
+                    (index + 2) % count,
                 ]);
             }
-
-            if do_outline {
-                stroke_vertex_data.extend_from_slice(&[
-                    point[0],
-                    point[1],
-                    stroke_color.0,
-                    stroke_color.1,
-                    stroke_color.2,
-                    stroke_color.3,
+            BatchKind::LinesAdjacency
+        } else {
+            for index in 0..count - 1 {
+                vertices.elements.extend_from_slice(&[
+                    index.saturating_sub(1),
+                    index,
+                    index + 1,
+                    (index + 2).min(count - 1),
                 ]);
             }
-        }
+            BatchKind::LinesAdjacency
+        };
 
-        if do_fill {
-            self.data
-                .push(RawOperationData::FillConvexPolygon(TriangleFanFillData {
-                    data: fill_vertex_data,
-                    num_vertices: polygon.points.len() as u32,
-                    transform: polygon.style.transform.clone().transpose_symmetric(),
-                }));
+        self.data.push(RawOperationData::Draw(kind, vertices));
+    }
+
+    fn load_shape_outline(&mut self, points: &[Vector2D<f32>], closed: bool, style: &Style) {
+        let color = GLColor::from_paint(style.stroke_rgba());
+        if color.3 == 0.0 {
+            return;
         }
 
-        if do_outline {
-            // Wrap around to include enough information to close the loop
-            let first_point = &polygon.points[0];
-            stroke_vertex_data.extend_from_slice(&[
-                first_point[0],
-                first_point[1],
-                stroke_color.0,
-                stroke_color.1,
-                stroke_color.2,
-                stroke_color.3,
-            ]);
+        let points: Vec<[f32; 2]> = points.iter().map(|point| [point[0], point[1]]).collect();
+        self.load_outline(&points, closed, style, &color);
+    }
 
-            let second_point = &polygon.points[1];
-            stroke_vertex_data.extend_from_slice(&[
-                second_point[0],
-                second_point[1],
-                stroke_color.0,
-                stroke_color.1,
-                stroke_color.2,
-                stroke_color.3,
-            ]);
-
-            match self.data.last_mut() {
-                Some(RawOperationData::DrawAdjacentLines(line_data)) => {
-                    line_data.data.extend(stroke_vertex_data);
-                    line_data.sequence.push((
-                        DrawLineParams {
-                            draw_type: gl::LINE_STRIP_ADJACENCY,
-                            transform: polygon.style.transform.clone().transpose_symmetric(),
-                            thickness: polygon.style.stroke_width,
-                        },
-                        num_stroke_vertices as u32,
-                    ));
-                }
-                _ => {
-                    self.data
-                        .push(RawOperationData::DrawAdjacentLines(LineVertexData {
-                            data: stroke_vertex_data,
-                            sequence: vec![(
-                                DrawLineParams {
-                                    draw_type: gl::LINE_STRIP_ADJACENCY,
-                                    transform: polygon
-                                        .style
-                                        .transform
-                                        .clone()
-                                        .transpose_symmetric(),
-                                    thickness: polygon.style.stroke_width,
-                                },
-                                num_stroke_vertices as u32,
-                            )],
-                        }));
-                }
-            };
-        }
+    fn has_fill(style: &Style) -> bool {
+        GLColor::from_paint(style.fill_rgba()).3 > 0.0
     }
 
-    fn load_polyline(&mut self, polyline: &Polyline) {
-        if polyline.points.len() < 2 {
+    fn load_polygon(&mut self, polygon: &Polygon) {
+        if polygon.points.len() < 3 {
             return;
         }
 
-        let mut fill_vertex_data: Vec<f32> = Vec::new();
-        let mut fill_element_data: Vec<GLuint> = Vec::new();
-        let mut stroke_vertex_data: Vec<f32> = Vec::new();
-        let num_stroke_vertices = polyline.points.len() + 2; // Add space for adjacency information
-        let fill_color: GLColor = polyline.style.fill_color.into();
-        let stroke_color: GLColor = polyline.style.stroke_color.into();
-        let do_outline = stroke_color.3 > 0.0 && polyline.style.stroke_width > 0.0;
-        let mut do_fill = fill_color.3 > 0.0 && polyline.points.len() > 2;
+        if Self::has_fill(&polygon.style) {
+            if let Some(triangles) = crate::render::triangulation::triangulate(&polygon.points) {
+                let elements = triangles.iter().flatten().map(|index| *index as GLuint);
+                self.load_fill(&polygon.points, &polygon.style, elements.collect());
+            }
+        }
 
-        let triangles = if do_fill {
-            crate::render::triangulation::triangulate(&polyline.points)
-        } else {
-            None
-        };
-        do_fill &= triangles.is_some();
+        self.load_shape_outline(&polygon.points, true, &polygon.style);
+    }
 
-        if !do_outline && !do_fill {
+    // Convex polygons can be split into a fan of triangles instead of triangulating them
+    fn load_convex_polygon(&mut self, polygon: &Polygon) {
+        if polygon.points.len() < 3 {
             return;
         }
 
-        if do_fill {
-            fill_vertex_data.reserve_exact(
-                polyline.points.len() * (shaders::POS_SIZE + shaders::COLOR_SIZE) as usize,
-            );
-            let triangles = triangles.unwrap();
-            fill_element_data.reserve_exact(triangles.len() * 3);
-            for triangle in triangles.iter() {
-                fill_element_data.push(triangle[0] as GLuint);
-                fill_element_data.push(triangle[1] as GLuint);
-                fill_element_data.push(triangle[2] as GLuint);
-            }
+        if Self::has_fill(&polygon.style) {
+            let elements = (1..polygon.points.len() as GLuint - 1)
+                .flat_map(|index| [0, index, index + 1])
+                .collect();
+            self.load_fill(&polygon.points, &polygon.style, elements);
         }
 
-        if do_outline {
-            stroke_vertex_data.reserve_exact(
-                num_stroke_vertices * (shaders::POS_SIZE + shaders::COLOR_SIZE) as usize,
-            );
+        self.load_shape_outline(&polygon.points, true, &polygon.style);
+    }
 
-            // Push a copy of the first point to buffer the adjacency information
-            let first_point = polyline.points.first().unwrap();
-            stroke_vertex_data.extend_from_slice(&[
-                first_point[0],
-                first_point[1],
-                stroke_color.0,
-                stroke_color.1,
-                stroke_color.2,
-                stroke_color.3,
-            ]);
-        }
-
-        for point in polyline.points.iter() {
-            if do_fill {
-                fill_vertex_data.extend_from_slice(&[
-                    point[0],
-                    point[1],
-                    fill_color.0,
-                    fill_color.1,
-                    fill_color.2,
-                    fill_color.3,
-                ]);
-            }
+    fn load_polyline(&mut self, polyline: &Polyline) {
+        if polyline.points.len() < 2 {
+            return;
+        }
 
-            if do_outline {
-                stroke_vertex_data.extend_from_slice(&[
-                    point[0],
-                    point[1],
-                    stroke_color.0,
-                    stroke_color.1,
-                    stroke_color.2,
-                    stroke_color.3,
-                ]);
+        if Self::has_fill(&polyline.style) && polyline.points.len() > 2 {
+            if let Some(triangles) = crate::render::triangulation::triangulate(&polyline.points) {
+                let elements = triangles.iter().flatten().map(|index| *index as GLuint);
+                self.load_fill(&polyline.points, &polyline.style, elements.collect());
             }
         }
 
-        if do_fill {
-            self.data
-                .push(RawOperationData::FillPolygon(PolygonFillData {
-                    data: fill_vertex_data,
-                    fill_sequence: fill_element_data,
-                    transform: polyline.style.transform.clone().transpose_symmetric(),
-                }));
+        self.load_shape_outline(&polyline.points, false, &polyline.style);
+    }
+
+    fn load_shape(
+        &mut self,
+        center: [f32; 2],
+        half_size: [f32; 2],
+        corner_radii: [f32; 2],
+        style: &Style,
+    ) {
+        let fill_color = GLColor::from_paint(style.fill_rgba());
+        let stroke_color = GLColor::from_paint(style.stroke_rgba());
+        if fill_color.3 == 0.0 && (stroke_color.3 == 0.0 || style.stroke_width <= 0.0) {
+            return;
+        }
+        if !self.reserve_vertices(ShapeBatch::NUM_VERTICES as usize) {
+            return;
         }
 
-        if do_outline {
-            // Push a copy of the last point to buffer adjacency information
-            let last_point = &polyline.points.last().unwrap();
-            stroke_vertex_data.extend_from_slice(&[
-                last_point[0],
-                last_point[1],
+        let transform = &style.transform;
+        let mut instance: ShapeInstance = [0.0; shaders::SHAPE_INSTANCE_SIZE as usize];
+        let mut set = |offset: usize, values: &[f32]| {
+            instance[offset..offset + values.len()].copy_from_slice(values);
+        };
+        set(shaders::SHAPE_CENTER, &center);
+        set(shaders::SHAPE_HALF_SIZE, &half_size);
+        set(shaders::SHAPE_CORNER_RADII, &corner_radii);
+        set(shaders::SHAPE_TRANSFORM_X, &transform[0][..3]);
+        set(shaders::SHAPE_TRANSFORM_Y, &transform[1][..3]);
+        set(
+            shaders::SHAPE_FILL_COLOR,
+            &[fill_color.0, fill_color.1, fill_color.2, fill_color.3],
+        );
+        set(
+            shaders::SHAPE_STROKE_COLOR,
+            &[
                 stroke_color.0,
                 stroke_color.1,
                 stroke_color.2,
                 stroke_color.3,
-            ]);
-
-            match self.data.last_mut() {
-                Some(RawOperationData::DrawAdjacentLines(line_data)) => {
-                    line_data.data.extend(stroke_vertex_data);
-                    line_data.sequence.push((
-                        DrawLineParams {
-                            draw_type: gl::LINE_STRIP_ADJACENCY,
-                            transform: polyline.style.transform.clone().transpose_symmetric(),
-                            thickness: polyline.style.stroke_width,
-                        },
-                        num_stroke_vertices as u32,
-                    ));
-                }
-                _ => {
-                    self.data
-                        .push(RawOperationData::DrawAdjacentLines(LineVertexData {
-                            data: stroke_vertex_data,
-                            sequence: vec![(
-                                DrawLineParams {
-                                    draw_type: gl::LINE_STRIP_ADJACENCY,
-                                    transform: polyline
-                                        .style
-                                        .transform
-                                        .clone()
-                                        .transpose_symmetric(),
-                                    thickness: polyline.style.stroke_width,
-                                },
-                                num_stroke_vertices as u32,
-                            )],
-                        }));
-                }
-            };
-        }
+            ],
+        );
+        set(shaders::SHAPE_STROKE_WIDTH, &[style.stroke_width.max(0.0)]);
+        self.data.push(RawOperationData::DrawShape(instance));
     }
 
     fn load_ellipse(&mut self, ellipse: &Ellipse) {
-        self.load_convex_polygon(&Polygon::from(ellipse));
+        if ellipse.radius[0] <= 0.0 || ellipse.radius[1] <= 0.0 {
+            return;
+        }
+
+        let radius = [ellipse.radius[0], ellipse.radius[1]];
+        self.load_shape(
+            [ellipse.center[0], ellipse.center[1]],
+            radius,
+            radius,
+            &ellipse.style,
+        );
     }
 
     fn load_rect(&mut self, rect: &Rect) {
-        self.load_convex_polygon(&Polygon::from(rect));
+        // Rects without an area still stroke the line they collapse to. They have no corners to
+        // round so any number of segments will do.
+        if rect.width <= 0.0 || rect.height <= 0.0 {
+            self.load_convex_polygon(&rect.to_polygon(4));
+            return;
+        }
+
+        let half_size = [rect.width * 0.5, rect.height * 0.5];
+        let (rx, ry) = rect.corner_radii();
+        self.load_shape(
+            [rect.x + half_size[0], rect.y + half_size[1]],
+            half_size,
+            [rx, ry],
+            &rect.style,
+        );
     }
 
     fn load_image(&mut self, image: &Image) {
         if image.width <= 0.0 || image.height <= 0.0 {
             return;
         }
+        if !self.reserve_vertices(TextureArray::NUM_VERTICES as usize) {
+            return;
+        }
 
         let data = [
             image.x,
@@ -681,105 +480,131 @@ impl OperationExtractor {
 
         self.data.push(RawOperationData::DrawImage(TextureData {
             data,
-            texture: image.texture.clone(),
+            texture: (*image.texture).clone(),
             transform: image.style.transform.clone(),
         }))
     }
 }
 
-struct PointArray {
+/// The vertices of many operations in one buffer, drawn with a single call.
+struct GeometryBatch {
+    kind: BatchKind,
+    num_vertices: u32,
+    num_elements: u32,
     array_index: GLuint,
     buffer_index: GLuint,
-    sequence: Vec<(DrawPointParams, u32)>,
+    element_buffer_index: GLuint,
 }
 
-impl PointArray {
-    unsafe fn draw(&self, shaders: &mut ShaderMgr) {
-        shaders.activate(shaders::Shader::Basic);
-        gl::BindVertexArray(self.array_index);
-        gl::BindBuffer(gl::ARRAY_BUFFER, self.buffer_index);
-        let mut total_drawn: u32 = 0;
-        for (params, num_points) in self.sequence.iter() {
-            shaders.set_svg_transform(params.transform.clone());
-            gl::DrawArrays(gl::POINTS, total_drawn as GLint, *num_points as GLsizei);
-            total_drawn += num_points;
-        }
-    }
-}
+impl GeometryBatch {
+    fn new(kind: BatchKind, vertices: &VertexData, shaders: &mut ShaderMgr) -> Self {
+        let mut batch = Self {
+            kind,
+            num_vertices: (vertices.data.len() / shaders::BASIC_VERTEX_SIZE as usize) as u32,
+            num_elements: vertices.elements.len() as u32,
+            array_index: 0,
+            buffer_index: 0,
+            element_buffer_index: 0,
+        };
 
-impl Drop for PointArray {
-    fn drop(&mut self) {
         unsafe {
-            gl::DeleteBuffers(1, &self.buffer_index);
-            gl::DeleteVertexArrays(1, &self.array_index);
+            shaders.activate(kind.shader());
+
+            gl::GenVertexArrays(1, &mut batch.array_index);
+            gl::BindVertexArray(batch.array_index);
+
+            gl::GenBuffers(1, &mut batch.buffer_index);
+            gl::BindBuffer(gl::ARRAY_BUFFER, batch.buffer_index);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (vertices.data.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+                vertices.data.as_ptr() as *const c_void,
+                gl::STATIC_DRAW,
+            );
+
+            gl::GenBuffers(1, &mut batch.element_buffer_index);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, batch.element_buffer_index);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                (vertices.elements.len() * std::mem::size_of::<GLuint>()) as gl::types::GLsizeiptr,
+                vertices.elements.as_ptr() as *const c_void,
+                gl::STATIC_DRAW,
+            );
+
+            shaders.bind_attributes_to_vertex_array();
         }
+
+        batch
     }
-}
 
-struct LineVertexArray {
-    array_index: GLuint,
-    buffer_index: GLuint,
-    sequence: Vec<(DrawLineParams, u32)>,
-    is_adjacency: bool,
-}
+    /// Copies the given elements and vertices back from the GPU, with the elements counted from
+    /// the first of those vertices.
+    fn read(&self, elements: &Range<u32>, vertices: &Range<u32>) -> VertexData {
+        let mut data = vec![0.0; vertices.len() * shaders::BASIC_VERTEX_SIZE as usize];
+        let mut read_elements: Vec<GLuint> = vec![0; elements.len()];
 
-impl LineVertexArray {
-    unsafe fn draw(&self, shaders: &mut ShaderMgr) {
-        shaders.activate(if self.is_adjacency {
-            shaders::Shader::LineAdjacency
-        } else {
-            shaders::Shader::Line
-        });
-        gl::BindVertexArray(self.array_index);
-        gl::BindBuffer(gl::ARRAY_BUFFER, self.buffer_index);
-        let mut total_drawn: u32 = 0;
-        for (params, num_vertices) in self.sequence.iter() {
-            shaders.set_svg_transform(params.transform.clone());
-            shaders.set_line_thickness(params.thickness);
-            gl::DrawArrays(
-                params.draw_type,
-                total_drawn as GLint,
-                *num_vertices as GLsizei,
+        unsafe {
+            read_buffer(
+                self.buffer_index,
+                vertices.start as usize * shaders::BASIC_VERTEX_SIZE as usize,
+                &mut data,
             );
-            total_drawn += num_vertices;
+            read_buffer(
+                self.element_buffer_index,
+                elements.start as usize,
+                &mut read_elements,
+            );
+        }
+
+        for element in read_elements.iter_mut() {
+            *element -= vertices.start;
+        }
+        VertexData {
+            data,
+            elements: read_elements,
         }
     }
-}
 
-impl Drop for LineVertexArray {
-    fn drop(&mut self) {
+    /// Overwrites the given vertices, and the elements that follow `first_element`, with
+    /// `vertices`.
+    fn write(&mut self, first_element: u32, first_vertex: u32, vertices: &VertexData) {
+        let elements: Vec<GLuint> = vertices
+            .elements
+            .iter()
+            .map(|element| element + first_vertex)
+            .collect();
+
         unsafe {
-            gl::DeleteBuffers(1, &self.buffer_index);
-            gl::DeleteVertexArrays(1, &self.array_index);
+            write_buffer(
+                self.buffer_index,
+                first_vertex as usize * shaders::BASIC_VERTEX_SIZE as usize,
+                &vertices.data,
+            );
+            write_buffer(self.element_buffer_index, first_element as usize, &elements);
         }
     }
-}
 
-struct TriangleVertexArray {
-    array_index: GLuint,
-    buffer_index: GLuint,
-    element_buffer_index: GLuint,
-    transform: Matrix3x3<f32>,
-    num_elements: u32,
-}
+    /// Draws the given ranges of elements.
+    unsafe fn draw(&self, shaders: &mut ShaderMgr, ranges: &[Range<u32>]) {
+        let counts: Vec<GLsizei> = ranges.iter().map(|range| range.len() as GLsizei).collect();
+        let offsets: Vec<*const c_void> = ranges
+            .iter()
+            .map(|range| (range.start as usize * std::mem::size_of::<GLuint>()) as *const c_void)
+            .collect();
 
-impl TriangleVertexArray {
-    unsafe fn draw(&self, shaders: &mut ShaderMgr) {
-        shaders.activate(shaders::Shader::Basic);
+        shaders.activate(self.kind.shader());
         gl::BindVertexArray(self.array_index);
-        gl::BindBuffer(gl::ARRAY_BUFFER, self.buffer_index);
-        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.element_buffer_index);
-        shaders.set_svg_transform(self.transform.clone());
-        gl::DrawElements(
-            gl::TRIANGLES,
-            self.num_elements as GLsizei,
+        gl::MultiDrawElements(
+            self.kind.draw_type(),
+            counts.as_ptr(),
             gl::UNSIGNED_INT,
-            std::ptr::null(),
+            offsets.as_ptr(),
+            ranges.len() as GLsizei,
         );
     }
 }
 
-impl Drop for TriangleVertexArray {
+impl Drop for GeometryBatch {
     fn drop(&mut self) {
         unsafe {
             gl::DeleteBuffers(1, &self.element_buffer_index);
@@ -789,24 +614,112 @@ impl Drop for TriangleVertexArray {
     }
 }
 
-struct TriangleFanVertexArray {
+// The copy targets are bound to nothing else, so reading and writing through them leaves the
+// buffers bound to vertex arrays alone. `offset` counts values of `T`.
+unsafe fn read_buffer<T>(buffer_index: GLuint, offset: usize, data: &mut [T]) {
+    gl::BindBuffer(gl::COPY_READ_BUFFER, buffer_index);
+    gl::GetBufferSubData(
+        gl::COPY_READ_BUFFER,
+        (offset * std::mem::size_of::<T>()) as gl::types::GLintptr,
+        std::mem::size_of_val(data) as gl::types::GLsizeiptr,
+        data.as_mut_ptr() as *mut c_void,
+    );
+}
+
+unsafe fn write_buffer<T>(buffer_index: GLuint, offset: usize, data: &[T]) {
+    gl::BindBuffer(gl::COPY_WRITE_BUFFER, buffer_index);
+    gl::BufferSubData(
+        gl::COPY_WRITE_BUFFER,
+        (offset * std::mem::size_of::<T>()) as gl::types::GLintptr,
+        std::mem::size_of_val(data) as gl::types::GLsizeiptr,
+        data.as_ptr() as *const c_void,
+    );
+}
+
+/// A rect or an ellipse laid out for the shape shader.
+type ShapeInstance = [f32; shaders::SHAPE_INSTANCE_SIZE as usize];
+
+/// Rects and ellipses drawn as instances of a quad, with their edges worked out per pixel.
+struct ShapeBatch {
+    num_instances: u32,
     array_index: GLuint,
     buffer_index: GLuint,
-    transform: Matrix3x3<f32>,
-    num_vertices: u32,
 }
 
-impl TriangleFanVertexArray {
-    unsafe fn draw(&self, shaders: &mut ShaderMgr) {
-        shaders.activate(shaders::Shader::Basic);
+impl ShapeBatch {
+    // The corners of the quad come from the vertex index, so it needs no vertex buffer.
+    const NUM_VERTICES: u8 = 4;
+
+    fn new(instances: &[f32], shaders: &mut ShaderMgr) -> Self {
+        let mut batch = Self {
+            num_instances: Placement::count_instances(instances),
+            array_index: 0,
+            buffer_index: 0,
+        };
+
+        unsafe {
+            shaders.activate(shaders::Shader::Shape);
+
+            gl::GenVertexArrays(1, &mut batch.array_index);
+            gl::BindVertexArray(batch.array_index);
+
+            gl::GenBuffers(1, &mut batch.buffer_index);
+            gl::BindBuffer(gl::ARRAY_BUFFER, batch.buffer_index);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(instances) as gl::types::GLsizeiptr,
+                instances.as_ptr() as *const c_void,
+                gl::STATIC_DRAW,
+            );
+
+            shaders.bind_attributes_to_vertex_array();
+        }
+
+        batch
+    }
+
+    /// Copies the given instances back from the GPU.
+    fn read(&self, instances: &Range<u32>) -> Vec<f32> {
+        let mut data = vec![0.0; instances.len() * shaders::SHAPE_INSTANCE_SIZE as usize];
+        unsafe {
+            read_buffer(
+                self.buffer_index,
+                instances.start as usize * shaders::SHAPE_INSTANCE_SIZE as usize,
+                &mut data,
+            );
+        }
+        data
+    }
+
+    /// Overwrites the instances that follow `first_instance` with `instances`.
+    fn write(&mut self, first_instance: u32, instances: &[f32]) {
+        unsafe {
+            write_buffer(
+                self.buffer_index,
+                first_instance as usize * shaders::SHAPE_INSTANCE_SIZE as usize,
+                instances,
+            );
+        }
+    }
+
+    /// Draws the given ranges of instances.
+    unsafe fn draw(&self, shaders: &mut ShaderMgr, ranges: &[Range<u32>]) {
+        shaders.activate(shaders::Shader::Shape);
         gl::BindVertexArray(self.array_index);
         gl::BindBuffer(gl::ARRAY_BUFFER, self.buffer_index);
-        shaders.set_svg_transform(self.transform.clone());
-        gl::DrawArrays(gl::TRIANGLE_FAN, 0, self.num_vertices as GLsizei);
+        for range in ranges {
+            shaders.bind_instances_from(range.start as usize);
+            gl::DrawArraysInstanced(
+                gl::TRIANGLE_STRIP,
+                0,
+                Self::NUM_VERTICES as GLsizei,
+                range.len() as GLsizei,
+            );
+        }
     }
 }
 
-impl Drop for TriangleFanVertexArray {
+impl Drop for ShapeBatch {
     fn drop(&mut self) {
         unsafe {
             gl::DeleteBuffers(1, &self.buffer_index);
@@ -825,10 +738,76 @@ struct TextureArray {
 impl TextureArray {
     const NUM_VERTICES: u8 = 4;
 
+    fn new(texture_data: TextureData, shaders: &mut ShaderMgr) -> Self {
+        let mut texture_array = Self {
+            array_index: 0,
+            buffer_index: 0,
+            texture_index: 0,
+            transform: texture_data.transform,
+        };
+
+        unsafe {
+            shaders.activate(shaders::Shader::Texture);
+
+            gl::GenVertexArrays(1, &mut texture_array.array_index);
+            gl::BindVertexArray(texture_array.array_index);
+
+            gl::GenBuffers(1, &mut texture_array.buffer_index);
+            gl::BindBuffer(gl::ARRAY_BUFFER, texture_array.buffer_index);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (texture_data.data.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
+                texture_data.data.as_ptr() as *const c_void,
+                gl::STATIC_DRAW,
+            );
+
+            gl::GenTextures(1, &mut texture_array.texture_index);
+            gl::BindTexture(gl::TEXTURE_2D, texture_array.texture_index);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                texture_data.texture.gl_internal_format() as GLint,
+                texture_data.texture.width() as GLsizei,
+                texture_data.texture.height() as GLsizei,
+                0,
+                texture_data.texture.gl_input_format(),
+                Texture::GL_DATA_TYPE,
+                texture_data.texture.data().as_ptr() as *const c_void,
+            );
+
+            shaders.set_sampler_source(0);
+
+            shaders.bind_attributes_to_vertex_array();
+
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MAG_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as GLint,
+            );
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+
+        texture_array
+    }
+
     unsafe fn draw(&self, shaders: &mut ShaderMgr) {
         shaders.activate(shaders::Shader::Texture);
         gl::BindVertexArray(self.array_index);
-        gl::BindBuffer(gl::ARRAY_BUFFER, self.buffer_index);
         gl::BindTexture(gl::TEXTURE_2D, self.texture_index);
         shaders.set_svg_transform(self.transform.clone());
         gl::DrawArrays(gl::TRIANGLE_FAN, 0, Self::NUM_VERTICES as GLsizei);
@@ -845,281 +824,649 @@ impl Drop for TextureArray {
     }
 }
 
+/// What one element draws.
+///
+/// Geometry stays on the CPU until it is packed into a batch with that of its neighbours, after
+/// which only its place in the batch is kept. Images are uploaded right away since their textures
+/// cannot be shared.
 enum Operation {
-    DrawPoints(PointArray),
-    DrawLines(LineVertexArray),
-    DrawAdjacentLines(LineVertexArray),
-    FillPolygon(TriangleVertexArray),
-    FillConvexPolygon(TriangleFanVertexArray),
-    DrawImage(TextureArray),
+    Draw(BatchKind, VertexData),
+    DrawShapes(Vec<f32>),
+    DrawImage(Rc<TextureArray>),
+    Packed(PackedOperation),
+}
+
+/// The operations that can share a batch when they follow each other in paint order. Images
+/// never do.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OperationKind {
+    Geometry(BatchKind),
+    Shapes,
+    Image,
+}
+
+/// An operation whose data is only kept in its batch.
+struct PackedOperation {
+    kind: OperationKind,
+    bounds: Option<BoundingBox>,
+    placement: Placement,
+    // The vertices, or instances, of the batch that belong to the operation.
+    vertices: Range<u32>,
 }
 
 impl Operation {
-    fn gen_from_svg(svg_object: &SVG, shaders: &mut ShaderMgr) -> Vec<Self> {
-        let raw_operation_data = OperationExtractor::from_svg_vertices(svg_object);
+    fn gen_from_extractor(
+        raw_operation_data: OperationExtractor,
+        shaders: &mut ShaderMgr,
+    ) -> Vec<Self> {
+        raw_operation_data
+            .data
+            .into_iter()
+            .map(|operation_data| match operation_data {
+                RawOperationData::Draw(kind, vertices) => Operation::Draw(kind, vertices),
+                RawOperationData::DrawShape(instance) => Operation::DrawShapes(instance.to_vec()),
+                RawOperationData::DrawImage(texture_data) => {
+                    Operation::DrawImage(Rc::new(TextureArray::new(texture_data, shaders)))
+                }
+            })
+            .collect()
+    }
 
-        let mut operations = Vec::new();
+    fn kind(&self) -> OperationKind {
+        match self {
+            Operation::Draw(kind, _) => OperationKind::Geometry(*kind),
+            Operation::DrawShapes(_) => OperationKind::Shapes,
+            Operation::DrawImage(_) => OperationKind::Image,
+            Operation::Packed(packed) => packed.kind,
+        }
+    }
 
-        operations.reserve_exact(raw_operation_data.data.len());
-
-        for operation_data in raw_operation_data.data.into_iter() {
-            match operation_data {
-                RawOperationData::DrawPoints(point_data) => {
-                    let mut point_array = PointArray {
-                        array_index: 0,
-                        buffer_index: 0,
-                        sequence: point_data.sequence,
-                    };
-
-                    unsafe {
-                        shaders.activate(shaders::Shader::Basic);
-
-                        gl::GenVertexArrays(1, &mut point_array.array_index);
-                        gl::BindVertexArray(point_array.array_index);
-
-                        gl::GenBuffers(1, &mut point_array.buffer_index);
-                        gl::BindBuffer(gl::ARRAY_BUFFER, point_array.buffer_index);
-                        gl::BufferData(
-                            gl::ARRAY_BUFFER,
-                            (point_data.data.len() * std::mem::size_of::<f32>())
-                                as gl::types::GLsizeiptr,
-                            point_data.data.as_ptr() as *const c_void,
-                            gl::STATIC_DRAW,
-                        );
+    // The elements or instances the operation draws, and the vertices or instances it takes up.
+    fn size(&self) -> (u32, u32) {
+        match self {
+            Operation::Draw(_, vertices) => (
+                vertices.elements.len() as u32,
+                (vertices.data.len() / shaders::BASIC_VERTEX_SIZE as usize) as u32,
+            ),
+            Operation::DrawShapes(instances) => {
+                let count = Placement::count_instances(instances);
+                (count, count)
+            }
+            Operation::DrawImage(_) => (1, 0),
+            Operation::Packed(packed) => (
+                packed.placement.range.len() as u32,
+                packed.vertices.len() as u32,
+            ),
+        }
+    }
 
-                        shaders.bind_attributes_to_vertex_array();
-                    }
+    /// The area the operation draws over in world coordinates, if it is known.
+    fn bounds(&self) -> Option<BoundingBox> {
+        let mut bounds: Option<BoundingBox> = None;
+        let mut include = |x: f32, y: f32| match bounds.as_mut() {
+            Some(bounds) => bounds.include(x, y),
+            None => bounds = Some(BoundingBox::from_point(x, y)),
+        };
 
-                    operations.push(Operation::DrawPoints(point_array));
+        match self {
+            Operation::Draw(_, vertices) => {
+                for vertex in vertices.data.chunks(shaders::BASIC_VERTEX_SIZE as usize) {
+                    // Lines reach half their thickness past their vertices.
+                    let margin = vertex[shaders::BASIC_THICKNESS] * 0.5;
+                    include(vertex[0] - margin, vertex[1] - margin);
+                    include(vertex[0] + margin, vertex[1] + margin);
                 }
-                RawOperationData::DrawLines(line_data) => {
-                    let mut line_vertex_array = LineVertexArray {
-                        array_index: 0,
-                        buffer_index: 0,
-                        sequence: line_data.sequence,
-                        is_adjacency: false,
-                    };
-
-                    unsafe {
-                        shaders.activate(shaders::Shader::Line);
-
-                        gl::GenVertexArrays(1, &mut line_vertex_array.array_index);
-                        gl::BindVertexArray(line_vertex_array.array_index);
-
-                        gl::GenBuffers(1, &mut line_vertex_array.buffer_index);
-                        gl::BindBuffer(gl::ARRAY_BUFFER, line_vertex_array.buffer_index);
-                        gl::BufferData(
-                            gl::ARRAY_BUFFER,
-                            (line_data.data.len() * std::mem::size_of::<f32>())
-                                as gl::types::GLsizeiptr,
-                            line_data.data.as_ptr() as *const c_void,
-                            gl::STATIC_DRAW,
+            }
+            Operation::DrawShapes(instances) => {
+                for instance in instances.chunks(shaders::SHAPE_INSTANCE_SIZE as usize) {
+                    let center = &instance[shaders::SHAPE_CENTER..];
+                    let half_size = &instance[shaders::SHAPE_HALF_SIZE..];
+                    let transform_x = &instance[shaders::SHAPE_TRANSFORM_X..];
+                    let transform_y = &instance[shaders::SHAPE_TRANSFORM_Y..];
+                    let half_stroke = instance[shaders::SHAPE_STROKE_WIDTH] / 2.0;
+                    let extent = [half_size[0] + half_stroke, half_size[1] + half_stroke];
+                    for (x_sign, y_sign) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                        let x = center[0] + x_sign * extent[0];
+                        let y = center[1] + y_sign * extent[1];
+                        include(
+                            transform_x[0] * x + transform_x[1] * y + transform_x[2],
+                            transform_y[0] * x + transform_y[1] * y + transform_y[2],
                         );
-
-                        shaders.bind_attributes_to_vertex_array();
                     }
-
-                    operations.push(Operation::DrawLines(line_vertex_array));
                 }
-                RawOperationData::DrawAdjacentLines(line_data) => {
-                    let mut line_vertex_array = LineVertexArray {
-                        array_index: 0,
-                        buffer_index: 0,
-                        sequence: line_data.sequence,
-                        is_adjacency: true,
-                    };
-
-                    unsafe {
-                        shaders.activate(shaders::Shader::LineAdjacency);
-
-                        gl::GenVertexArrays(1, &mut line_vertex_array.array_index);
-                        gl::BindVertexArray(line_vertex_array.array_index);
-
-                        gl::GenBuffers(1, &mut line_vertex_array.buffer_index);
-                        gl::BindBuffer(gl::ARRAY_BUFFER, line_vertex_array.buffer_index);
-                        gl::BufferData(
-                            gl::ARRAY_BUFFER,
-                            (line_data.data.len() * std::mem::size_of::<f32>())
-                                as gl::types::GLsizeiptr,
-                            line_data.data.as_ptr() as *const c_void,
-                            gl::STATIC_DRAW,
-                        );
+            }
+            // Images are rare enough that they are always drawn.
+            Operation::DrawImage(_) => {}
+            Operation::Packed(packed) => return packed.bounds.clone(),
+        }
+
+        bounds
+    }
+
+    /// Adds the data of `other` after this operation's, as if they had been generated together.
+    fn append(&mut self, other: &Operation) {
+        match (self, other) {
+            (Operation::Draw(_, vertices), Operation::Draw(_, other)) => vertices.append(other),
+            (Operation::DrawShapes(instances), Operation::DrawShapes(other)) => {
+                instances.extend_from_slice(other)
+            }
+            _ => panic!("Tried to merge operations that cannot share a batch."),
+        }
+    }
+}
+
+/// Where an operation ended up once packed: which batch, and which of its elements or instances.
+#[derive(Clone, Debug, PartialEq)]
+struct Placement {
+    batch: usize,
+    range: Range<u32>,
+}
+
+impl Placement {
+    fn count_instances(instances: &[f32]) -> u32 {
+        (instances.len() / shaders::SHAPE_INSTANCE_SIZE as usize) as u32
+    }
 
-                        shaders.bind_attributes_to_vertex_array();
+    /// Groups the placements of `operations`, given in paint order, by batch. Parts of a batch
+    /// that follow each other are drawn as one range.
+    fn merge(placements: &[Placement], operations: &[usize]) -> Vec<(usize, Vec<Range<u32>>)> {
+        let mut merged: Vec<(usize, Vec<Range<u32>>)> = Vec::new();
+
+        for placement in operations.iter().map(|operation| &placements[*operation]) {
+            match merged.last_mut() {
+                Some((batch, ranges)) if *batch == placement.batch => match ranges.last_mut() {
+                    Some(last) if last.end == placement.range.start => {
+                        last.end = placement.range.end;
                     }
+                    _ => ranges.push(placement.range.clone()),
+                },
+                _ => merged.push((placement.batch, vec![placement.range.clone()])),
+            }
+        }
+
+        merged
+    }
+}
+
+/// One batch of a `Layout`.
+#[derive(Debug, PartialEq)]
+struct BatchPlan {
+    kind: OperationKind,
+    operations: Range<usize>,
+    // Its elements or instances, and its vertices or instances.
+    size: (u32, u32),
+    // The old batch that already holds its operations. The operations that were generated again
+    // are written over the ones they replace.
+    reused: Option<usize>,
+}
+
+/// How operations are packed into batches.
+///
+/// Operations of the same kind that follow each other in paint order share a batch. A batch whose
+/// packed operations are all still in one old batch, at the same place and with the gaps between
+/// them filled by operations of the same size, keeps the old batch.
+#[derive(Debug, Default, PartialEq)]
+struct Layout {
+    plans: Vec<BatchPlan>,
+    // Where each operation goes, in paint order.
+    placements: Vec<Placement>,
+    vertices: Vec<Range<u32>>,
+}
 
-                    operations.push(Operation::DrawAdjacentLines(line_vertex_array));
+impl Layout {
+    /// `old_sizes` are the sizes of the batches the packed operations are in, as given by
+    /// `Batch::size`.
+    fn new<'a>(
+        operations: impl IntoIterator<Item = &'a Operation>,
+        old_sizes: &[(u32, u32)],
+    ) -> Self {
+        let mut layout = Self::default();
+        // Whether the packed operations of each plan line up with their old batch so far.
+        let mut lines_up = Vec::new();
+
+        for (index, operation) in operations.into_iter().enumerate() {
+            let kind = operation.kind();
+            match layout.plans.last_mut() {
+                Some(plan) if plan.kind == kind && kind != OperationKind::Image => {
+                    plan.operations.end = index + 1;
                 }
-                RawOperationData::FillPolygon(polygon_data) => {
-                    let mut triangle_vertex_array = TriangleVertexArray {
-                        array_index: 0,
-                        buffer_index: 0,
-                        element_buffer_index: 0,
-                        transform: polygon_data.transform,
-                        num_elements: polygon_data.fill_sequence.len() as u32,
-                    };
-
-                    unsafe {
-                        shaders.activate(shaders::Shader::Basic);
-
-                        gl::GenVertexArrays(1, &mut triangle_vertex_array.array_index);
-                        gl::BindVertexArray(triangle_vertex_array.array_index);
-
-                        gl::GenBuffers(1, &mut triangle_vertex_array.buffer_index);
-                        gl::BindBuffer(gl::ARRAY_BUFFER, triangle_vertex_array.buffer_index);
-                        gl::BufferData(
-                            gl::ARRAY_BUFFER,
-                            (polygon_data.data.len() * std::mem::size_of::<f32>())
-                                as gl::types::GLsizeiptr,
-                            polygon_data.data.as_ptr() as *const c_void,
-                            gl::STATIC_DRAW,
-                        );
+                _ => {
+                    layout.plans.push(BatchPlan {
+                        kind,
+                        operations: index..index + 1,
+                        size: (0, 0),
+                        reused: None,
+                    });
+                    lines_up.push(true);
+                }
+            }
 
-                        gl::GenBuffers(1, &mut triangle_vertex_array.element_buffer_index);
-                        gl::BindBuffer(
-                            gl::ELEMENT_ARRAY_BUFFER,
-                            triangle_vertex_array.element_buffer_index,
-                        );
-                        gl::BufferData(
-                            gl::ELEMENT_ARRAY_BUFFER,
-                            (polygon_data.fill_sequence.len() * std::mem::size_of::<GLuint>())
-                                as gl::types::GLsizeiptr,
-                            polygon_data.fill_sequence.as_ptr() as *const c_void,
-                            gl::STATIC_DRAW,
-                        );
+            let batch = layout.plans.len() - 1;
+            let plan = &mut layout.plans[batch];
+            if let Operation::Packed(packed) = operation {
+                let old_batch = *plan.reused.get_or_insert(packed.placement.batch);
+                *lines_up.last_mut().unwrap() &= old_batch == packed.placement.batch
+                    && packed.placement.range.start == plan.size.0
+                    && packed.vertices.start == plan.size.1;
+            }
+
+            let (num_elements, num_vertices) = operation.size();
+            layout.placements.push(Placement {
+                batch,
+                range: plan.size.0..plan.size.0 + num_elements,
+            });
+            layout
+                .vertices
+                .push(plan.size.1..plan.size.1 + num_vertices);
+            plan.size = (plan.size.0 + num_elements, plan.size.1 + num_vertices);
+        }
+
+        let mut claimed = vec![false; old_sizes.len()];
+        for (plan, lines_up) in layout.plans.iter_mut().zip(lines_up) {
+            plan.reused = plan.reused.filter(|old_batch| {
+                lines_up
+                    && old_sizes[*old_batch] == plan.size
+                    && !std::mem::replace(&mut claimed[*old_batch], true)
+            });
+        }
 
-                        shaders.bind_attributes_to_vertex_array();
+        layout
+    }
+}
+
+/// One draw call.
+enum Batch {
+    Geometry(GeometryBatch),
+    Shapes(ShapeBatch),
+    Image(Rc<TextureArray>),
+}
+
+impl Batch {
+    fn upload(operation: Operation, shaders: &mut ShaderMgr) -> Self {
+        match operation {
+            Operation::Draw(kind, vertices) => {
+                Batch::Geometry(GeometryBatch::new(kind, &vertices, shaders))
+            }
+            Operation::DrawShapes(instances) => Batch::Shapes(ShapeBatch::new(&instances, shaders)),
+            Operation::DrawImage(texture_array) => Batch::Image(texture_array),
+            Operation::Packed(_) => panic!("Tried to upload an operation that is already packed."),
+        }
+    }
+
+    /// Packs the operations of `plan` into a new batch, copying those that are packed already out
+    /// of the batches in `old`.
+    fn build(
+        plan: &BatchPlan,
+        operations: &[&mut Operation],
+        old: &[Option<Batch>],
+        shaders: &mut ShaderMgr,
+    ) -> Self {
+        let old_batch = |packed: &PackedOperation| {
+            old[packed.placement.batch]
+                .as_ref()
+                .expect("Old batches are only taken once nothing is copied out of them")
+        };
+
+        let mut merged = match plan.kind {
+            OperationKind::Geometry(kind) => Operation::Draw(kind, VertexData::default()),
+            OperationKind::Shapes => Operation::DrawShapes(Vec::new()),
+            // Images are alone in their batch.
+            OperationKind::Image => {
+                let image = match &*operations[plan.operations.start] {
+                    Operation::Packed(packed) => old_batch(packed).unpack(packed),
+                    Operation::DrawImage(texture_array) => {
+                        Operation::DrawImage(texture_array.clone())
                     }
+                    _ => panic!("Tried to pack another operation as an image."),
+                };
+                return Batch::upload(image, shaders);
+            }
+        };
+
+        for operation in &operations[plan.operations.clone()] {
+            match &**operation {
+                Operation::Packed(packed) => merged.append(&old_batch(packed).unpack(packed)),
+                operation => merged.append(operation),
+            }
+        }
+
+        Batch::upload(merged, shaders)
+    }
+
+    // The elements and vertices of the batch, or its instances twice.
+    fn size(&self) -> (u32, u32) {
+        match self {
+            Batch::Geometry(geometry_batch) => {
+                (geometry_batch.num_elements, geometry_batch.num_vertices)
+            }
+            Batch::Shapes(shape_batch) => (shape_batch.num_instances, shape_batch.num_instances),
+            Batch::Image(_) => (1, 0),
+        }
+    }
+
+    /// Copies a packed operation back out of the batch.
+    fn unpack(&self, packed: &PackedOperation) -> Operation {
+        match self {
+            Batch::Geometry(geometry_batch) => Operation::Draw(
+                geometry_batch.kind,
+                geometry_batch.read(&packed.placement.range, &packed.vertices),
+            ),
+            Batch::Shapes(shape_batch) => Operation::DrawShapes(shape_batch.read(&packed.vertices)),
+            Batch::Image(texture_array) => Operation::DrawImage(texture_array.clone()),
+        }
+    }
+
+    /// Writes an operation over the part of the batch it is placed at. Operations that are packed
+    /// already are there.
+    fn write(&mut self, operation: &Operation, placement: &Placement, vertices: &Range<u32>) {
+        match (self, operation) {
+            (_, Operation::Packed(_)) => {}
+            (Batch::Geometry(geometry_batch), Operation::Draw(_, data)) => {
+                geometry_batch.write(placement.range.start, vertices.start, data)
+            }
+            (Batch::Shapes(shape_batch), Operation::DrawShapes(instances)) => {
+                shape_batch.write(vertices.start, instances)
+            }
+            _ => panic!("Tried to write an operation into a batch of another kind."),
+        }
+    }
+
+    /// Draws the given ranges of the batch. Images only have one.
+    fn execute(&self, shaders: &mut ShaderMgr, ranges: &[Range<u32>]) {
+        unsafe {
+            match self {
+                Batch::Geometry(geometry_batch) => geometry_batch.draw(shaders, ranges),
+                Batch::Shapes(shape_batch) => shape_batch.draw(shaders, ranges),
+                Batch::Image(texture_array) => texture_array.draw(shaders),
+            }
+        }
+    }
+}
+
+/// The operations generated for one element.
+///
+/// Nodes are laid out like the document they were generated from so that edits to the document
+/// only regenerate the operations of the elements they touched.
+struct OperationNode {
+    operations: Vec<Operation>,
+    // The vertices generated for this node's own operations.
+    num_vertices: usize,
+    children: Vec<OperationNode>,
+    is_dirty: bool,
+}
+
+impl OperationNode {
+    fn new() -> Self {
+        Self {
+            operations: Vec::new(),
+            num_vertices: 0,
+            children: Vec::new(),
+            is_dirty: false,
+        }
+    }
+
+    // Stands in for an element until its operations are generated.
+    fn dirty() -> Self {
+        Self {
+            is_dirty: true,
+            ..Self::new()
+        }
+    }
+
+    // Takes the vertices it generates out of `vertices_left`, and fails if there are not enough.
+    fn gen_from_svg(
+        svg_object: &SVG,
+        shaders: &mut ShaderMgr,
+        stroke_mode: StrokeMode,
+        vertices_left: &mut usize,
+    ) -> Result<Self, ReadError> {
+        Ok(
+            OperationTreeBuilder::build(shaders, stroke_mode, vertices_left, |builder| {
+                svg_object.walk(builder)
+            })?
+            .expect("Walking an svg always produces a node"),
+        )
+    }
+
+    // End tags do not produce any node so an empty one keeps the tree lined up with the document.
+    fn gen_from_path(
+        svg_object: &SVG,
+        path: &[usize],
+        shaders: &mut ShaderMgr,
+        stroke_mode: StrokeMode,
+        vertices_left: &mut usize,
+    ) -> Result<Self, ReadError> {
+        Ok(
+            OperationTreeBuilder::build(shaders, stroke_mode, vertices_left, |builder| {
+                svg_object.walk_from(path, builder);
+            })?
+            .unwrap_or_else(Self::new),
+        )
+    }
 
-                    operations.push(Operation::FillPolygon(triangle_vertex_array))
+    fn total_vertices(&self) -> usize {
+        self.num_vertices
+            + self
+                .children
+                .iter()
+                .map(|child| child.total_vertices())
+                .sum::<usize>()
+    }
+
+    fn get_mut(&mut self, path: &[usize]) -> Option<&mut Self> {
+        let mut node = self;
+        for index in path {
+            node = node.children.get_mut(*index)?;
+        }
+        Some(node)
+    }
+
+    // Only the layout of the tree is updated here. The elements are generated once all the
+    // changes are applied since the document may have changed again after this change was made.
+    //
+    // Returns None if the change does not fit this tree.
+    fn apply_change(&mut self, change: &Change) -> Option<()> {
+        match change {
+            Change::Inserted(path) => {
+                let (index, parent) = path.split_last()?;
+                let siblings = &mut self.get_mut(parent)?.children;
+                if *index > siblings.len() {
+                    return None;
                 }
-                RawOperationData::FillConvexPolygon(triangle_fan_data) => {
-                    let mut triangle_fan_vertex_array = TriangleFanVertexArray {
-                        array_index: 0,
-                        buffer_index: 0,
-                        transform: triangle_fan_data.transform,
-                        num_vertices: triangle_fan_data.num_vertices,
-                    };
-
-                    unsafe {
-                        shaders.activate(shaders::Shader::Basic);
-
-                        gl::GenVertexArrays(1, &mut triangle_fan_vertex_array.array_index);
-                        gl::BindVertexArray(triangle_fan_vertex_array.array_index);
-
-                        gl::GenBuffers(1, &mut triangle_fan_vertex_array.buffer_index);
-                        gl::BindBuffer(gl::ARRAY_BUFFER, triangle_fan_vertex_array.buffer_index);
-                        gl::BufferData(
-                            gl::ARRAY_BUFFER,
-                            (triangle_fan_data.data.len() * std::mem::size_of::<f32>())
-                                as gl::types::GLsizeiptr,
-                            triangle_fan_data.data.as_ptr() as *const c_void,
-                            gl::STATIC_DRAW,
-                        );
+                siblings.insert(*index, Self::dirty());
+            }
+            Change::Removed(path) => {
+                let (index, parent) = path.split_last()?;
+                let siblings = &mut self.get_mut(parent)?.children;
+                if *index >= siblings.len() {
+                    return None;
+                }
+                siblings.remove(*index);
+            }
+            Change::Modified(path) => self.get_mut(path)?.is_dirty = true,
+        }
+
+        Some(())
+    }
+
+    // The vertices of the nodes that are generated again go back into `vertices_left` first.
+    fn regenerate_dirty(
+        &mut self,
+        svg_object: &SVG,
+        path: &mut ElementPath,
+        shaders: &mut ShaderMgr,
+        stroke_mode: StrokeMode,
+        vertices_left: &mut usize,
+    ) -> Result<(), ReadError> {
+        let num_elements = match svg_object.get(path) {
+            _ if path.is_empty() => svg_object.elements.len(),
+            Some(element) => element.children().len(),
+            None => 0,
+        };
+
+        // The document was edited without going through the edit API.
+        if self.children.len() != num_elements {
+            *vertices_left += self.total_vertices();
+            *self = if path.is_empty() {
+                Self::gen_from_svg(svg_object, shaders, stroke_mode, vertices_left)?
+            } else {
+                Self::gen_from_path(svg_object, path, shaders, stroke_mode, vertices_left)?
+            };
+            return Ok(());
+        }
+
+        for (index, node) in self.children.iter_mut().enumerate() {
+            path.push(index);
+            if node.is_dirty {
+                *vertices_left += node.total_vertices();
+                *node = Self::gen_from_path(svg_object, path, shaders, stroke_mode, vertices_left)?;
+            } else {
+                node.regenerate_dirty(svg_object, path, shaders, stroke_mode, vertices_left)?;
+            }
+            path.pop();
+        }
+
+        Ok(())
+    }
+
+    // Lists the operations of this node and its children in paint order.
+    fn collect_operations<'a>(&'a mut self, operations: &mut Vec<&'a mut Operation>) {
+        operations.extend(self.operations.iter_mut());
+
+        for child in self.children.iter_mut() {
+            child.collect_operations(operations);
+        }
+    }
+}
+
+/// Builds one operation node per element it visits.
+struct OperationTreeBuilder<'a> {
+    shaders: &'a mut ShaderMgr,
+    // Nodes of the containers that are still being visited. The first node collects the node of
+    // the element the walk started from.
+    open_nodes: Vec<OperationNode>,
+    stroke_mode: StrokeMode,
+    vertices_left: usize,
+    // Set once an element needs more vertices than are left. Nothing is generated after that.
+    over_limit: bool,
+}
+
+impl<'a> OperationTreeBuilder<'a> {
+    fn build(
+        shaders: &'a mut ShaderMgr,
+        stroke_mode: StrokeMode,
+        vertices_left: &mut usize,
+        walk: impl FnOnce(&mut Self),
+    ) -> Result<Option<OperationNode>, ReadError> {
+        let mut builder = Self {
+            shaders,
+            open_nodes: vec![OperationNode::new()],
+            stroke_mode,
+            vertices_left: *vertices_left,
+            over_limit: false,
+        };
+
+        walk(&mut builder);
+
+        if builder.over_limit {
+            return Err(ReadError::LimitExceeded(Limit::Vertices));
+        }
+        *vertices_left = builder.vertices_left;
+        Ok(builder
+            .open_nodes
+            .pop()
+            .and_then(|mut node| node.children.pop()))
+    }
+
+    fn open_node(&mut self) {
+        self.open_nodes.push(OperationNode::new());
+    }
+
+    fn close_node(&mut self) {
+        let node = self
+            .open_nodes
+            .pop()
+            .expect("Closed more nodes than were opened");
+        self.add_node(node);
+    }
+
+    fn add_node(&mut self, node: OperationNode) {
+        self.open_nodes
+            .last_mut()
+            .expect("Closed more nodes than were opened")
+            .children
+            .push(node);
+    }
+
+    fn add_leaf(&mut self, load: impl FnOnce(&mut OperationExtractor)) {
+        if self.over_limit {
+            self.add_node(OperationNode::new());
+            return;
+        }
+
+        let mut extractor = OperationExtractor::new(self.vertices_left, self.stroke_mode);
+        load(&mut extractor);
+        self.over_limit = extractor.over_limit;
+
+        let num_vertices = self.vertices_left - extractor.vertices_left;
+        self.vertices_left = extractor.vertices_left;
+        let node = OperationNode {
+            operations: Operation::gen_from_extractor(extractor, self.shaders),
+            num_vertices,
+            ..OperationNode::new()
+        };
+        self.add_node(node);
+    }
+}
+
+impl Visitor for OperationTreeBuilder<'_> {
+    fn enter_svg(&mut self, _svg: &SVG, _ctx: &VisitContext) {
+        self.open_node();
+    }
+
+    fn leave_svg(&mut self, _svg: &SVG, _ctx: &VisitContext) {
+        self.close_node();
+    }
 
-                        shaders.bind_attributes_to_vertex_array();
-                    }
+    fn enter_group(&mut self, _group: &Group, _ctx: &VisitContext) {
+        self.open_node();
+    }
 
-                    operations.push(Operation::FillConvexPolygon(triangle_fan_vertex_array))
-                }
-                RawOperationData::DrawImage(texture_data) => {
-                    let mut texture_array = TextureArray {
-                        array_index: 0,
-                        buffer_index: 0,
-                        texture_index: 0,
-                        transform: texture_data.transform,
-                    };
-
-                    unsafe {
-                        shaders.activate(shaders::Shader::Texture);
-
-                        gl::GenVertexArrays(1, &mut texture_array.array_index);
-                        gl::BindVertexArray(texture_array.array_index);
-
-                        gl::GenBuffers(1, &mut texture_array.buffer_index);
-                        gl::BindBuffer(gl::ARRAY_BUFFER, texture_array.buffer_index);
-                        gl::BufferData(
-                            gl::ARRAY_BUFFER,
-                            (texture_data.data.len() * std::mem::size_of::<f32>())
-                                as gl::types::GLsizeiptr,
-                            texture_data.data.as_ptr() as *const c_void,
-                            gl::STATIC_DRAW,
-                        );
+    fn leave_group(&mut self, _group: &Group, _ctx: &VisitContext) {
+        self.close_node();
+    }
 
-                        gl::GenTextures(1, &mut texture_array.texture_index);
-                        gl::BindTexture(gl::TEXTURE_2D, texture_array.texture_index);
-                        gl::TexImage2D(
-                            gl::TEXTURE_2D,
-                            0,
-                            texture_data.texture.gl_internal_format() as GLint,
-                            texture_data.texture.width() as GLsizei,
-                            texture_data.texture.height() as GLsizei,
-                            0,
-                            texture_data.texture.gl_input_format(),
-                            Texture::GL_DATA_TYPE,
-                            texture_data.texture.data().as_ptr() as *const c_void,
-                        );
+    fn visit_ellipse(&mut self, ellipse: &Ellipse, _ctx: &VisitContext) {
+        self.add_leaf(|extractor| extractor.load_ellipse(ellipse));
+    }
 
-                        shaders.set_sampler_source(0);
+    fn visit_image(&mut self, image: &Image, _ctx: &VisitContext) {
+        self.add_leaf(|extractor| extractor.load_image(image));
+    }
 
-                        shaders.bind_attributes_to_vertex_array();
+    fn visit_line(&mut self, line: &Line, _ctx: &VisitContext) {
+        self.add_leaf(|extractor| extractor.load_line(line));
+    }
 
-                        gl::TexParameteri(
-                            gl::TEXTURE_2D,
-                            gl::TEXTURE_WRAP_S,
-                            gl::CLAMP_TO_EDGE as GLint,
-                        );
-                        gl::TexParameteri(
-                            gl::TEXTURE_2D,
-                            gl::TEXTURE_WRAP_T,
-                            gl::CLAMP_TO_EDGE as GLint,
-                        );
-                        gl::TexParameteri(
-                            gl::TEXTURE_2D,
-                            gl::TEXTURE_MIN_FILTER,
-                            gl::LINEAR_MIPMAP_LINEAR as GLint,
-                        );
-                        gl::TexParameteri(
-                            gl::TEXTURE_2D,
-                            gl::TEXTURE_MAG_FILTER,
-                            gl::LINEAR_MIPMAP_LINEAR as GLint,
-                        );
-                        gl::GenerateMipmap(gl::TEXTURE_2D);
-                    }
+    fn visit_point(&mut self, point: &Point, _ctx: &VisitContext) {
+        self.add_leaf(|extractor| extractor.load_point(point));
+    }
 
-                    operations.push(Operation::DrawImage(texture_array));
-                }
-            }
-        }
+    fn visit_polygon(&mut self, polygon: &Polygon, _ctx: &VisitContext) {
+        self.add_leaf(|extractor| extractor.load_polygon(polygon));
+    }
 
-        operations
+    fn visit_polyline(&mut self, polyline: &Polyline, _ctx: &VisitContext) {
+        self.add_leaf(|extractor| extractor.load_polyline(polyline));
     }
 
-    fn execute(&self, shaders: &mut ShaderMgr) {
-        unsafe {
-            match self {
-                Operation::DrawPoints(point_array) => {
-                    point_array.draw(shaders);
-                }
-                Operation::DrawLines(line_vertex_array)
-                | Operation::DrawAdjacentLines(line_vertex_array) => {
-                    line_vertex_array.draw(shaders);
-                }
-                Operation::FillPolygon(element_buffer) => {
-                    element_buffer.draw(shaders);
-                }
-                Operation::FillConvexPolygon(triangle_fan) => {
-                    triangle_fan.draw(shaders);
-                }
-                Operation::DrawImage(texture_array) => {
-                    texture_array.draw(shaders);
-                }
-            }
-        }
+    fn visit_rect(&mut self, rect: &Rect, _ctx: &VisitContext) {
+        self.add_leaf(|extractor| extractor.load_rect(rect));
     }
 }
 
-struct GLViewer {
+/// A `Viewer` for a viewport of `width_px` by `height_px` drawn by a `GLSceneRenderer`.
+pub struct GLViewer {
     width_px: u32,
     height_px: u32,
     center: Vector2D<f32>,
@@ -1129,22 +1476,34 @@ struct GLViewer {
 
 impl Viewer for GLViewer {
     fn center_on_object(&mut self, object: &Object) {
-        let object_radius = object.svg_inst.dimension.clone() * 0.5;
-        self.center[0] = object.position[0] + object_radius[0];
-        self.center[1] = object.position[1] + object_radius[1];
+        self.frame_area(
+            &Vector2D::from_vector(&object.position),
+            &object.svg_inst.dimension,
+        );
+    }
 
-        // In OpenGL, screen coordinates range from -1.0 to 1.0.
-        // So screen width and height is always 2.0.
-        let zoom_x = 2.0 / object.svg_inst.dimension[0];
-        let zoom_y = 2.0 / object.svg_inst.dimension[1];
+    fn fit_to_content(&mut self, object: &Object) {
+        match object.svg_inst.stroke_bounds() {
+            Some(bounds) => self.frame_area(
+                &(Vector2D::from_vector(&object.position) + &bounds.min),
+                &bounds.size(),
+            ),
+            None => self.center_on_object(object),
+        }
+    }
 
-        self.zoom = zoom_x.min(zoom_y);
+    fn pixel_to_world(&self, x: f32, y: f32) -> Vector2D<f32> {
+        // The shaders flip the y axis of the viewer so it grows downwards like pixels do.
+        let viewer_x = x / self.width_px as f32 * 2.0 - 1.0;
+        let viewer_y = y / self.height_px as f32 * 2.0 - 1.0;
 
-        if self.zoom.is_infinite() {
-            self.zoom = 1.0;
+        // A zoom of zero shows the center of the viewer everywhere.
+        match self.norm_to_self_transform.inverse() {
+            Some(viewer_to_world) => Vector2D::from_vector(
+                &(Vector3D::from([viewer_x, viewer_y, 1.0]) * &viewer_to_world),
+            ),
+            None => self.center.clone(),
         }
-
-        self.update_norm_to_self_transform();
     }
 
     fn move_to_world_coords(&mut self, new_center: Vector2D<f32>) {
@@ -1178,7 +1537,25 @@ impl Viewer for GLViewer {
 }
 
 impl GLViewer {
-    fn new(width_px: u32, height_px: u32) -> Self {
+    // Centers the viewer on an area of the world and zooms in until it fills the screen.
+    fn frame_area(&mut self, top_left: &Vector2D<f32>, size: &Vector2D<f32>) {
+        self.center = top_left + &(size.clone() * 0.5);
+
+        // In OpenGL, screen coordinates range from -1.0 to 1.0.
+        // So screen width and height is always 2.0.
+        let zoom_x = 2.0 / size[0];
+        let zoom_y = 2.0 / size[1];
+
+        self.zoom = zoom_x.min(zoom_y);
+
+        if self.zoom.is_infinite() {
+            self.zoom = 1.0;
+        }
+
+        self.update_norm_to_self_transform();
+    }
+
+    pub fn new(width_px: u32, height_px: u32) -> Self {
         const DEFAULT_CENTER: Vector2D<f32> = Vector2D::ZERO;
         const DEFAULT_ZOOM: f32 = 1.0;
 
@@ -1195,17 +1572,15 @@ impl GLViewer {
         }
     }
 
-    fn resize(&mut self, new_width: u32, new_height: u32) {
+    /// Keeps the view undistorted on a viewport of the new size.
+    pub fn resize(&mut self, new_width: u32, new_height: u32) {
         self.width_px = new_width;
         self.height_px = new_height;
         self.update_norm_to_self_transform();
-
-        unsafe {
-            gl::Viewport(0, 0, new_width as i32, new_height as i32);
-        }
     }
 
-    fn get_norm_to_viewer(&self) -> &Matrix3x3<f32> {
+    /// The transform `GLSceneRenderer::render` expects to draw what this viewer sees.
+    pub fn get_norm_to_viewer(&self) -> &Matrix3x3<f32> {
         &self.norm_to_self_transform
     }
 
@@ -1242,41 +1617,342 @@ impl GLViewer {
     }
 }
 
+/// The area of the framebuffer to draw into, in pixels from its bottom left corner.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GLViewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl GLViewport {
+    /// A viewport covering a whole framebuffer of the given size.
+    pub fn full(width: u32, height: u32) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+}
+
+/// Draws the objects of an `ObjectMgr` into whichever framebuffer is bound, in an OpenGL 3.3 core
+/// context owned by the caller.
+///
+/// The context has to be current and its functions loaded with `gl::load_with` when the renderer
+/// is created, on every call and when it is dropped. Drawing changes the bound program, vertex
+/// array, textures and blending state and leaves them changed. It never clears or swaps buffers.
+pub struct GLSceneRenderer {
+    shaders: ShaderMgr,
+    objects: Vec<OperationNode>,
+    // The operations of every object packed into as few draw calls as possible. They are packed
+    // again on the next render after any object changes.
+    batches: Vec<Batch>,
+    batches_outdated: bool,
+    // Where each operation went in the batches, in paint order, and an index over the bounds of
+    // the operations that have them so that only the visible ones are drawn.
+    placements: Vec<Placement>,
+    index: Bvh,
+    // The operations in the order the index knows them by, and those it does not know.
+    indexed: Vec<usize>,
+    unbounded: Vec<usize>,
+    // The vertices each document may generate.
+    max_vertices: usize,
+    stroke_mode: StrokeMode,
+}
+
+impl GLSceneRenderer {
+    pub fn new(object_mgr: &ObjectMgr) -> Result<Self, String> {
+        Self::with_limits(object_mgr, &Limits::NONE)
+    }
+
+    /// Like `new`, but fails rather than generate more vertices for a document than
+    /// `Limits::max_vertices` allows. Strokes drawn as triangles are counted once they are
+    /// turned into triangles.
+    pub fn with_limits(object_mgr: &ObjectMgr, limits: &Limits) -> Result<Self, String> {
+        let mut shaders = ShaderMgr::new()?;
+        let stroke_mode = StrokeMode::default();
+
+        let objects = object_mgr
+            .get_objects()
+            .iter()
+            .map(|object| {
+                let mut vertices_left = limits.max_vertices;
+                OperationNode::gen_from_svg(
+                    &object.svg_inst,
+                    &mut shaders,
+                    stroke_mode,
+                    &mut vertices_left,
+                )
+            })
+            .collect::<Result<_, _>>()
+            .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            shaders,
+            objects,
+            batches: Vec::new(),
+            batches_outdated: true,
+            placements: Vec::new(),
+            index: Bvh::new(&[]),
+            indexed: Vec::new(),
+            unbounded: Vec::new(),
+            max_vertices: limits.max_vertices,
+            stroke_mode,
+        })
+    }
+
+    /// Draws strokes another way from now on. Every object is generated again on the next
+    /// `update_objects`, and nothing is drawn until then.
+    pub fn set_stroke_mode(&mut self, stroke_mode: StrokeMode) {
+        if stroke_mode != self.stroke_mode {
+            self.stroke_mode = stroke_mode;
+            self.objects.clear();
+            self.batches_outdated = true;
+        }
+    }
+
+    /// Catches up with the objects added to `object_mgr` and the edits made to their documents
+    /// since the last update.
+    ///
+    /// A document that would go over the vertex limit is not drawn until it is edited again.
+    /// Returns the first such error.
+    pub fn update_objects(&mut self, object_mgr: &mut ObjectMgr) -> Result<(), String> {
+        let shaders = &mut self.shaders;
+        let mut result = Ok(());
+
+        for (index, object) in object_mgr.get_objects_mut().iter_mut().enumerate() {
+            let changes = object.svg_inst.take_changes();
+
+            let Some(node) = self.objects.get_mut(index) else {
+                let mut vertices_left = self.max_vertices;
+                let node = OperationNode::gen_from_svg(
+                    &object.svg_inst,
+                    shaders,
+                    self.stroke_mode,
+                    &mut vertices_left,
+                );
+                self.objects.push(node.unwrap_or_else(|err| {
+                    result = result.clone().and(Err(err.to_string()));
+                    OperationNode::new()
+                }));
+                self.batches_outdated = true;
+                continue;
+            };
+
+            if changes.is_empty() {
+                continue;
+            }
+            self.batches_outdated = true;
+
+            let regenerated = if changes
+                .iter()
+                .all(|change| node.apply_change(change).is_some())
+            {
+                let mut vertices_left = self.max_vertices.saturating_sub(node.total_vertices());
+                node.regenerate_dirty(
+                    &object.svg_inst,
+                    &mut Vec::new(),
+                    shaders,
+                    self.stroke_mode,
+                    &mut vertices_left,
+                )
+            } else {
+                let mut vertices_left = self.max_vertices;
+                OperationNode::gen_from_svg(
+                    &object.svg_inst,
+                    shaders,
+                    self.stroke_mode,
+                    &mut vertices_left,
+                )
+                .map(|new_node| *node = new_node)
+            };
+
+            // The tree may be left half generated. An empty one does not fit the document, so
+            // the next edit generates all of it again.
+            if let Err(err) = regenerated {
+                *node = OperationNode::new();
+                result = result.and(Err(err.to_string()));
+            }
+        }
+
+        result
+    }
+
+    /// Draws every object into `viewport`. `norm_to_viewer` maps world coordinates to the
+    /// viewport's normalized device coordinates, with y growing downwards. A `GLViewer` provides
+    /// one through `GLViewer::get_norm_to_viewer`.
+    pub fn render(&mut self, viewport: &GLViewport, norm_to_viewer: &Matrix3x3<f32>) {
+        unsafe {
+            gl::Viewport(
+                viewport.x,
+                viewport.y,
+                viewport.width as GLsizei,
+                viewport.height as GLsizei,
+            );
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            self.shaders.update_norm_to_viewer(norm_to_viewer);
+            self.shaders
+                .update_viewport_size(viewport.width, viewport.height);
+        }
+
+        if self.batches_outdated {
+            self.pack_batches();
+        }
+
+        let mut visible = Vec::new();
+        match Self::view_bounds(viewport, norm_to_viewer) {
+            Some(view) => {
+                self.index.query(&view, &mut visible);
+                for operation in visible.iter_mut() {
+                    *operation = self.indexed[*operation];
+                }
+                visible.extend_from_slice(&self.unbounded);
+                visible.sort_unstable();
+            }
+            None => visible.extend(0..self.placements.len()),
+        }
+
+        for (batch, ranges) in Placement::merge(&self.placements, &visible) {
+            self.batches[batch].execute(&mut self.shaders, &ranges);
+        }
+    }
+
+    // The part of the world inside the viewport, with a couple of pixels to spare for the
+    // anti-aliased edges of shapes. None if the whole world is squashed into a line or a point.
+    fn view_bounds(viewport: &GLViewport, norm_to_viewer: &Matrix3x3<f32>) -> Option<BoundingBox> {
+        let viewer_to_world = norm_to_viewer.inverse()?;
+        let margin_x = 1.0 + 4.0 / viewport.width.max(1) as f32;
+        let margin_y = 1.0 + 4.0 / viewport.height.max(1) as f32;
+
+        let mut bounds: Option<BoundingBox> = None;
+        for (x, y) in [
+            (-margin_x, -margin_y),
+            (margin_x, -margin_y),
+            (margin_x, margin_y),
+            (-margin_x, margin_y),
+        ] {
+            let corner = Vector2D::from_vector(&(Vector3D::from([x, y, 1.0]) * &viewer_to_world));
+            match bounds.as_mut() {
+                Some(bounds) => bounds.include(corner[0], corner[1]),
+                None => bounds = Some(BoundingBox::from_point(corner[0], corner[1])),
+            }
+        }
+
+        bounds
+    }
+
+    // Only the batches whose operations changed are uploaded again. Those that keep their size
+    // are written over in place.
+    fn pack_batches(&mut self) {
+        let mut operations = Vec::new();
+        for node in self.objects.iter_mut() {
+            node.collect_operations(&mut operations);
+        }
+
+        let mut boxes = Vec::new();
+        self.indexed.clear();
+        self.unbounded.clear();
+        for (index, operation) in operations.iter().enumerate() {
+            match operation.bounds() {
+                Some(bounds) => {
+                    boxes.push(bounds);
+                    self.indexed.push(index);
+                }
+                None => self.unbounded.push(index),
+            }
+        }
+        self.index = Bvh::new(&boxes);
+
+        let old_sizes: Vec<(u32, u32)> = self.batches.iter().map(Batch::size).collect();
+        let layout = Layout::new(operations.iter().map(|operation| &**operation), &old_sizes);
+
+        // New batches copy what they keep out of the old ones before those are written over.
+        let mut old: Vec<Option<Batch>> = self.batches.drain(..).map(Some).collect();
+        let mut batches: Vec<Option<Batch>> = layout
+            .plans
+            .iter()
+            .map(|plan| match plan.reused {
+                Some(_) => None,
+                None => Some(Batch::build(plan, &operations, &old, &mut self.shaders)),
+            })
+            .collect();
+        for (plan, batch) in layout.plans.iter().zip(batches.iter_mut()) {
+            let Some(reused) = plan.reused else {
+                continue;
+            };
+            let mut reused = old[reused]
+                .take()
+                .expect("Old batches are only reused once");
+            for index in plan.operations.clone() {
+                reused.write(
+                    operations[index],
+                    &layout.placements[index],
+                    &layout.vertices[index],
+                );
+            }
+            *batch = Some(reused);
+        }
+        drop(old);
+        self.batches = batches
+            .into_iter()
+            .map(|batch| batch.expect("Every batch is built or reused"))
+            .collect();
+
+        // The data of the operations now only lives in the batches.
+        for ((operation, placement), vertices) in operations
+            .into_iter()
+            .zip(layout.placements.iter())
+            .zip(layout.vertices)
+        {
+            match operation {
+                Operation::Packed(packed) => {
+                    packed.placement.clone_from(placement);
+                    packed.vertices = vertices;
+                }
+                operation => {
+                    *operation = Operation::Packed(PackedOperation {
+                        kind: operation.kind(),
+                        bounds: operation.bounds(),
+                        placement: placement.clone(),
+                        vertices,
+                    })
+                }
+            }
+        }
+        self.placements = layout.placements;
+        self.batches_outdated = false;
+    }
+}
+
+/// Draws into an SDL2 window that it owns along with the window's OpenGL context.
+#[cfg(feature = "sdl")]
 pub struct GLRenderer {
     window: Window,
     _gl_ctx: GLContext,
     viewer: GLViewer,
-    shaders: RefCell<ShaderMgr>,
-    operation: Vec<Operation>,
+    scene: GLSceneRenderer,
 }
 
+#[cfg(feature = "sdl")]
 impl GLRenderer {
     pub fn new(window: Window, gl_ctx: GLContext, object_mgr: &ObjectMgr) -> Result<Self, String> {
         let window_size = window.size();
+        let scene = GLSceneRenderer::new(object_mgr)?;
 
-        let mut shaders = ShaderMgr::new()?;
-
-        let mut operations = Vec::new();
-        for object in object_mgr.get_objects() {
-            operations.extend(Operation::gen_from_svg(&object.svg_inst, &mut shaders));
-        }
-
-        let gl_renderer = Self {
+        Ok(Self {
             window,
             _gl_ctx: gl_ctx,
             viewer: GLViewer::new(window_size.0, window_size.1),
-            shaders: RefCell::new(shaders),
-            operation: operations,
-        };
-
-        Ok(gl_renderer)
-    }
-
-    fn perform_operation(&self, operation: &Operation) {
-        operation.execute(&mut *self.shaders.borrow_mut());
+            scene,
+        })
     }
 }
 
+#[cfg(feature = "sdl")]
 impl Renderer for GLRenderer {
     fn get_viewer(&mut self) -> &mut dyn Viewer {
         &mut self.viewer
@@ -1303,17 +1979,16 @@ impl Renderer for GLRenderer {
         }
     }
 
-    fn render_objects(&mut self) {
-        // update uniform controlling the viewer transform (if necessary? Maybe do that only when it updates?)
-        unsafe {
-            self.shaders
-                .borrow_mut()
-                .update_norm_to_viewer(self.viewer.get_norm_to_viewer());
+    fn update_objects(&mut self, object_mgr: &mut ObjectMgr) {
+        if let Err(err) = self.scene.update_objects(object_mgr) {
+            println!("Error while updating the scene: {}", err);
         }
+    }
 
-        for operation in self.operation.iter() {
-            self.perform_operation(operation);
-        }
+    fn render_objects(&mut self, _object_mgr: &ObjectMgr) {
+        let viewport = GLViewport::full(self.viewer.width_px, self.viewer.height_px);
+        self.scene
+            .render(&viewport, self.viewer.get_norm_to_viewer());
     }
 
     fn present(&mut self) {
@@ -1324,12 +1999,18 @@ impl Renderer for GLRenderer {
 #[cfg(test)]
 mod tests {
     use crate::{
-        objects::{svg::SVG, Object},
+        objects::{
+            svg::{read_from_str, Change, Element, EmptyTag, ParseOptions, SVG},
+            Object,
+        },
         render::Viewer,
         vector::{Vector2D, Vector3D},
     };
 
-    use super::GLViewer;
+    use super::{
+        shaders, BatchKind, GLViewer, Layout, Operation, OperationExtractor, OperationNode,
+        PackedOperation, Placement, RawOperationData, StrokeMode, VertexData,
+    };
 
     fn new_viewer() -> GLViewer {
         GLViewer::new(100, 100)
@@ -1407,10 +2088,7 @@ mod tests {
         let mut viewer = new_viewer();
         let object = Object {
             position: Vector3D::from([4.0, -3.0, 1.0]),
-            svg_inst: SVG {
-                dimension: Vector2D::from([20.0, 20.0]),
-                elements: Vec::new(),
-            },
+            svg_inst: SVG::new(Vector2D::from([20.0, 20.0])),
         };
 
         viewer.center_on_object(&object);
@@ -1426,10 +2104,7 @@ mod tests {
         let mut viewer = new_viewer();
         let object = Object {
             position: Vector3D::from([4.0, -3.0, 1.0]),
-            svg_inst: SVG {
-                dimension: Vector2D::from([10.0, 25.0]),
-                elements: Vec::new(),
-            },
+            svg_inst: SVG::new(Vector2D::from([10.0, 25.0])),
         };
 
         viewer.center_on_object(&object);
@@ -1437,15 +2112,29 @@ mod tests {
         assert_eq!(viewer.zoom, 2.0 / 25.0)
     }
 
+    #[test]
+    fn viewer_fits_to_the_content_of_an_object() {
+        let mut viewer = new_viewer();
+        let object = Object {
+            position: Vector3D::from([4.0, -3.0, 1.0]),
+            svg_inst: read_from_str(
+                r#"<svg width="100" height="100"><rect x="10" y="20" width="4" height="2" stroke="none"/></svg>"#,
+                &ParseOptions::STRICT,
+            )
+            .unwrap(),
+        };
+
+        viewer.fit_to_content(&object);
+
+        assert_eq!(viewer.center, Vector2D::from([16.0, 18.0]));
+    }
+
     #[test]
     fn viewer_shouldnt_zoom_infinitely_when_object_size_is_zero() {
         let mut viewer = new_viewer();
         let object = Object {
             position: Vector3D::from([4.0, -3.0, 1.0]),
-            svg_inst: SVG {
-                dimension: Vector2D::from([0.0, 0.0]),
-                elements: Vec::new(),
-            },
+            svg_inst: SVG::new(Vector2D::from([0.0, 0.0])),
         };
 
         viewer.center_on_object(&object);
@@ -1453,6 +2142,22 @@ mod tests {
         assert_ne!(viewer.zoom, f32::INFINITY)
     }
 
+    #[test]
+    fn pixels_map_back_to_the_world_coordinates_drawn_there() {
+        let mut viewer = new_viewer();
+        viewer.move_to_world_coords(Vector2D::from([5.0, -5.0]));
+        viewer.zoom_to(0.5);
+
+        assert_eq!(
+            viewer.pixel_to_world(50.0, 50.0),
+            Vector2D::from([5.0, -5.0])
+        );
+        assert_eq!(
+            viewer.pixel_to_world(100.0, 100.0),
+            Vector2D::from([7.0, -3.0])
+        );
+    }
+
     #[test]
     fn viewer_centers_itself_on_position_to_move_to() {
         let mut viewer = new_viewer();
@@ -1476,4 +2181,361 @@ mod tests {
 
         assert_eq!(delta_position * (1.0 / ZOOM_AMOUNT), center_after_move);
     }
+
+    fn new_node(children: Vec<OperationNode>) -> OperationNode {
+        OperationNode {
+            children,
+            ..OperationNode::new()
+        }
+    }
+
+    #[test]
+    fn operation_tree_follows_document_changes() {
+        let mut root = new_node(vec![
+            new_node(vec![new_node(Vec::new()), new_node(Vec::new())]),
+            new_node(Vec::new()),
+        ]);
+
+        assert!(root.apply_change(&Change::Inserted(vec![0, 1])).is_some());
+        assert_eq!(root.children[0].children.len(), 3);
+        assert!(root.children[0].children[1].is_dirty);
+
+        assert!(root.apply_change(&Change::Removed(vec![1])).is_some());
+        assert_eq!(root.children.len(), 1);
+
+        assert!(root.apply_change(&Change::Modified(vec![0, 0])).is_some());
+        assert!(root.children[0].children[0].is_dirty);
+        assert!(!root.children[0].is_dirty);
+    }
+
+    fn points(positions: &[f32]) -> Operation {
+        let mut vertices = VertexData::default();
+        for position in positions {
+            vertices
+                .data
+                .extend_from_slice(&[*position, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+            vertices.elements.push(vertices.elements.len() as u32);
+        }
+        Operation::Draw(BatchKind::Points, vertices)
+    }
+
+    #[test]
+    fn consecutive_operations_of_a_kind_are_packed_together() {
+        let fill = Operation::Draw(
+            BatchKind::Triangles,
+            VertexData {
+                data: vec![0.0; 3 * shaders::BASIC_VERTEX_SIZE as usize],
+                elements: vec![2, 1, 0],
+            },
+        );
+        let operations = [points(&[1.0]), points(&[2.0, 3.0]), fill, points(&[4.0])];
+
+        let layout = Layout::new(&operations, &[]);
+
+        assert_eq!(
+            layout
+                .plans
+                .iter()
+                .map(|plan| (plan.operations.clone(), plan.size))
+                .collect::<Vec<_>>(),
+            vec![(0..2, (3, 3)), (2..3, (3, 3)), (3..4, (1, 1))]
+        );
+        assert!(layout.plans.iter().all(|plan| plan.reused.is_none()));
+        assert_eq!(
+            layout.placements,
+            vec![
+                Placement {
+                    batch: 0,
+                    range: 0..1
+                },
+                Placement {
+                    batch: 0,
+                    range: 1..3
+                },
+                Placement {
+                    batch: 1,
+                    range: 0..3
+                },
+                Placement {
+                    batch: 2,
+                    range: 0..1
+                },
+            ]
+        );
+        assert_eq!(layout.vertices, vec![0..1, 1..3, 0..3, 0..1]);
+    }
+
+    #[test]
+    fn appended_vertices_keep_their_elements() {
+        let mut merged = points(&[1.0]);
+        merged.append(&points(&[2.0, 3.0]));
+
+        let Operation::Draw(BatchKind::Points, vertices) = &merged else {
+            panic!("Expected the points to stay points");
+        };
+        assert_eq!(vertices.elements, vec![0, 1, 2]);
+        assert_eq!(
+            vertices
+                .data
+                .chunks(shaders::BASIC_VERTEX_SIZE as usize)
+                .map(|vertex| vertex[0])
+                .collect::<Vec<_>>(),
+            vec![1.0, 2.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn consecutive_shapes_share_one_instance_buffer() {
+        let size = shaders::SHAPE_INSTANCE_SIZE as usize;
+        let operations = [
+            Operation::DrawShapes(vec![1.0; size]),
+            Operation::DrawShapes(vec![2.0; size]),
+            points(&[1.0]),
+            Operation::DrawShapes(vec![3.0; size]),
+        ];
+
+        let layout = Layout::new(&operations, &[]);
+
+        assert_eq!(layout.plans.len(), 3);
+        assert_eq!(layout.plans[0].size, (2, 2));
+        assert_eq!(layout.plans[2].operations, 3..4);
+
+        let mut merged = Operation::DrawShapes(Vec::new());
+        merged.append(&operations[0]);
+        merged.append(&operations[1]);
+        let Operation::DrawShapes(instances) = &merged else {
+            panic!("Expected the shapes to stay shapes");
+        };
+        assert_eq!(instances.len(), 2 * size);
+        assert_eq!((instances[0], instances[size]), (1.0, 2.0));
+    }
+
+    fn packed(operation: &Operation, batch: usize, first: u32) -> Operation {
+        let (num_elements, num_vertices) = operation.size();
+        Operation::Packed(PackedOperation {
+            kind: operation.kind(),
+            bounds: None,
+            placement: Placement {
+                batch,
+                range: first..first + num_elements,
+            },
+            vertices: first..first + num_vertices,
+        })
+    }
+
+    #[test]
+    fn batches_are_kept_while_their_operations_line_up() {
+        // The old batch holds points of sizes 1, 2 and 1.
+        let old_sizes = [(4, 4)];
+        let first = || packed(&points(&[1.0]), 0, 0);
+        let last = || packed(&points(&[4.0]), 0, 3);
+
+        let same_size = [first(), points(&[2.0, 3.0]), last()];
+        let layout = Layout::new(&same_size, &old_sizes);
+        assert_eq!(layout.plans[0].reused, Some(0));
+        assert_eq!(layout.vertices[1], 1..3);
+
+        let grown = [first(), points(&[2.0, 3.0, 5.0]), last()];
+        assert_eq!(Layout::new(&grown, &old_sizes).plans[0].reused, None);
+
+        let removed = [first(), last()];
+        assert_eq!(Layout::new(&removed, &old_sizes).plans[0].reused, None);
+
+        // Only one of the batches split out of an old batch can keep it.
+        let split = [
+            first(),
+            points(&[2.0, 3.0, 4.0]),
+            Operation::DrawShapes(vec![1.0; shaders::SHAPE_INSTANCE_SIZE as usize]),
+            last(),
+        ];
+        let layout = Layout::new(&split, &old_sizes);
+        assert_eq!(layout.plans[0].reused, Some(0));
+        assert_eq!(layout.plans[2].reused, None);
+    }
+
+    #[test]
+    fn visible_neighbours_are_drawn_as_one_range() {
+        let placements = [
+            Placement {
+                batch: 0,
+                range: 0..3,
+            },
+            Placement {
+                batch: 0,
+                range: 3..6,
+            },
+            Placement {
+                batch: 0,
+                range: 6..9,
+            },
+            Placement {
+                batch: 1,
+                range: 0..1,
+            },
+            Placement {
+                batch: 2,
+                range: 0..2,
+            },
+        ];
+
+        let ranges = |operations: &[usize]| {
+            Placement::merge(&placements, operations)
+                .into_iter()
+                .flat_map(|(batch, ranges)| ranges.into_iter().map(move |range| (batch, range)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ranges(&[0, 1, 3, 4]), vec![(0, 0..6), (1, 0..1), (2, 0..2)]);
+        assert_eq!(ranges(&[0, 2]), vec![(0, 0..3), (0, 6..9)]);
+        assert_eq!(Placement::merge(&placements, &[0, 2]).len(), 1);
+    }
+
+    #[test]
+    fn operation_bounds_cover_stroked_shapes() {
+        // A rect of 4 by 2 around (1, 1) with a stroke of 2, scaled by 2 and moved by (10, 0).
+        let mut instance = vec![0.0; shaders::SHAPE_INSTANCE_SIZE as usize];
+        instance[shaders::SHAPE_CENTER..][..2].copy_from_slice(&[1.0, 1.0]);
+        instance[shaders::SHAPE_HALF_SIZE..][..2].copy_from_slice(&[2.0, 1.0]);
+        instance[shaders::SHAPE_TRANSFORM_X..][..3].copy_from_slice(&[2.0, 0.0, 10.0]);
+        instance[shaders::SHAPE_TRANSFORM_Y..][..3].copy_from_slice(&[0.0, 2.0, 0.0]);
+        instance[shaders::SHAPE_STROKE_WIDTH] = 2.0;
+
+        let bounds = Operation::DrawShapes(instance).bounds().unwrap();
+        assert_eq!((bounds.min[0], bounds.min[1]), (6.0, -2.0));
+        assert_eq!((bounds.max[0], bounds.max[1]), (18.0, 6.0));
+
+        let bounds = points(&[3.0, -1.0]).bounds().unwrap();
+        assert_eq!((bounds.min[0], bounds.max[0]), (-1.0, 3.0));
+    }
+
+    #[test]
+    fn operations_are_collected_in_paint_order() {
+        let mut root = new_node(vec![new_node(Vec::new()), new_node(Vec::new())]);
+        root.operations.push(points(&[1.0]));
+        root.children[0].operations.push(points(&[2.0]));
+        root.children[1].operations.push(points(&[3.0]));
+
+        let mut operations = Vec::new();
+        root.collect_operations(&mut operations);
+
+        assert_eq!(
+            operations
+                .iter()
+                .map(|operation| match &**operation {
+                    Operation::Draw(_, vertices) => vertices.data[0],
+                    _ => panic!("Expected points"),
+                })
+                .collect::<Vec<_>>(),
+            vec![1.0, 2.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn operation_tree_rejects_changes_that_do_not_fit() {
+        let mut root = new_node(vec![new_node(Vec::new())]);
+
+        assert!(root.apply_change(&Change::Inserted(vec![2])).is_none());
+        assert!(root.apply_change(&Change::Removed(vec![1])).is_none());
+        assert!(root.apply_change(&Change::Modified(vec![0, 0])).is_none());
+    }
+
+    #[test]
+    fn strokes_that_go_over_the_vertex_limit_are_left_out() {
+        let svg = read_from_str(
+            r##"<svg><polyline points="0,0 10,0 10,10" fill="none" stroke="#000000" stroke-width="2"/></svg>"##,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+        let Element::EmptyTag(EmptyTag::Polyline(polyline)) = &svg.elements[0] else {
+            panic!("Expected a polyline");
+        };
+
+        for stroke_mode in [StrokeMode::GeometryShaders, StrokeMode::Triangles] {
+            let mut extractor = OperationExtractor::new(usize::MAX, stroke_mode);
+            extractor.load_polyline(polyline);
+            let needed = usize::MAX - extractor.vertices_left;
+            assert!(!extractor.over_limit && needed > 0);
+
+            let mut extractor = OperationExtractor::new(needed - 1, stroke_mode);
+            extractor.load_polyline(polyline);
+            assert!(extractor.over_limit);
+            assert!(extractor.data.is_empty());
+            assert_eq!(extractor.vertices_left, needed - 1);
+        }
+    }
+
+    #[test]
+    fn polyline_segments_are_drawn_with_their_neighbours() {
+        let svg = read_from_str(
+            r##"<svg><polyline points="0,0 10,0 10,10 0,10" fill="none" stroke="#000000" stroke-width="2" transform="scale(3)"/></svg>"##,
+            &ParseOptions::STRICT,
+        )
+        .unwrap();
+        let Element::EmptyTag(EmptyTag::Polyline(polyline)) = &svg.elements[0] else {
+            panic!("Expected a polyline");
+        };
+
+        let mut extractor = OperationExtractor::new(usize::MAX, StrokeMode::GeometryShaders);
+        extractor.load_polyline(polyline);
+
+        let [RawOperationData::Draw(BatchKind::LinesAdjacency, vertices)] = &extractor.data[..]
+        else {
+            panic!("Expected the outline to go through the adjacency shader");
+        };
+        assert!(vertices
+            .data
+            .chunks(shaders::BASIC_VERTEX_SIZE as usize)
+            .all(|vertex| vertex[shaders::BASIC_THICKNESS] == 6.0));
+        assert_eq!(vertices.elements, vec![0, 0, 1, 2, 0, 1, 2, 3, 1, 2, 3, 3]);
+    }
+
+    // Filled and stroked polylines of a few widths, like the illustrations in svg/illustration.
+    fn polylines(fill: &str) -> SVG {
+        let polylines: String = (0..12)
+            .map(|index| {
+                format!(
+                    r##"<polyline fill="{fill}" stroke="#000000" stroke-width="{width}" points="{left},0 {right},0 {right},10 {left},10"/>"##,
+                    fill = fill,
+                    width = 1 + index % 3,
+                    left = index * 20,
+                    right = index * 20 + 10,
+                )
+            })
+            .collect();
+        read_from_str(&format!("<svg>{}</svg>", polylines), &ParseOptions::STRICT).unwrap()
+    }
+
+    fn polyline_operations(svg: &SVG, stroke_mode: StrokeMode) -> Vec<Operation> {
+        let mut extractor = OperationExtractor::new(usize::MAX, stroke_mode);
+        for element in &svg.elements {
+            if let Element::EmptyTag(EmptyTag::Polyline(polyline)) = element {
+                extractor.load_polyline(polyline);
+            }
+        }
+
+        extractor
+            .data
+            .into_iter()
+            .map(|data| match data {
+                RawOperationData::Draw(kind, vertices) => Operation::Draw(kind, vertices),
+                _ => panic!("Expected polylines to draw geometry"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fills_and_strokes_are_drawn_in_one_batch() {
+        let operations = polyline_operations(&polylines("#ff0000"), StrokeMode::default());
+
+        assert_eq!(operations.len(), 24);
+        assert_eq!(Layout::new(&operations, &[]).plans.len(), 1);
+    }
+
+    #[test]
+    fn strokes_of_any_width_share_a_geometry_shader_batch() {
+        let operations = polyline_operations(&polylines("none"), StrokeMode::GeometryShaders);
+
+        assert_eq!(operations.len(), 12);
+        assert_eq!(Layout::new(&operations, &[]).plans.len(), 1);
+    }
 }
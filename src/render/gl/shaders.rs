@@ -7,10 +7,28 @@ pub const POS_SIZE: u8 = 2;
 pub const TEX_COORD_SIZE: u8 = 2;
 pub const COLOR_SIZE: u8 = 4;
 
+// A vertex is its position, its color and the thickness of the line it is on, which is zero for
+// anything that is not widened by a geometry shader.
+pub const BASIC_THICKNESS: usize = (POS_SIZE + COLOR_SIZE) as usize;
+pub const BASIC_VERTEX_SIZE: u8 = BASIC_THICKNESS as u8 + 1;
+
+// A shape is its center, half its size, the radii of its corners, the first two rows of its
+// transform, its fill and stroke colors, and its stroke width, each starting at its offset below.
+pub const SHAPE_CENTER: usize = 0;
+pub const SHAPE_HALF_SIZE: usize = SHAPE_CENTER + POS_SIZE as usize;
+pub const SHAPE_CORNER_RADII: usize = SHAPE_HALF_SIZE + POS_SIZE as usize;
+pub const SHAPE_TRANSFORM_X: usize = SHAPE_CORNER_RADII + POS_SIZE as usize;
+pub const SHAPE_TRANSFORM_Y: usize = SHAPE_TRANSFORM_X + 3;
+pub const SHAPE_FILL_COLOR: usize = SHAPE_TRANSFORM_Y + 3;
+pub const SHAPE_STROKE_COLOR: usize = SHAPE_FILL_COLOR + COLOR_SIZE as usize;
+pub const SHAPE_STROKE_WIDTH: usize = SHAPE_STROKE_COLOR + COLOR_SIZE as usize;
+pub const SHAPE_INSTANCE_SIZE: u8 = SHAPE_STROKE_WIDTH as u8 + 1;
+
 pub enum Shader {
     Basic,
     Line,
     LineAdjacency,
+    Shape,
     Texture,
 }
 
@@ -18,6 +36,7 @@ pub struct ShaderMgr {
     basic_shader: BasicShader,
     line_shader: LineShader,
     line_adjacency_shader: LineAdjacencyShader,
+    shape_shader: ShapeShader,
     texture_shader: TextureShader,
     active_shader: Shader,
 }
@@ -34,6 +53,7 @@ impl ShaderMgr {
             basic_shader: BasicShader::build()?,
             line_shader: LineShader::build()?,
             line_adjacency_shader: LineAdjacencyShader::build()?,
+            shape_shader: ShapeShader::build()?,
             texture_shader: TextureShader::build()?,
             active_shader: Shader::Basic,
         })
@@ -44,6 +64,7 @@ impl ShaderMgr {
             Shader::Basic => self.basic_shader.activate(),
             Shader::Line => self.line_shader.activate(),
             Shader::LineAdjacency => self.line_adjacency_shader.activate(),
+            Shader::Shape => self.shape_shader.activate(),
             Shader::Texture => self.texture_shader.activate(),
         }
         self.active_shader = shader;
@@ -54,10 +75,20 @@ impl ShaderMgr {
             Shader::Basic => self.basic_shader.attributes.bind(),
             Shader::Line => self.line_shader.attributes.bind(),
             Shader::LineAdjacency => self.line_adjacency_shader.attributes.bind(),
+            Shader::Shape => self.shape_shader.attributes.bind(),
             Shader::Texture => self.texture_shader.attributes.bind(),
         }
     }
 
+    // Instanced attributes can only start at another instance by pointing them further into the
+    // buffer, since a 3.3 context has no base instance for instanced draws.
+    pub unsafe fn bind_instances_from(&self, first_instance: usize) {
+        match self.active_shader {
+            Shader::Shape => self.shape_shader.attributes.bind_from(first_instance),
+            _ => panic!("Tried to offset the instances of a shader that is not instanced."),
+        }
+    }
+
     pub unsafe fn update_norm_to_viewer(&mut self, norm_to_viewer_transform: &Matrix3x3<f32>) {
         self.basic_shader.activate();
         self.basic_shader
@@ -74,54 +105,51 @@ impl ShaderMgr {
             .attributes
             .norm_to_viewer
             .update(norm_to_viewer_transform.clone());
+        self.shape_shader.activate();
+        self.shape_shader
+            .attributes
+            .norm_to_viewer
+            .update(norm_to_viewer_transform.clone());
         self.texture_shader.activate();
         self.texture_shader
             .attributes
             .norm_to_viewer
             .update(norm_to_viewer_transform.clone());
 
+        self.reactivate();
+    }
+
+    // Shapes need to know how big a pixel is to leave room for their anti-aliased edges.
+    pub unsafe fn update_viewport_size(&mut self, width: u32, height: u32) {
+        self.shape_shader.activate();
+        self.shape_shader
+            .attributes
+            .viewport_size
+            .update([width as f32, height as f32]);
+
+        self.reactivate();
+    }
+
+    unsafe fn reactivate(&self) {
         match self.active_shader {
             Shader::Basic => self.basic_shader.activate(),
             Shader::Line => self.line_shader.activate(),
             Shader::LineAdjacency => self.line_adjacency_shader.activate(),
+            Shader::Shape => self.shape_shader.activate(),
             Shader::Texture => self.texture_shader.activate(),
         }
     }
 
+    // Shapes are moved into world coordinates before they are uploaded so that many of them can
+    // be drawn at once. Only images still carry their transform to the shader.
     pub unsafe fn set_svg_transform(&mut self, svg_transform: Matrix3x3<f32>) {
         match self.active_shader {
-            Shader::Basic => self
-                .basic_shader
-                .attributes
-                .svg_transform
-                .update(svg_transform),
-            Shader::Line => self
-                .line_shader
-                .attributes
-                .svg_transform
-                .update(svg_transform),
-            Shader::LineAdjacency => self
-                .line_adjacency_shader
-                .attributes
-                .svg_transform
-                .update(svg_transform),
             Shader::Texture => self
                 .texture_shader
                 .attributes
                 .svg_transform
                 .update(svg_transform),
-        }
-    }
-
-    pub unsafe fn set_line_thickness(&mut self, thickness: f32) {
-        match self.active_shader {
-            Shader::Line => self.line_shader.attributes.thickness.update(thickness),
-            Shader::LineAdjacency => self
-                .line_adjacency_shader
-                .attributes
-                .thickness
-                .update(thickness),
-            _ => panic!("Tried to update line thickness on a shader that does not support it."),
+            _ => panic!("Tried to set the svg transform for a shader that does not support it."),
         }
     }
 
@@ -155,35 +183,35 @@ where
     current_value: Option<T>,
 }
 
-impl Uniform<f32> {
-    fn update(&mut self, new_value: f32) {
+impl Uniform<Matrix3x3<f32>> {
+    fn update(&mut self, new_value: Matrix3x3<f32>) {
         match &self.current_value {
             Some(value) if *value == new_value => return,
             _ => {}
         }
 
         unsafe {
-            gl::Uniform1f(self.uniform_index, new_value);
+            gl::UniformMatrix3fv(
+                self.uniform_index,
+                1,
+                gl::TRUE,
+                <&Matrix3x3<f32> as Into<&[[f32; 3]; 3]>>::into(&new_value)[0].as_ptr(),
+            );
         }
 
         self.current_value = Some(new_value);
     }
 }
 
-impl Uniform<Matrix3x3<f32>> {
-    fn update(&mut self, new_value: Matrix3x3<f32>) {
+impl Uniform<[f32; 2]> {
+    fn update(&mut self, new_value: [f32; 2]) {
         match &self.current_value {
             Some(value) if *value == new_value => return,
             _ => {}
         }
 
         unsafe {
-            gl::UniformMatrix3fv(
-                self.uniform_index,
-                1,
-                gl::TRUE,
-                <&Matrix3x3<f32> as Into<&[[f32; 3]; 3]>>::into(&new_value)[0].as_ptr(),
-            );
+            gl::Uniform2f(self.uniform_index, new_value[0], new_value[1]);
         }
 
         self.current_value = Some(new_value);
@@ -209,13 +237,18 @@ trait Attributes {
     fn get_position_index(&self) -> GLuint;
     fn get_color_index(&self) -> GLuint;
 
+    // Only the line shaders read the thickness of their vertices.
+    fn get_thickness_index(&self) -> Option<GLuint> {
+        None
+    }
+
     unsafe fn bind(&self) {
         gl::VertexAttribPointer(
             self.get_position_index(),
             POS_SIZE as GLint,
             gl::FLOAT,
             gl::FALSE,
-            ((POS_SIZE + COLOR_SIZE) as usize * std::mem::size_of::<f32>()) as gl::types::GLsizei,
+            (BASIC_VERTEX_SIZE as usize * std::mem::size_of::<f32>()) as gl::types::GLsizei,
             std::ptr::null(),
         );
 
@@ -224,12 +257,24 @@ trait Attributes {
             COLOR_SIZE as GLint,
             gl::FLOAT,
             gl::FALSE,
-            ((POS_SIZE + COLOR_SIZE) as usize * std::mem::size_of::<f32>()) as gl::types::GLsizei,
+            (BASIC_VERTEX_SIZE as usize * std::mem::size_of::<f32>()) as gl::types::GLsizei,
             (POS_SIZE as usize * std::mem::size_of::<f32>()) as *const c_void,
         );
 
         gl::EnableVertexAttribArray(self.get_position_index());
         gl::EnableVertexAttribArray(self.get_color_index());
+
+        if let Some(thickness_index) = self.get_thickness_index() {
+            gl::VertexAttribPointer(
+                thickness_index,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                (BASIC_VERTEX_SIZE as usize * std::mem::size_of::<f32>()) as gl::types::GLsizei,
+                (BASIC_THICKNESS * std::mem::size_of::<f32>()) as *const c_void,
+            );
+            gl::EnableVertexAttribArray(thickness_index);
+        }
     }
 }
 
@@ -237,7 +282,6 @@ struct BasicAttributes {
     position: GLuint,
     color: GLuint,
     norm_to_viewer: Uniform<Matrix3x3<f32>>,
-    svg_transform: Uniform<Matrix3x3<f32>>,
 }
 
 impl Attributes for BasicAttributes {
@@ -261,9 +305,6 @@ impl BasicAttributes {
         let norm_to_viewer = gl::GetUniformLocation(shader_program, c"norm_to_viewer".as_ptr());
         maybe_get_gl_error()?;
 
-        let svg_transform = gl::GetUniformLocation(shader_program, c"svg_transform".as_ptr());
-        maybe_get_gl_error()?;
-
         Ok(BasicAttributes {
             position: position as GLuint,
             color: color as GLuint,
@@ -271,10 +312,6 @@ impl BasicAttributes {
                 uniform_index: norm_to_viewer,
                 current_value: None,
             },
-            svg_transform: Uniform {
-                uniform_index: svg_transform,
-                current_value: None,
-            },
         })
     }
 }
@@ -282,9 +319,8 @@ impl BasicAttributes {
 struct LineAttributes {
     position: GLuint,
     color: GLuint,
+    thickness: GLuint,
     norm_to_viewer: Uniform<Matrix3x3<f32>>,
-    svg_transform: Uniform<Matrix3x3<f32>>,
-    thickness: Uniform<f32>,
 }
 
 impl Attributes for LineAttributes {
@@ -295,6 +331,10 @@ impl Attributes for LineAttributes {
     fn get_color_index(&self) -> GLuint {
         self.color
     }
+
+    fn get_thickness_index(&self) -> Option<GLuint> {
+        Some(self.thickness)
+    }
 }
 
 impl LineAttributes {
@@ -305,32 +345,92 @@ impl LineAttributes {
         let color = gl::GetAttribLocation(shader_program, c"color".as_ptr());
         maybe_get_gl_error()?;
 
-        let norm_to_viewer = gl::GetUniformLocation(shader_program, c"norm_to_viewer".as_ptr());
+        let thickness = gl::GetAttribLocation(shader_program, c"thickness".as_ptr());
         maybe_get_gl_error()?;
 
-        let svg_transform = gl::GetUniformLocation(shader_program, c"svg_transform".as_ptr());
-        maybe_get_gl_error()?;
-
-        let thickness = gl::GetUniformLocation(shader_program, c"thickness".as_ptr());
+        let norm_to_viewer = gl::GetUniformLocation(shader_program, c"norm_to_viewer".as_ptr());
         maybe_get_gl_error()?;
 
         Ok(LineAttributes {
             position: position as GLuint,
             color: color as GLuint,
+            thickness: thickness as GLuint,
             norm_to_viewer: Uniform {
                 uniform_index: norm_to_viewer,
                 current_value: None,
             },
-            svg_transform: Uniform {
-                uniform_index: svg_transform,
+        })
+    }
+}
+
+struct ShapeAttributes {
+    // Every attribute changes once per instance, in the same order as `PER_INSTANCE`.
+    per_instance: [GLuint; 8],
+    norm_to_viewer: Uniform<Matrix3x3<f32>>,
+    viewport_size: Uniform<[f32; 2]>,
+}
+
+impl ShapeAttributes {
+    // The name, offset and size of each attribute of an instance.
+    const PER_INSTANCE: [(&'static CStr, usize, u8); 8] = [
+        (c"center", SHAPE_CENTER, POS_SIZE),
+        (c"half_size", SHAPE_HALF_SIZE, POS_SIZE),
+        (c"corner_radii", SHAPE_CORNER_RADII, POS_SIZE),
+        (c"transform_x", SHAPE_TRANSFORM_X, 3),
+        (c"transform_y", SHAPE_TRANSFORM_Y, 3),
+        (c"fill_color", SHAPE_FILL_COLOR, COLOR_SIZE),
+        (c"stroke_color", SHAPE_STROKE_COLOR, COLOR_SIZE),
+        (c"stroke_width", SHAPE_STROKE_WIDTH, 1),
+    ];
+
+    unsafe fn new(shader_program: GLuint) -> Result<Self, String> {
+        let mut per_instance = [0; 8];
+        for (attribute, (name, _, _)) in per_instance.iter_mut().zip(Self::PER_INSTANCE) {
+            let index = gl::GetAttribLocation(shader_program, name.as_ptr());
+            maybe_get_gl_error()?;
+            *attribute = index as GLuint;
+        }
+
+        let norm_to_viewer = gl::GetUniformLocation(shader_program, c"norm_to_viewer".as_ptr());
+        maybe_get_gl_error()?;
+
+        let viewport_size = gl::GetUniformLocation(shader_program, c"viewport_size".as_ptr());
+        maybe_get_gl_error()?;
+
+        Ok(ShapeAttributes {
+            per_instance,
+            norm_to_viewer: Uniform {
+                uniform_index: norm_to_viewer,
                 current_value: None,
             },
-            thickness: Uniform {
-                uniform_index: thickness,
+            viewport_size: Uniform {
+                uniform_index: viewport_size,
                 current_value: None,
             },
         })
     }
+
+    unsafe fn bind(&self) {
+        self.bind_from(0);
+    }
+
+    unsafe fn bind_from(&self, first_instance: usize) {
+        let stride =
+            (SHAPE_INSTANCE_SIZE as usize * std::mem::size_of::<f32>()) as gl::types::GLsizei;
+        let first = first_instance * SHAPE_INSTANCE_SIZE as usize;
+        for (index, (_, offset, size)) in self.per_instance.into_iter().zip(Self::PER_INSTANCE) {
+            gl::VertexAttribPointer(
+                index,
+                size as GLint,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                ((first + offset) * std::mem::size_of::<f32>()) as *const c_void,
+            );
+            gl::VertexAttribDivisor(index, 1);
+            gl::EnableVertexAttribArray(index);
+        }
+    }
 }
 
 struct TextureAttributes {
@@ -402,6 +502,7 @@ impl TextureAttributes {
     }
 }
 
+/// Widens lines into quads in a geometry shader. Every pair of vertices is one line.
 struct LineShader {
     vertex_shader: GLuint,
     fragment_shader: GLuint,
@@ -415,11 +516,14 @@ impl LineShader {
 
 in vec2 position;
 in vec4 color;
+in float thickness;
 
 out vec4 VertexColor;
+out float VertexThickness;
 
 void main() {
     VertexColor = color;
+    VertexThickness = thickness;
     gl_Position = vec4(position, 0.0, 1.0);
 }";
 
@@ -439,14 +543,13 @@ layout(lines) in;
 layout(triangle_strip, max_vertices = 4) out;
 
 in vec4 VertexColor[];
+in float VertexThickness[];
 out vec4 GeoColor;
 
-uniform float thickness;
 uniform mat3 norm_to_viewer;
-uniform mat3 svg_transform;
 
 void EmitTransformedVertex(in vec2 position) {
-    vec3 transformed = vec3(position, 1.0) * svg_transform * norm_to_viewer;
+    vec3 transformed = vec3(position, 1.0) * norm_to_viewer;
     gl_Position = vec4(transformed.x, -transformed.y, 0.0, 1.0);
     EmitVertex();
 }
@@ -456,7 +559,7 @@ void main() {
     vec2 p1 = gl_in[1].gl_Position.xy;
 
     vec2 dir = normalize(p1 - p0);
-    vec2 offset = vec2(-dir.y, dir.x) * thickness * 0.5;
+    vec2 offset = vec2(-dir.y, dir.x) * VertexThickness[0] * 0.5;
 
     // Generate corners of rectangle
     vec2 v0 = p0 + offset;
@@ -467,11 +570,11 @@ void main() {
     GeoColor = VertexColor[0];
     EmitTransformedVertex(v0);
     EmitTransformedVertex(v1);
-    
+
     GeoColor = VertexColor[1];
     EmitTransformedVertex(v2);
     EmitTransformedVertex(v3);
-    
+
     EndPrimitive();
 }";
 }
@@ -538,6 +641,8 @@ impl Drop for LineShader {
     }
 }
 
+/// Widens the segments of polylines in a geometry shader, with lines adjacency primitives so that
+/// each segment can fill the gap to the next one.
 struct LineAdjacencyShader {
     vertex_shader: GLuint,
     fragment_shader: GLuint,
@@ -551,11 +656,14 @@ impl LineAdjacencyShader {
 
 in vec2 position;
 in vec4 color;
+in float thickness;
 
 out vec4 VertexColor;
+out float VertexThickness;
 
 void main() {
     VertexColor = color;
+    VertexThickness = thickness;
     gl_Position = vec4(position, 0.0, 1.0);
 }";
 
@@ -575,14 +683,13 @@ layout(lines_adjacency) in;
 layout(triangle_strip, max_vertices = 8) out;
 
 in vec4 VertexColor[];
+in float VertexThickness[];
 out vec4 GeoColor;
 
-uniform float thickness;
 uniform mat3 norm_to_viewer;
-uniform mat3 svg_transform;
 
 void EmitTransformedVertex(in vec2 position) {
-    vec3 transformed = vec3(position, 1.0) * svg_transform * norm_to_viewer;
+    vec3 transformed = vec3(position, 1.0) * norm_to_viewer;
     gl_Position = vec4(transformed.x, -transformed.y, 0.0, 1.0);
     EmitVertex();
 }
@@ -596,7 +703,7 @@ void main() {
     vec2 v1 = normalize(p2 - p1); // current segment direction
     vec2 v2 = normalize(p3 - p2); // next segment direction
 
-    vec2 n1 = vec2(-v1.y, v1.x) * thickness * 0.5;
+    vec2 n1 = vec2(-v1.y, v1.x) * VertexThickness[1] * 0.5;
 
     // Create basic line segment
     GeoColor = VertexColor[1];
@@ -609,12 +716,12 @@ void main() {
 
     // Handle join at p2
     if (p2 != p3) {
-        vec2 n2 = vec2(-v2.y, v2.x) * thickness * 0.5;
-        
+        vec2 n2 = vec2(-v2.y, v2.x) * VertexThickness[2] * 0.5;
+
         EmitTransformedVertex(p2 + n2);
         EmitTransformedVertex(p2 - n2);
     }
-    
+
     EndPrimitive();
 }";
 }
@@ -695,13 +802,12 @@ in vec2 position;
 in vec4 color;
 
 uniform mat3 norm_to_viewer;
-uniform mat3 svg_transform;
 
 out vec4 Color;
 
 void main() {
     Color = color;
-    vec3 transformed_position = vec3(position, 1.0) * svg_transform * norm_to_viewer;
+    vec3 transformed_position = vec3(position, 1.0) * norm_to_viewer;
     gl_Position = vec4(transformed_position.x, -transformed_position.y, 0.0, 1.0);
 }";
 
@@ -772,6 +878,170 @@ impl Drop for BasicShader {
     }
 }
 
+/// Draws rects and ellipses as instances of a quad, working out their edges for every pixel.
+///
+/// An ellipse is a rect whose corners are as round as they can be, so both use the same distance
+/// to the edge of a rounded rect.
+struct ShapeShader {
+    vertex_shader: GLuint,
+    fragment_shader: GLuint,
+    shader_program: GLuint,
+    attributes: ShapeAttributes,
+}
+
+impl ShapeShader {
+    const VERTEX_SHADER: &CStr = c"#version 150 core
+
+in vec2 center;
+in vec2 half_size;
+in vec2 corner_radii;
+in vec3 transform_x;
+in vec3 transform_y;
+in vec4 fill_color;
+in vec4 stroke_color;
+in float stroke_width;
+
+uniform mat3 norm_to_viewer;
+uniform vec2 viewport_size;
+
+out vec2 Offset;
+flat out vec2 HalfSize;
+flat out vec2 CornerRadii;
+flat out vec4 FillColor;
+flat out vec4 StrokeColor;
+flat out float HalfStrokeWidth;
+
+vec2 ToViewer(in vec2 position) {
+    vec3 svg_position = vec3(position, 1.0);
+    vec3 world_position = vec3(dot(svg_position, transform_x), dot(svg_position, transform_y), 1.0);
+    return (world_position * norm_to_viewer).xy;
+}
+
+void main() {
+    HalfSize = half_size;
+    CornerRadii = corner_radii;
+    FillColor = fill_color;
+    StrokeColor = stroke_color;
+    HalfStrokeWidth = stroke_width * 0.5;
+
+    // Leave a couple of pixels around the shape for its anti-aliased edge.
+    vec2 pixels_per_unit = vec2(
+        length((ToViewer(center + vec2(1.0, 0.0)) - ToViewer(center)) * viewport_size * 0.5),
+        length((ToViewer(center + vec2(0.0, 1.0)) - ToViewer(center)) * viewport_size * 0.5));
+    vec2 margin = 2.0 / max(pixels_per_unit, vec2(1e-6));
+
+    vec2 corner = vec2((gl_VertexID & 1) == 0 ? -1.0 : 1.0, (gl_VertexID & 2) == 0 ? -1.0 : 1.0);
+    Offset = corner * (half_size + HalfStrokeWidth + margin);
+
+    vec2 transformed_position = ToViewer(center + Offset);
+    gl_Position = vec4(transformed_position.x, -transformed_position.y, 0.0, 1.0);
+}";
+
+    const FRAGMENT_SHADER: &CStr = c"#version 150 core
+
+in vec2 Offset;
+flat in vec2 HalfSize;
+flat in vec2 CornerRadii;
+flat in vec4 FillColor;
+flat in vec4 StrokeColor;
+flat in float HalfStrokeWidth;
+
+out vec4 outColor;
+
+// How far a point is from the edge of an ellipse around the origin. It is negative inside.
+// Dividing by the length of the gradient keeps it close to the true distance near the edge.
+float EllipseDistance(in vec2 position, in vec2 radii) {
+    float scaled_length = length(position / radii);
+    vec2 gradient = position / (radii * radii);
+    return (scaled_length - 1.0) * scaled_length / max(length(gradient), 1e-12);
+}
+
+float ShapeDistance(in vec2 position) {
+    vec2 corner_position = abs(position) - (HalfSize - CornerRadii);
+    if (all(greaterThan(corner_position, vec2(0.0))) && all(greaterThan(CornerRadii, vec2(0.0)))) {
+        return EllipseDistance(corner_position, CornerRadii);
+    }
+
+    // Sharp corners keep their strokes square, like a miter join would.
+    vec2 box_position = abs(position) - HalfSize;
+    return max(box_position.x, box_position.y);
+}
+
+void main() {
+    float distance = ShapeDistance(Offset);
+    float pixel = max(fwidth(distance), 1e-6);
+
+    float fill = clamp(0.5 - distance / pixel, 0.0, 1.0) * FillColor.a;
+    float stroke = HalfStrokeWidth > 0.0
+        ? clamp(0.5 - (abs(distance) - HalfStrokeWidth) / pixel, 0.0, 1.0) * StrokeColor.a
+        : 0.0;
+
+    // The stroke is painted over the fill.
+    float alpha = stroke + fill * (1.0 - stroke);
+    if (alpha <= 0.0) {
+        discard;
+    }
+
+    vec3 color = StrokeColor.rgb * stroke + FillColor.rgb * fill * (1.0 - stroke);
+    outColor = vec4(color / alpha, alpha);
+}";
+}
+
+impl ShapeShader {
+    unsafe fn bind_fragment_shader_output(&self) -> Result<(), String> {
+        gl::BindFragDataLocation(self.shader_program, 0, c"outColor".as_ptr());
+
+        maybe_get_gl_error()?;
+
+        Ok(())
+    }
+}
+
+impl ShaderProgram for ShapeShader {
+    fn build() -> Result<Self, String> {
+        unsafe {
+            let shader_program = create_program()?;
+            let vertex_shader = send_compile_and_attach_shader(
+                gl::VERTEX_SHADER,
+                Self::VERTEX_SHADER,
+                shader_program,
+            )?;
+            let fragment_shader = send_compile_and_attach_shader(
+                gl::FRAGMENT_SHADER,
+                Self::FRAGMENT_SHADER,
+                shader_program,
+            )?;
+
+            link_program(shader_program)?;
+
+            let shader = Self {
+                vertex_shader,
+                fragment_shader,
+                shader_program,
+                attributes: ShapeAttributes::new(shader_program)?,
+            };
+
+            shader.bind_fragment_shader_output()?;
+
+            Ok(shader)
+        }
+    }
+
+    unsafe fn activate(&self) {
+        gl::UseProgram(self.shader_program);
+    }
+}
+
+impl Drop for ShapeShader {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader_program);
+            gl::DeleteShader(self.fragment_shader);
+            gl::DeleteShader(self.vertex_shader);
+        }
+    }
+}
+
 struct TextureShader {
     vertex_shader: GLuint,
     fragment_shader: GLuint,
@@ -1,7 +1,23 @@
-use crate::{objects::Object, vector::Vector2D};
+use crate::{
+    objects::{Object, ObjectMgr},
+    vector::Vector2D,
+};
 
+#[cfg(feature = "gl")]
+mod bvh;
+#[cfg(feature = "sdl")]
 pub mod canvas;
+mod element_cache;
+#[cfg(feature = "gl")]
 pub mod gl;
+mod pixel_viewer;
+#[cfg(feature = "sdl")]
+mod sdl_texture;
+pub mod software;
+#[cfg(feature = "sdl")]
+pub mod software_window;
+mod stroke;
+mod tessellation;
 pub mod triangulation;
 
 /// Virtual camera looking at a canvas containing SVG objects.
@@ -10,6 +26,11 @@ pub mod triangulation;
 /// is moving in the opposite direction.
 pub trait Viewer {
     fn center_on_object(&mut self, object: &Object);
+    /// Frames what is drawn in the object, including strokes, rather than its declared dimension.
+    fn fit_to_content(&mut self, object: &Object);
+
+    /// Maps a pixel of the window to the world coordinates drawn there.
+    fn pixel_to_world(&self, x: f32, y: f32) -> Vector2D<f32>;
 
     fn move_to_world_coords(&mut self, new_center: Vector2D<f32>);
     fn move_by_world_coords(&mut self, delta_x: f32, delta_y: f32);
@@ -28,11 +49,16 @@ pub trait Renderer {
 
     fn clear(&mut self);
 
-    fn render_objects(&mut self);
+    /// Catches up with the objects added to `object_mgr` and the edits made to their documents
+    /// since the last update.
+    fn update_objects(&mut self, object_mgr: &mut ObjectMgr);
+
+    fn render_objects(&mut self, object_mgr: &ObjectMgr);
 
     fn present(&mut self);
 }
 
+#[cfg(feature = "sdl")]
 fn bound_window_size(width: &mut u32, height: &mut u32) {
     const MIN_WINDOW_SIZE: u32 = 200;
 
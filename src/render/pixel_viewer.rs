@@ -0,0 +1,336 @@
+use num_traits::ConstZero;
+
+use crate::{
+    matrix::Matrix3x3,
+    objects::Object,
+    render::Viewer,
+    vector::{Vector2D, Vector3D},
+};
+
+/// A `Viewer` that maps the world straight to pixels. A zoom of 1 draws one world unit per pixel.
+pub struct PixelViewer {
+    width_px: u32,
+    height_px: u32,
+    center: Vector2D<f32>,
+    zoom: f32,
+    norm_to_self_transform: Matrix3x3<f32>,
+}
+
+impl Viewer for PixelViewer {
+    fn center_on_object(&mut self, object: &Object) {
+        self.frame_area(
+            &Vector2D::from_vector(&object.position),
+            &object.svg_inst.dimension,
+        );
+    }
+
+    fn fit_to_content(&mut self, object: &Object) {
+        match object.svg_inst.stroke_bounds() {
+            Some(bounds) => self.frame_area(
+                &(Vector2D::from_vector(&object.position) + &bounds.min),
+                &bounds.size(),
+            ),
+            None => self.center_on_object(object),
+        }
+    }
+
+    fn pixel_to_world(&self, x: f32, y: f32) -> Vector2D<f32> {
+        // A zoom of zero shows the center of the viewer everywhere.
+        match self.norm_to_self_transform.inverse() {
+            Some(pixel_to_world) => {
+                Vector2D::from_vector(&(Vector3D::from([x, y, 1.0]) * &pixel_to_world))
+            }
+            None => self.center.clone(),
+        }
+    }
+
+    fn move_to_world_coords(&mut self, new_center: Vector2D<f32>) {
+        self.center = new_center;
+        self.update_norm_to_self_transform();
+    }
+
+    fn move_by_world_coords(&mut self, delta_x: f32, delta_y: f32) {
+        let delta_center = Vector2D::from([delta_x, delta_y]);
+        self.center += delta_center * (1.0 / self.zoom);
+        self.update_norm_to_self_transform();
+    }
+
+    fn move_by_pixels(&mut self, delta_x: f32, delta_y: f32) {
+        self.move_by_world_coords(delta_x, delta_y)
+    }
+
+    fn zoom_to(&mut self, new_zoom: f32) {
+        self.zoom = new_zoom;
+        self.update_norm_to_self_transform();
+    }
+
+    fn zoom_by(&mut self, zoom_modifier: f32) {
+        self.zoom *= zoom_modifier;
+        self.update_norm_to_self_transform();
+    }
+}
+
+impl PixelViewer {
+    // Centers the viewer on an area of the world and zooms in until it fills the screen.
+    fn frame_area(&mut self, top_left: &Vector2D<f32>, size: &Vector2D<f32>) {
+        self.center = top_left + &(size.clone() * 0.5);
+
+        let zoom_x = self.width_px as f32 / size[0];
+        let zoom_y = self.height_px as f32 / size[1];
+
+        self.zoom = zoom_x.min(zoom_y);
+
+        if self.zoom.is_infinite() {
+            self.zoom = 1.0;
+        }
+
+        self.update_norm_to_self_transform();
+    }
+
+    pub fn new(width_px: u32, height_px: u32) -> Self {
+        const DEFAULT_CENTER: Vector2D<f32> = Vector2D::ZERO;
+        const DEFAULT_ZOOM: f32 = 1.0;
+        Self {
+            width_px,
+            height_px,
+            center: DEFAULT_CENTER,
+            zoom: DEFAULT_ZOOM,
+            norm_to_self_transform: Self::generate_norm_to_self_transform(
+                &DEFAULT_CENTER,
+                DEFAULT_ZOOM,
+                width_px,
+                height_px,
+            ),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width_px
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height_px
+    }
+
+    pub fn resize(&mut self, new_width: u32, new_height: u32) {
+        self.width_px = new_width;
+        self.height_px = new_height;
+        self.update_norm_to_self_transform();
+    }
+
+    pub fn get_norm_to_viewer(&self) -> &Matrix3x3<f32> {
+        &self.norm_to_self_transform
+    }
+
+    #[cfg(test)]
+    pub fn norm_to_viewer(&self, position: &Vector2D<f32>) -> Vector2D<f32> {
+        let transformed = Vector3D::from_vector(position) * &self.norm_to_self_transform;
+        Vector2D::from_vector(&transformed)
+    }
+
+    fn generate_norm_to_self_transform(
+        center: &Vector2D<f32>,
+        zoom: f32,
+        width_px: u32,
+        height_px: u32,
+    ) -> Matrix3x3<f32> {
+        // Translate to viewer position
+        let mut position_matrix = Matrix3x3::IDENTITY3X3;
+        position_matrix[2][0] = -center[0];
+        position_matrix[2][1] = -center[1];
+
+        // Zoom the appropriate amount
+        let mut zoom_matrix = Matrix3x3::IDENTITY3X3;
+        zoom_matrix[0][0] = zoom;
+        zoom_matrix[1][1] = zoom;
+
+        // Move origin to center of the viewer
+        let mut center_matrix = Matrix3x3::IDENTITY3X3;
+        center_matrix[2][0] = width_px as f32 / 2.0;
+        center_matrix[2][1] = height_px as f32 / 2.0;
+
+        &position_matrix * &zoom_matrix * &center_matrix
+    }
+
+    fn update_norm_to_self_transform(&mut self) {
+        self.norm_to_self_transform = Self::generate_norm_to_self_transform(
+            &self.center,
+            self.zoom,
+            self.width_px,
+            self.height_px,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        objects::{
+            svg::{read_from_str, ParseOptions, SVG},
+            Object,
+        },
+        render::Viewer,
+        vector::{Vector2D, Vector3D},
+    };
+
+    use super::PixelViewer;
+
+    fn new_viewer() -> PixelViewer {
+        PixelViewer::new(100, 100)
+    }
+
+    #[test]
+    fn init_at_origin() {
+        let viewer = new_viewer();
+        assert_eq!(viewer.center, Vector2D::from([0.0, 0.0]));
+    }
+
+    #[test]
+    fn pixels_at_viewer_center_map_to_the_screen_center() {
+        let viewer = PixelViewer::new(100, 100);
+        assert_eq!(
+            viewer.norm_to_viewer(&viewer.center),
+            Vector2D::from([50.0, 50.0])
+        );
+    }
+
+    #[test]
+    fn pixels_at_screen_center_are_unaffected_by_zoom() {
+        let mut viewer = new_viewer();
+        let pixel_mapping_before_zoom = viewer.norm_to_viewer(&viewer.center);
+        viewer.zoom_by(2.0);
+        let pixel_mapping_after_zoom = viewer.norm_to_viewer(&viewer.center);
+        assert_eq!(pixel_mapping_before_zoom, pixel_mapping_after_zoom);
+    }
+
+    #[test]
+    fn zoom_value_of_1_does_not_change_position_norm() {
+        const ZOOM_AMOUNT: f32 = 1.0;
+
+        let mut viewer = new_viewer();
+        let screen_center = viewer.norm_to_viewer(&viewer.center);
+        viewer.zoom_to(ZOOM_AMOUNT);
+
+        let pixel = Vector2D::from([3.0, 4.0]);
+        let position_norm_before_mapping = pixel.get_norm();
+        let position_norm_after_mapping =
+            (viewer.norm_to_viewer(&pixel) - screen_center).get_norm();
+
+        assert_eq!(position_norm_before_mapping, position_norm_after_mapping);
+    }
+
+    #[test]
+    fn zooming_moves_pixels_away_from_the_screen_center_by_the_same_amount() {
+        const ZOOM_AMOUNT: f32 = 3.77;
+
+        let mut viewer = new_viewer();
+        let screen_center = viewer.norm_to_viewer(&viewer.center);
+
+        let pixel = Vector2D::from([3.0, 4.0]);
+        let position_norm_before_zoom = (viewer.norm_to_viewer(&pixel) - &screen_center).get_norm();
+
+        viewer.zoom_by(ZOOM_AMOUNT);
+
+        let position_norm_after_zoom = (viewer.norm_to_viewer(&pixel) - &screen_center).get_norm();
+
+        assert_eq!(
+            position_norm_before_zoom * ZOOM_AMOUNT,
+            position_norm_after_zoom
+        );
+    }
+
+    #[test]
+    fn viewer_centers_on_a_given_object() {
+        let mut viewer = new_viewer();
+        let object = Object {
+            position: Vector3D::from([4.0, -3.0, 1.0]),
+            svg_inst: SVG::new(Vector2D::from([20.0, 20.0])),
+        };
+
+        viewer.center_on_object(&object);
+
+        assert_eq!(
+            viewer.center,
+            Vector2D::from([(20.0 / 2.0) + 4.0, (20.0 / 2.0) - 3.0])
+        );
+    }
+
+    #[test]
+    fn viewer_zooms_to_largest_dimension_of_object() {
+        let mut viewer = PixelViewer::new(100, 100);
+        let object = Object {
+            position: Vector3D::from([4.0, -3.0, 1.0]),
+            svg_inst: SVG::new(Vector2D::from([10.0, 25.0])),
+        };
+
+        viewer.center_on_object(&object);
+
+        assert_eq!(viewer.zoom, 100.0 / 25.0)
+    }
+
+    #[test]
+    fn viewer_fits_to_the_content_of_an_object() {
+        let mut viewer = new_viewer();
+        let object = Object {
+            position: Vector3D::from([4.0, -3.0, 1.0]),
+            svg_inst: read_from_str(
+                r#"<svg width="100" height="100"><rect x="10" y="20" width="4" height="2" stroke="none"/></svg>"#,
+                &ParseOptions::STRICT,
+            )
+            .unwrap(),
+        };
+
+        viewer.fit_to_content(&object);
+
+        assert_eq!(viewer.center, Vector2D::from([16.0, 18.0]));
+    }
+
+    #[test]
+    fn viewer_shouldnt_zoom_infinitely_when_object_size_is_zero() {
+        let mut viewer = new_viewer();
+        let object = Object {
+            position: Vector3D::from([4.0, -3.0, 1.0]),
+            svg_inst: SVG::new(Vector2D::from([0.0, 0.0])),
+        };
+
+        viewer.center_on_object(&object);
+
+        assert_ne!(viewer.zoom, f32::INFINITY)
+    }
+
+    #[test]
+    fn pixels_map_back_to_the_world_coordinates_drawn_there() {
+        let mut viewer = new_viewer();
+        viewer.move_to_world_coords(Vector2D::from([5.0, -5.0]));
+        viewer.zoom_to(2.0);
+
+        let world = Vector2D::from([8.0, 1.0]);
+        let pixel = viewer.norm_to_viewer(&world);
+
+        assert_eq!(viewer.pixel_to_world(pixel[0], pixel[1]), world);
+    }
+
+    #[test]
+    fn viewer_centers_itself_on_position_to_move_to() {
+        let mut viewer = new_viewer();
+        let new_center = Vector2D::from([5.0, -5.0]);
+
+        viewer.move_to_world_coords(new_center.clone());
+
+        assert_eq!(viewer.center, new_center);
+    }
+
+    #[test]
+    fn viewer_moves_by_amount_specified_divided_by_zoom() {
+        const ZOOM_AMOUNT: f32 = 5.0;
+        let delta_position = Vector2D::from([5.0, -5.0]);
+
+        let mut viewer = new_viewer();
+        viewer.zoom_to(ZOOM_AMOUNT);
+
+        viewer.move_by_world_coords(delta_position[0], delta_position[1]);
+        let center_after_move = viewer.center.clone();
+
+        assert_eq!(delta_position * (1.0 / ZOOM_AMOUNT), center_after_move);
+    }
+}
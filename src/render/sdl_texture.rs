@@ -0,0 +1,110 @@
+use std::{ffi::c_int, ptr};
+
+use sdl2::{
+    pixels::PixelFormatEnum,
+    rect::Rect,
+    render::{TextureAccess, WindowCanvas},
+    sys::{self, SDL_BlendMode, SDL_Texture},
+};
+
+/// An RGBA texture kept next to the canvas it belongs to.
+///
+/// The textures of the sdl2 crate borrow the `TextureCreator` they come from, so they cannot be
+/// stored in the same struct as their canvas. These must be dropped before their canvas, since SDL
+/// frees the textures of a renderer along with it.
+pub struct CanvasTexture {
+    raw: *mut SDL_Texture,
+    width: u32,
+    height: u32,
+}
+
+impl CanvasTexture {
+    /// A texture that replaces what it is drawn over, alpha included, until `set_blending` is
+    /// called.
+    pub fn new(
+        canvas: &WindowCanvas,
+        access: TextureAccess,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, String> {
+        let raw = unsafe {
+            sys::SDL_CreateTexture(
+                canvas.raw(),
+                PixelFormatEnum::RGBA32 as u32,
+                access as c_int,
+                width as c_int,
+                height as c_int,
+            )
+        };
+        if raw.is_null() {
+            return Err(sdl2::get_error());
+        }
+
+        Ok(Self { raw, width, height })
+    }
+
+    pub fn raw(&self) -> *mut SDL_Texture {
+        self.raw
+    }
+
+    /// Makes the texture blend with what it is drawn over, or replace it.
+    pub fn set_blending(&mut self, blending: bool) -> Result<(), String> {
+        let mode = if blending {
+            SDL_BlendMode::SDL_BLENDMODE_BLEND
+        } else {
+            SDL_BlendMode::SDL_BLENDMODE_NONE
+        };
+        check(unsafe { sys::SDL_SetTextureBlendMode(self.raw, mode) })
+    }
+
+    /// Replaces every pixel with `rgba`, four bytes per pixel and row after row.
+    pub fn update(&mut self, rgba: &[u8]) -> Result<(), String> {
+        assert_eq!(rgba.len(), self.width as usize * self.height as usize * 4);
+        check(unsafe {
+            sys::SDL_UpdateTexture(
+                self.raw,
+                ptr::null(),
+                rgba.as_ptr().cast(),
+                self.width as c_int * 4,
+            )
+        })
+    }
+
+    /// Multiplies the alpha of every pixel by `alpha` when the texture is drawn.
+    pub fn set_alpha_mod(&mut self, alpha: u8) -> Result<(), String> {
+        check(unsafe { sys::SDL_SetTextureAlphaMod(self.raw, alpha) })
+    }
+
+    /// Draws the part of the texture in `rect` at the same place on the canvas, or all of it
+    /// stretched over the canvas.
+    pub fn copy(&self, canvas: &mut WindowCanvas, rect: Option<Rect>) -> Result<(), String> {
+        let rect = rect.as_ref().map_or(ptr::null(), |rect| rect.raw());
+        check(unsafe { sys::SDL_RenderCopy(canvas.raw(), self.raw, rect, rect) })
+    }
+
+    /// Makes the canvas draw into the texture instead of its window until `draw` returns. The
+    /// texture must have been made with `TextureAccess::Target`.
+    pub fn draw_into(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        draw: impl FnOnce(&mut WindowCanvas) -> Result<(), String>,
+    ) -> Result<(), String> {
+        check(unsafe { sys::SDL_SetRenderTarget(canvas.raw(), self.raw) })?;
+        let result = draw(canvas);
+        check(unsafe { sys::SDL_SetRenderTarget(canvas.raw(), ptr::null_mut()) })?;
+        result
+    }
+}
+
+impl Drop for CanvasTexture {
+    fn drop(&mut self) {
+        unsafe { sys::SDL_DestroyTexture(self.raw) };
+    }
+}
+
+fn check(result: c_int) -> Result<(), String> {
+    if result != 0 {
+        return Err(sdl2::get_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,686 @@
+use std::io::Write;
+
+use png::{BitDepth, ColorType, Encoder};
+
+use crate::{
+    color::Color,
+    matrix::Matrix3x3,
+    objects::{
+        svg::{
+            Ellipse, FillRule, Image, Line, Point, Polygon, Polyline, Rect, Style, Transform,
+            VisitContext, Visitor,
+        },
+        ObjectMgr,
+    },
+    render::{
+        pixel_viewer::PixelViewer, stroke::stroke_outline, tessellation::CurveCache, Renderer,
+        Viewer,
+    },
+    texture::Texture,
+    vector::Vector2D,
+};
+
+// Every row of pixels is sampled along this many lines. Coverage along each line is exact so
+// edges are anti-aliased horizontally and to 1/SUBSCANLINES vertically.
+const SUBSCANLINES: usize = 16;
+
+/// Draws into an RGBA buffer in memory, without a GPU or a display.
+///
+/// The viewer works like the canvas renderer's: a zoom of 1 draws one world unit per pixel.
+pub struct SoftwareRenderer {
+    viewer: PixelViewer,
+    background: Color,
+    // Colors premultiplied by their alpha, from 0 to 1, row by row from the top left.
+    pixels: Vec<[f32; 4]>,
+    curves: CurveCache,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut renderer = Self {
+            viewer: PixelViewer::new(width, height),
+            background: Color::WHITE,
+            pixels: Vec::new(),
+            curves: CurveCache::default(),
+        };
+        renderer.resize_window(width, height);
+        renderer
+    }
+
+    /// Sets the color `clear` fills the buffer with. White by default.
+    pub fn set_background(&mut self, background: Color) {
+        self.background = background;
+    }
+
+    /// The color of the pixel at column `x` and row `y`, counting from the top left.
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let index = y as usize * self.viewer.width() as usize + x as usize;
+        unpremultiply(self.pixels[index])
+    }
+
+    /// The whole buffer as 8-bit RGBA with straight alpha, row by row from the top left.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|pixel| {
+                let color = unpremultiply(*pixel);
+                [color.r, color.g, color.b, color.a]
+            })
+            .collect()
+    }
+
+    /// Encodes the buffer as an 8-bit RGBA PNG.
+    pub fn write_png<W: Write>(&self, w: W) -> Result<(), png::EncodingError> {
+        let mut encoder = Encoder::new(w, self.viewer.width(), self.viewer.height());
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_rgba8())?;
+        writer.finish()
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn get_viewer(&mut self) -> &mut dyn Viewer {
+        &mut self.viewer
+    }
+
+    fn height(&self) -> u32 {
+        self.viewer.height()
+    }
+
+    fn width(&self) -> u32 {
+        self.viewer.width()
+    }
+
+    // A buffer has no window to keep usable so any size is allowed.
+    fn resize_window(&mut self, new_width: u32, new_height: u32) {
+        self.viewer.resize(new_width, new_height);
+        self.pixels = vec![premultiply(self.background); new_width as usize * new_height as usize];
+    }
+
+    fn clear(&mut self) {
+        self.pixels.fill(premultiply(self.background));
+    }
+
+    // The documents are drawn from scratch every time, only the outlines of curves are kept.
+    fn update_objects(&mut self, object_mgr: &mut ObjectMgr) {
+        self.curves.update(object_mgr);
+    }
+
+    fn render_objects(&mut self, object_mgr: &ObjectMgr) {
+        let world_to_pixel = self
+            .viewer
+            .get_norm_to_viewer()
+            .clone()
+            .transpose_symmetric();
+
+        for (index, object) in object_mgr.get_objects().iter().enumerate() {
+            let mut position = Matrix3x3::IDENTITY3X3;
+            position[0][2] = object.position[0];
+            position[1][2] = object.position[1];
+
+            let mut painter = Painter {
+                canvas: Canvas {
+                    width: self.viewer.width() as usize,
+                    height: self.viewer.height() as usize,
+                    pixels: &mut self.pixels,
+                },
+                object_to_pixel: &world_to_pixel * &position,
+                curves: &mut self.curves,
+                object: index,
+            };
+            object.svg_inst.walk(&mut painter);
+        }
+    }
+
+    // The buffer is the output so there is nothing to present.
+    fn present(&mut self) {}
+}
+
+type DevicePoint = [f32; 2];
+
+fn premultiply(color: Color) -> [f32; 4] {
+    let alpha = color.a as f32 / 255.0;
+    [
+        color.r as f32 / 255.0 * alpha,
+        color.g as f32 / 255.0 * alpha,
+        color.b as f32 / 255.0 * alpha,
+        alpha,
+    ]
+}
+
+fn unpremultiply(pixel: [f32; 4]) -> Color {
+    let alpha = pixel[3];
+    if alpha <= 0.0 {
+        return Color::TRANSPARENT;
+    }
+
+    let channel = |value: f32| (value / alpha * 255.0).round().clamp(0.0, 255.0) as u8;
+    Color::RGBA(
+        channel(pixel[0]),
+        channel(pixel[1]),
+        channel(pixel[2]),
+        (alpha * 255.0).round().clamp(0.0, 255.0) as u8,
+    )
+}
+
+// Maps a point with a transform in the column form used by styles.
+fn apply(transform: &Transform, point: DevicePoint) -> DevicePoint {
+    [
+        transform[0][0] * point[0] + transform[0][1] * point[1] + transform[0][2],
+        transform[1][0] * point[0] + transform[1][1] * point[1] + transform[1][2],
+    ]
+}
+
+/// How much of each pixel in an area a path covers, from 0 to 1.
+struct Coverage {
+    left: usize,
+    top: usize,
+    width: usize,
+    values: Vec<f32>,
+}
+
+impl Coverage {
+    // Returns None when the path covers nothing inside the canvas.
+    fn rasterize(
+        subpaths: &[Vec<DevicePoint>],
+        fill_rule: FillRule,
+        canvas_width: usize,
+        canvas_height: usize,
+    ) -> Option<Self> {
+        // Edges go downwards. The direction they had in the path is kept for the winding number.
+        let mut edges: Vec<(DevicePoint, DevicePoint, i32)> = Vec::new();
+        for subpath in subpaths {
+            for (index, from) in subpath.iter().enumerate() {
+                let to = subpath[(index + 1) % subpath.len()];
+                if !from.iter().chain(to.iter()).all(|value| value.is_finite()) {
+                    return None;
+                }
+
+                if from[1] < to[1] {
+                    edges.push((*from, to, 1));
+                } else if from[1] > to[1] {
+                    edges.push((to, *from, -1));
+                }
+            }
+        }
+
+        let (mut min_x, mut max_x) = (f32::INFINITY, f32::NEG_INFINITY);
+        let (mut min_y, mut max_y) = (f32::INFINITY, f32::NEG_INFINITY);
+        for (top, bottom, _) in edges.iter() {
+            min_x = min_x.min(top[0]).min(bottom[0]);
+            max_x = max_x.max(top[0]).max(bottom[0]);
+            min_y = min_y.min(top[1]);
+            max_y = max_y.max(bottom[1]);
+        }
+
+        let left = min_x.floor().max(0.0) as usize;
+        let right = (max_x.ceil().max(0.0) as usize).min(canvas_width);
+        let top = min_y.floor().max(0.0) as usize;
+        let bottom = (max_y.ceil().max(0.0) as usize).min(canvas_height);
+        if left >= right || top >= bottom {
+            return None;
+        }
+
+        let width = right - left;
+        let mut values = Vec::with_capacity(width * (bottom - top));
+
+        edges.sort_by(|a, b| a.0[1].total_cmp(&b.0[1]));
+        let mut next_edge = 0;
+        let mut active: Vec<(DevicePoint, DevicePoint, i32)> = Vec::new();
+        let mut crossings: Vec<(f32, i32)> = Vec::new();
+
+        // Partly covered pixels add to `partial`. Fully covered runs add to `full` as the
+        // difference from the pixel before them, so a run costs the same however long it is.
+        let mut partial = vec![0.0; width + 1];
+        let mut full = vec![0.0; width + 1];
+        const WEIGHT: f32 = 1.0 / SUBSCANLINES as f32;
+
+        for row in top..bottom {
+            for line in 0..SUBSCANLINES {
+                let y = row as f32 + (line as f32 + 0.5) * WEIGHT;
+
+                while next_edge < edges.len() && edges[next_edge].0[1] <= y {
+                    active.push(edges[next_edge]);
+                    next_edge += 1;
+                }
+                active.retain(|(_, bottom, _)| bottom[1] > y);
+
+                crossings.clear();
+                crossings.extend(active.iter().filter(|(top, _, _)| top[1] <= y).map(
+                    |(top, bottom, direction)| {
+                        let t = (y - top[1]) / (bottom[1] - top[1]);
+                        (top[0] + t * (bottom[0] - top[0]), *direction)
+                    },
+                ));
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    let inside = match fill_rule {
+                        FillRule::NonZero => winding != 0,
+                        FillRule::EvenOdd => winding % 2 != 0,
+                    };
+                    if !inside {
+                        continue;
+                    }
+
+                    let start = (pair[0].0 - left as f32).clamp(0.0, width as f32);
+                    let end = (pair[1].0 - left as f32).clamp(0.0, width as f32);
+                    let (first, last) = (start as usize, end as usize);
+                    if first == last {
+                        partial[first] += (end - start) * WEIGHT;
+                    } else {
+                        partial[first] += (first as f32 + 1.0 - start) * WEIGHT;
+                        full[first + 1] += WEIGHT;
+                        full[last] -= WEIGHT;
+                        partial[last] += (end - last as f32) * WEIGHT;
+                    }
+                }
+            }
+
+            let mut run = 0.0;
+            for x in 0..width {
+                run += full[x];
+                values.push((partial[x] + run).clamp(0.0, 1.0));
+            }
+            partial.fill(0.0);
+            full.fill(0.0);
+        }
+
+        Some(Self {
+            left,
+            top,
+            width,
+            values,
+        })
+    }
+}
+
+struct Canvas<'a> {
+    width: usize,
+    height: usize,
+    pixels: &'a mut [[f32; 4]],
+}
+
+impl Canvas<'_> {
+    // Blends `shade`, a premultiplied color for each pixel, over the pixels a path covers.
+    fn fill(
+        &mut self,
+        subpaths: &[Vec<DevicePoint>],
+        fill_rule: FillRule,
+        mut shade: impl FnMut(usize, usize) -> [f32; 4],
+    ) {
+        let Some(coverage) = Coverage::rasterize(subpaths, fill_rule, self.width, self.height)
+        else {
+            return;
+        };
+
+        for (index, amount) in coverage.values.iter().enumerate() {
+            if *amount <= 0.0 {
+                continue;
+            }
+
+            let x = coverage.left + index % coverage.width;
+            let y = coverage.top + index / coverage.width;
+            let source = shade(x, y).map(|channel| channel * amount);
+            let pixel = &mut self.pixels[y * self.width + x];
+            for channel in 0..4 {
+                pixel[channel] = source[channel] + pixel[channel] * (1.0 - source[3]);
+            }
+        }
+    }
+
+    fn fill_color(&mut self, subpaths: &[Vec<DevicePoint>], fill_rule: FillRule, color: Color) {
+        let color = premultiply(color);
+        self.fill(subpaths, fill_rule, |_, _| color);
+    }
+}
+
+/// Draws the shapes of one object as they are visited.
+struct Painter<'a> {
+    canvas: Canvas<'a>,
+    // Maps the world coordinates of the object to pixels.
+    object_to_pixel: Transform,
+    curves: &'a mut CurveCache,
+    // The index of the object in the `ObjectMgr`.
+    object: usize,
+}
+
+impl Painter<'_> {
+    fn local_to_pixel(&self, style: &Style) -> Transform {
+        &self.object_to_pixel * &style.transform
+    }
+
+    fn to_pixels(&self, style: &Style, points: &[DevicePoint]) -> Vec<DevicePoint> {
+        let transform = self.local_to_pixel(style);
+        points
+            .iter()
+            .map(|point| apply(&transform, *point))
+            .collect()
+    }
+
+    fn draw_shape(&mut self, style: &Style, points: &[Vector2D<f32>], closed: bool) {
+        let points: Vec<DevicePoint> = points.iter().map(|point| [point[0], point[1]]).collect();
+
+        if let Some(color) = style.fill_rgba() {
+            if points.len() > 2 {
+                let outline = self.to_pixels(style, &points);
+                self.canvas.fill_color(&[outline], style.fill_rule, color);
+            }
+        }
+
+        if let Some(color) = style.stroke_rgba() {
+            if style.stroke_width > 0.0 {
+                let outline: Vec<Vec<DevicePoint>> = stroke_outline(&points, closed, style)
+                    .iter()
+                    .map(|piece| self.to_pixels(style, piece))
+                    .collect();
+                self.canvas.fill_color(&outline, FillRule::NonZero, color);
+            }
+        }
+    }
+}
+
+impl Visitor for Painter<'_> {
+    fn visit_ellipse(&mut self, ellipse: &Ellipse, ctx: &VisitContext) {
+        let local_to_pixel = self.local_to_pixel(&ellipse.style);
+        let outline = self
+            .curves
+            .ellipse(self.object, ctx.path, ellipse, &local_to_pixel);
+        self.draw_shape(&ellipse.style, &outline, true);
+    }
+
+    fn visit_image(&mut self, image: &Image, _ctx: &VisitContext) {
+        let texture = &image.texture;
+        if image.width <= 0.0
+            || image.height <= 0.0
+            || texture.width() == 0
+            || texture.height() == 0
+        {
+            return;
+        }
+
+        let local_to_pixel = self.local_to_pixel(&image.style);
+        let Some(pixel_to_local) = local_to_pixel.inverse() else {
+            return;
+        };
+
+        let corners = [
+            [image.x, image.y],
+            [image.x + image.width, image.y],
+            [image.x + image.width, image.y + image.height],
+            [image.x, image.y + image.height],
+        ];
+        let outline = self.to_pixels(&image.style, &corners);
+
+        self.canvas.fill(&[outline], FillRule::NonZero, |x, y| {
+            let local = apply(&pixel_to_local, [x as f32 + 0.5, y as f32 + 0.5]);
+            sample(
+                texture,
+                (local[0] - image.x) / image.width * texture.width() as f32,
+                (local[1] - image.y) / image.height * texture.height() as f32,
+            )
+        });
+    }
+
+    fn visit_line(&mut self, line: &Line, _ctx: &VisitContext) {
+        let Some(color) = line.style.stroke_rgba() else {
+            return;
+        };
+
+        let points = [[line.from[0], line.from[1]], [line.to[0], line.to[1]]];
+        let outline: Vec<Vec<DevicePoint>> = stroke_outline(&points, false, &line.style)
+            .iter()
+            .map(|piece| self.to_pixels(&line.style, piece))
+            .collect();
+        self.canvas.fill_color(&outline, FillRule::NonZero, color);
+    }
+
+    // Points are drawn as a single pixel.
+    fn visit_point(&mut self, point: &Point, _ctx: &VisitContext) {
+        let Some(color) = point.style.fill_rgba() else {
+            return;
+        };
+
+        let [x, y] = apply(
+            &self.local_to_pixel(&point.style),
+            [point.position[0], point.position[1]],
+        );
+        let square = vec![
+            [x - 0.5, y - 0.5],
+            [x + 0.5, y - 0.5],
+            [x + 0.5, y + 0.5],
+            [x - 0.5, y + 0.5],
+        ];
+        self.canvas.fill_color(&[square], FillRule::NonZero, color);
+    }
+
+    fn visit_polygon(&mut self, polygon: &Polygon, _ctx: &VisitContext) {
+        self.draw_shape(&polygon.style, &polygon.points, true);
+    }
+
+    fn visit_polyline(&mut self, polyline: &Polyline, _ctx: &VisitContext) {
+        self.draw_shape(&polyline.style, &polyline.points, false);
+    }
+
+    fn visit_rect(&mut self, rect: &Rect, ctx: &VisitContext) {
+        // A rect without an area is not drawn at all, not even its stroke.
+        if rect.width <= 0.0 || rect.height <= 0.0 {
+            return;
+        }
+
+        let local_to_pixel = self.local_to_pixel(&rect.style);
+        let outline = self
+            .curves
+            .rect(self.object, ctx.path, rect, &local_to_pixel);
+        self.draw_shape(&rect.style, &outline, true);
+    }
+}
+
+// Samples the texture between its pixels so scaled images are smooth. Returns a premultiplied
+// color.
+fn sample(texture: &Texture, x: f32, y: f32) -> [f32; 4] {
+    let max_x = texture.width() - 1;
+    let max_y = texture.height() - 1;
+    let x = (x - 0.5).clamp(0.0, max_x as f32);
+    let y = (y - 0.5).clamp(0.0, max_y as f32);
+
+    let (x0, y0) = (x as u32, y as u32);
+    let (x1, y1) = ((x0 + 1).min(max_x), (y0 + 1).min(max_y));
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+    let texel = |x, y| premultiply(texture.color_at(x, y));
+    let (top_left, top_right) = (texel(x0, y0), texel(x1, y0));
+    let (bottom_left, bottom_right) = (texel(x0, y1), texel(x1, y1));
+
+    let mut color = [0.0; 4];
+    for channel in 0..4 {
+        let top = top_left[channel] + (top_right[channel] - top_left[channel]) * tx;
+        let bottom = bottom_left[channel] + (bottom_right[channel] - bottom_left[channel]) * tx;
+        color[channel] = top + (bottom - top) * ty;
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Color,
+        objects::{
+            svg::{read_from_str, ParseOptions},
+            ObjectMgr,
+        },
+        render::Renderer,
+        vector::Vector2D,
+    };
+
+    use super::SoftwareRenderer;
+
+    // Draws the document with one world unit per pixel and the origin in the top left corner.
+    fn render(width: u32, height: u32, document: &str) -> SoftwareRenderer {
+        let mut object_mgr = ObjectMgr::new();
+        object_mgr.add_object(
+            read_from_str(document, &ParseOptions::STRICT)
+                .unwrap()
+                .into(),
+        );
+
+        let mut renderer = SoftwareRenderer::new(width, height);
+        renderer
+            .get_viewer()
+            .move_to_world_coords(Vector2D::from([width as f32 / 2.0, height as f32 / 2.0]));
+        renderer.clear();
+        renderer.update_objects(&mut object_mgr);
+        renderer.render_objects(&object_mgr);
+        renderer
+    }
+
+    #[test]
+    fn fills_cover_whole_pixels_exactly() {
+        let renderer = render(
+            4,
+            4,
+            r##"<svg><rect x="1" y="1" width="2" height="2" fill="#ff0000" stroke="none"/></svg>"##,
+        );
+
+        assert_eq!(renderer.pixel(0, 0), Color::WHITE);
+        assert_eq!(renderer.pixel(1, 1), Color::RGB(255, 0, 0));
+        assert_eq!(renderer.pixel(2, 2), Color::RGB(255, 0, 0));
+        assert_eq!(renderer.pixel(3, 2), Color::WHITE);
+    }
+
+    #[test]
+    fn paint_servers_are_drawn_with_their_fallback() {
+        let renderer = render(
+            2,
+            2,
+            r##"<svg><rect width="2" height="2" fill="url(#g) red" stroke="none"/></svg>"##,
+        );
+
+        assert_eq!(renderer.pixel(0, 0), Color::RGB(255, 0, 0));
+    }
+
+    #[test]
+    fn edges_are_anti_aliased() {
+        let renderer = render(
+            4,
+            4,
+            r##"<svg><rect x="0.5" y="0" width="2" height="4" fill="#000000" stroke="none"/></svg>"##,
+        );
+
+        assert_eq!(renderer.pixel(0, 1), Color::RGB(128, 128, 128));
+        assert_eq!(renderer.pixel(1, 1), Color::BLACK);
+        assert_eq!(renderer.pixel(2, 1), Color::RGB(128, 128, 128));
+    }
+
+    #[test]
+    fn fill_rule_decides_if_overlaps_are_inside() {
+        let document = |fill_rule| {
+            format!(
+                r#"<svg><polygon fill-rule="{}" stroke="none" points="0,0 6,0 6,6 0,6 0,0 1,1 5,1 5,5 1,5 1,1"/></svg>"#,
+                fill_rule
+            )
+        };
+
+        let nonzero = render(6, 6, &document("nonzero"));
+        assert_eq!(nonzero.pixel(3, 3), Color::BLACK);
+
+        let evenodd = render(6, 6, &document("evenodd"));
+        assert_eq!(evenodd.pixel(3, 3), Color::WHITE);
+        assert_eq!(evenodd.pixel(0, 3), Color::BLACK);
+    }
+
+    #[test]
+    fn transparent_colors_blend_with_what_is_below() {
+        let renderer = render(
+            2,
+            2,
+            r##"<svg><rect width="2" height="2" fill="#0000ff" fill-opacity="0.5" stroke="none"/></svg>"##,
+        );
+
+        assert_eq!(renderer.pixel(0, 0), Color::RGB(127, 127, 255));
+
+        let mut renderer = SoftwareRenderer::new(1, 1);
+        renderer.set_background(Color::TRANSPARENT);
+        renderer.clear();
+        assert_eq!(renderer.to_rgba8(), vec![0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn strokes_are_centered_on_the_outline_and_mitered() {
+        let renderer = render(
+            10,
+            10,
+            r##"<svg><polyline points="2,8 2,2 8,2" fill="none" stroke="#00ff00" stroke-width="2"/></svg>"##,
+        );
+
+        // Both sides of the line are covered but not past half the stroke width.
+        assert_eq!(renderer.pixel(1, 5), Color::RGB(0, 255, 0));
+        assert_eq!(renderer.pixel(2, 5), Color::RGB(0, 255, 0));
+        assert_eq!(renderer.pixel(3, 5), Color::WHITE);
+
+        // The miter fills the outer corner and the butt caps stop at the ends.
+        assert_eq!(renderer.pixel(1, 1), Color::RGB(0, 255, 0));
+        assert_eq!(renderer.pixel(8, 2), Color::WHITE);
+    }
+
+    #[test]
+    fn images_are_drawn_from_their_texture() {
+        // A 2x1 image with a red and a blue pixel.
+        let renderer = render(
+            4,
+            2,
+            r#"<svg><image width="4" height="2" href="data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAIAAAB7QOjdAAAADUlEQVR4nGP4zwAE/wEHAAH/4iOeWQAAAABJRU5ErkJggg=="/></svg>"#,
+        );
+
+        assert_eq!(renderer.pixel(0, 0), Color::RGB(255, 0, 0));
+        assert_eq!(renderer.pixel(3, 1), Color::RGB(0, 0, 255));
+    }
+
+    #[test]
+    fn buffer_is_written_as_png() {
+        let mut renderer = SoftwareRenderer::new(3, 2);
+        renderer.set_background(Color::RGBA(10, 20, 30, 40));
+        renderer.clear();
+
+        let mut encoded = Vec::new();
+        renderer.write_png(&mut encoded).unwrap();
+
+        let mut reader = png::Decoder::new(std::io::Cursor::new(encoded))
+            .read_info()
+            .unwrap();
+        let mut data = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut data).unwrap();
+
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(data, renderer.to_rgba8());
+        assert_eq!(&data[..4], &[10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn viewer_decides_what_is_drawn_where() {
+        let mut object_mgr = ObjectMgr::new();
+        object_mgr.add_object(
+            read_from_str(
+                r##"<svg><rect x="100" y="100" width="10" height="10" fill="#ff0000" stroke="none"/></svg>"##,
+                &ParseOptions::STRICT,
+            )
+            .unwrap()
+            .into(),
+        );
+
+        let mut renderer = SoftwareRenderer::new(20, 20);
+        renderer
+            .get_viewer()
+            .fit_to_content(&object_mgr.get_objects()[0]);
+        renderer.clear();
+        renderer.render_objects(&object_mgr);
+
+        assert_eq!(renderer.pixel(0, 0), Color::RGB(255, 0, 0));
+        assert_eq!(renderer.pixel(19, 19), Color::RGB(255, 0, 0));
+    }
+}
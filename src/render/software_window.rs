@@ -0,0 +1,105 @@
+use sdl2::{
+    render::{TextureAccess, WindowCanvas},
+    video::Window,
+};
+
+use crate::{
+    objects::ObjectMgr,
+    render::{sdl_texture::CanvasTexture, software::SoftwareRenderer, Renderer, Viewer},
+};
+
+/// Draws with the `SoftwareRenderer` and shows its buffer in a window.
+///
+/// It needs nothing from the GPU beyond copying a texture, so it works where OpenGL does not.
+pub struct SoftwareWindowRenderer {
+    // The frame shown in the window, as large as the window. It comes first so it is dropped
+    // before the canvas that made it. None if it could not be made again after a resize.
+    frame: Option<CanvasTexture>,
+    canvas: WindowCanvas,
+    renderer: SoftwareRenderer,
+}
+
+impl SoftwareWindowRenderer {
+    pub fn new(window: Window) -> Result<Self, String> {
+        let window_size = window.size();
+        // SDL's own software renderer, so that this still works when every GPU driver fails.
+        let canvas = window
+            .into_canvas()
+            .software()
+            .present_vsync()
+            .build()
+            .map_err(|err| err.to_string())?;
+        let frame = CanvasTexture::new(
+            &canvas,
+            TextureAccess::Streaming,
+            window_size.0,
+            window_size.1,
+        )?;
+
+        Ok(Self {
+            frame: Some(frame),
+            canvas,
+            renderer: SoftwareRenderer::new(window_size.0, window_size.1),
+        })
+    }
+}
+
+impl Renderer for SoftwareWindowRenderer {
+    fn get_viewer(&mut self) -> &mut dyn Viewer {
+        self.renderer.get_viewer()
+    }
+
+    fn height(&self) -> u32 {
+        self.renderer.height()
+    }
+
+    fn width(&self) -> u32 {
+        self.renderer.width()
+    }
+
+    fn resize_window(&mut self, mut new_width: u32, mut new_height: u32) {
+        super::bound_window_size(&mut new_width, &mut new_height);
+        self.renderer.resize_window(new_width, new_height);
+        self.canvas
+            .window_mut()
+            .set_size(new_width, new_height)
+            .unwrap();
+
+        self.frame = None;
+        match CanvasTexture::new(
+            &self.canvas,
+            TextureAccess::Streaming,
+            new_width,
+            new_height,
+        ) {
+            Ok(frame) => self.frame = Some(frame),
+            Err(err) => println!("Error while making the frame for the new size: {}", err),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.renderer.clear();
+    }
+
+    fn update_objects(&mut self, object_mgr: &mut ObjectMgr) {
+        self.renderer.update_objects(object_mgr);
+    }
+
+    fn render_objects(&mut self, object_mgr: &ObjectMgr) {
+        self.renderer.render_objects(object_mgr);
+    }
+
+    fn present(&mut self) {
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+
+        let shown = frame
+            .update(&self.renderer.to_rgba8())
+            .and_then(|()| frame.copy(&mut self.canvas, None));
+        if let Err(err) = shown {
+            println!("Error while showing the frame: {}", err);
+        }
+        self.canvas.present();
+    }
+}
@@ -0,0 +1,105 @@
+use crate::objects::svg::Style;
+
+/// The stroke of a shape as convex pieces that all wind the same way, so that filling them
+/// together with the nonzero rule draws their union. Ends are butt caps and corners are miter
+/// joins that fall back to bevels past the miter limit. Points are in the shape's own coordinates.
+pub fn stroke_outline(points: &[[f32; 2]], closed: bool, style: &Style) -> Vec<Vec<[f32; 2]>> {
+    let half_width = style.stroke_width * 0.5;
+
+    let mut points: Vec<[f32; 2]> = points.to_vec();
+    points.dedup();
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+
+    let mut pieces = Vec::new();
+    if points.len() < 2 || half_width <= 0.0 {
+        return pieces;
+    }
+
+    let num_segments = if closed && points.len() > 2 {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+
+    // The offset to the left of each segment, as long as half the stroke.
+    let offsets: Vec<[f32; 2]> = (0..num_segments)
+        .map(|index| {
+            let from = points[index];
+            let to = points[(index + 1) % points.len()];
+            let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+            let scale = half_width / (dx * dx + dy * dy).sqrt();
+            [-dy * scale, dx * scale]
+        })
+        .collect();
+
+    for (index, offset) in offsets.iter().enumerate() {
+        let from = points[index];
+        let to = points[(index + 1) % points.len()];
+        pieces.push(vec![
+            [from[0] + offset[0], from[1] + offset[1]],
+            [to[0] + offset[0], to[1] + offset[1]],
+            [to[0] - offset[0], to[1] - offset[1]],
+            [from[0] - offset[0], from[1] - offset[1]],
+        ]);
+    }
+
+    let joins = if num_segments == points.len() {
+        0..num_segments
+    } else {
+        1..num_segments
+    };
+    for index in joins {
+        let before = offsets[(index + num_segments - 1) % num_segments];
+        let after = offsets[index];
+        let corner = points[index];
+
+        // The outer side of the corner is on the right of a left turn and the other way around.
+        let turn = before[0] * after[1] - before[1] * after[0];
+        if turn == 0.0 && before[0] * after[0] + before[1] * after[1] > 0.0 {
+            continue;
+        }
+        let side = if turn > 0.0 { -1.0 } else { 1.0 };
+
+        let mut join = vec![
+            corner,
+            [corner[0] + side * before[0], corner[1] + side * before[1]],
+        ];
+
+        let bisector = [before[0] + after[0], before[1] + after[1]];
+        let bisector_length2 = bisector[0] * bisector[0] + bisector[1] * bisector[1];
+        if bisector_length2 > 0.0 {
+            // The tip is as far past the corner as the half width over the cosine of half the
+            // angle between the offsets.
+            let scale = 2.0 * half_width * half_width / bisector_length2;
+            let miter_ratio = (scale * bisector_length2.sqrt()) / half_width;
+            if miter_ratio <= style.miter_limit {
+                join.push([
+                    corner[0] + side * bisector[0] * scale,
+                    corner[1] + side * bisector[1] * scale,
+                ]);
+            }
+        }
+
+        join.push([corner[0] + side * after[0], corner[1] + side * after[1]]);
+        pieces.push(join);
+    }
+
+    for piece in pieces.iter_mut() {
+        if signed_area(piece) < 0.0 {
+            piece.reverse();
+        }
+    }
+
+    pieces
+}
+
+fn signed_area(points: &[[f32; 2]]) -> f32 {
+    let mut area = 0.0;
+    for (index, from) in points.iter().enumerate() {
+        let to = points[(index + 1) % points.len()];
+        area += from[0] * to[1] - to[0] * from[1];
+    }
+    area * 0.5
+}
@@ -0,0 +1,262 @@
+use std::rc::Rc;
+
+use crate::{
+    objects::{
+        svg::{Change, Ellipse, Rect, Transform},
+        ObjectMgr,
+    },
+    render::element_cache::ElementCache,
+    vector::Vector2D,
+};
+
+// How far a flattened curve may stray from the real one, in pixels.
+const TOLERANCE: f32 = 0.1;
+
+// Curves are split into a power of two segments per turn, so zooming in or out only flattens them
+// again once it crosses into another level of detail.
+const MIN_SEGMENTS_PER_TURN: u32 = 8;
+const MAX_SEGMENTS_PER_TURN: u32 = 1 << 14;
+
+// Zooming back and forth tends to go through the same few levels of detail.
+const LEVELS_PER_ELEMENT: usize = 3;
+
+/// The flattened outlines of the ellipses and rounded rects of every document, at the levels of
+/// detail they were last drawn at.
+///
+/// Outlines are flattened finely enough to look smooth at the zoom they are drawn at, and no
+/// finer.
+#[derive(Default)]
+pub struct CurveCache {
+    outlines: ElementCache<Vec<Outline>>,
+}
+
+struct Outline {
+    segments_per_turn: u32,
+    points: Rc<[Vector2D<f32>]>,
+}
+
+impl CurveCache {
+    /// Forgets the outlines of the elements edited in `object_mgr` since the last update.
+    pub fn update(&mut self, object_mgr: &mut ObjectMgr) {
+        for (index, object) in object_mgr.get_objects_mut().iter_mut().enumerate() {
+            self.forget_changes(index, &object.svg_inst.take_changes());
+        }
+    }
+
+    /// Forgets the outlines of the elements that `changes` touched in object number `object`,
+    /// for renderers that also need the changes for something else.
+    pub fn forget_changes(&mut self, object: usize, changes: &[Change]) {
+        self.outlines.forget_changes(object, changes);
+    }
+
+    /// The outline of the ellipse at `path` in object number `object`, flattened for drawing
+    /// with `local_to_pixel`.
+    pub fn ellipse(
+        &mut self,
+        object: usize,
+        path: &[usize],
+        ellipse: &Ellipse,
+        local_to_pixel: &Transform,
+    ) -> Rc<[Vector2D<f32>]> {
+        let radius = ellipse.radius[0].max(ellipse.radius[1]) * max_scale(local_to_pixel);
+        self.outline(object, path, segments_per_turn(radius), |segments| {
+            ellipse.to_polygon(segments).points
+        })
+    }
+
+    /// The outline of the rect at `path` in object number `object`, flattened for drawing with
+    /// `local_to_pixel`.
+    pub fn rect(
+        &mut self,
+        object: usize,
+        path: &[usize],
+        rect: &Rect,
+        local_to_pixel: &Transform,
+    ) -> Rc<[Vector2D<f32>]> {
+        let (rx, ry) = rect.corner_radii();
+        let radius = rx.max(ry) * max_scale(local_to_pixel);
+        self.outline(object, path, segments_per_turn(radius), |segments| {
+            rect.to_polygon(segments).points
+        })
+    }
+
+    fn outline(
+        &mut self,
+        object: usize,
+        path: &[usize],
+        segments_per_turn: u32,
+        flatten: impl FnOnce(u32) -> Vec<Vector2D<f32>>,
+    ) -> Rc<[Vector2D<f32>]> {
+        let outlines = self.outlines.get_or_insert_with(object, path, Vec::new);
+
+        // The level of detail used last comes first, and the one used least recently is the
+        // first to go.
+        match outlines
+            .iter()
+            .position(|outline| outline.segments_per_turn == segments_per_turn)
+        {
+            Some(index) => outlines[..=index].rotate_right(1),
+            None => {
+                outlines.insert(
+                    0,
+                    Outline {
+                        segments_per_turn,
+                        points: flatten(segments_per_turn).into(),
+                    },
+                );
+                outlines.truncate(LEVELS_PER_ELEMENT);
+            }
+        }
+
+        outlines[0].points.clone()
+    }
+}
+
+// The number of segments a full turn of a curve `radius` pixels wide needs to stay within the
+// tolerance.
+fn segments_per_turn(radius: f32) -> u32 {
+    // A chord spanning `angle` of the curve strays radius * (1 - cos(angle / 2)) from it.
+    let angle = 2.0 * (1.0 - TOLERANCE / radius).max(-1.0).acos();
+    let segments = (core::f32::consts::TAU / angle).ceil();
+
+    (segments.min(MAX_SEGMENTS_PER_TURN as f32) as u32)
+        .next_power_of_two()
+        .clamp(MIN_SEGMENTS_PER_TURN, MAX_SEGMENTS_PER_TURN)
+}
+
+// How much the transform stretches lengths at most, which is the largest singular value of the
+// part of it that is not a translation.
+fn max_scale(transform: &Transform) -> f32 {
+    let (a, b) = (transform[0][0], transform[0][1]);
+    let (c, d) = (transform[1][0], transform[1][1]);
+
+    let sum = a * a + b * b + c * c + d * d;
+    let determinant = a * d - b * c;
+    ((sum
+        + (sum * sum - 4.0 * determinant * determinant)
+            .max(0.0)
+            .sqrt())
+        / 2.0)
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{
+        matrix::Matrix3x3,
+        objects::{
+            svg::{self, Element, EmptyTag, ParseOptions},
+            ObjectMgr,
+        },
+    };
+
+    use super::*;
+
+    fn scale(factor: f32) -> Transform {
+        let mut transform = Matrix3x3::IDENTITY3X3;
+        transform[0][0] = factor;
+        transform[1][1] = factor;
+        transform
+    }
+
+    fn document(contents: &str) -> ObjectMgr {
+        let mut object_mgr = ObjectMgr::new();
+        object_mgr.add_object(
+            svg::read_from_str(contents, &ParseOptions::STRICT)
+                .unwrap()
+                .into(),
+        );
+        object_mgr
+    }
+
+    fn ellipse_at(object_mgr: &ObjectMgr, index: usize) -> &Ellipse {
+        match &object_mgr.get_objects()[0].svg_inst.elements[index] {
+            Element::EmptyTag(EmptyTag::Ellipse(ellipse)) => ellipse,
+            _ => panic!("Expected an ellipse"),
+        }
+    }
+
+    #[test]
+    fn curves_stay_within_a_tenth_of_a_pixel() {
+        let mut last = 0;
+        for radius in [0.0, 0.1, 1.0, 10.0, 100.0, 1000.0, 100000.0] {
+            let segments = segments_per_turn(radius);
+            assert!(segments.is_power_of_two());
+            assert!(segments >= last);
+            last = segments;
+
+            let angle = core::f32::consts::TAU / segments as f32;
+            assert!(radius * (1.0 - (angle / 2.0).cos()) <= TOLERANCE);
+        }
+
+        assert_eq!(segments_per_turn(f32::INFINITY), MAX_SEGMENTS_PER_TURN);
+        assert_eq!(segments_per_turn(f32::NAN), MIN_SEGMENTS_PER_TURN);
+    }
+
+    #[test]
+    fn max_scale_is_the_longest_stretch() {
+        assert_eq!(max_scale(&scale(3.0)), 3.0);
+
+        // Stretching by 2 along x and rotating by 45 degrees.
+        let mut transform = Matrix3x3::IDENTITY3X3;
+        let half_sqrt_2 = core::f32::consts::FRAC_1_SQRT_2;
+        transform[0][0] = 2.0 * half_sqrt_2;
+        transform[0][1] = -half_sqrt_2;
+        transform[1][0] = 2.0 * half_sqrt_2;
+        transform[1][1] = half_sqrt_2;
+        assert!((max_scale(&transform) - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn zooming_reuses_recent_levels_of_detail() {
+        let object_mgr = document(r#"<svg><ellipse cx="0" cy="0" rx="10" ry="5"/></svg>"#);
+        let ellipse = ellipse_at(&object_mgr, 0);
+        let mut cache = CurveCache::default();
+
+        let far = cache.ellipse(0, &[0], ellipse, &scale(1.0));
+        let near = cache.ellipse(0, &[0], ellipse, &scale(100.0));
+        assert!(near.len() > far.len());
+        assert!(Rc::ptr_eq(
+            &far,
+            &cache.ellipse(0, &[0], ellipse, &scale(1.0))
+        ));
+
+        // Only the most recent levels are kept.
+        cache.ellipse(0, &[0], ellipse, &scale(10000.0));
+        cache.ellipse(0, &[0], ellipse, &scale(1000000.0));
+        assert!(!Rc::ptr_eq(
+            &near,
+            &cache.ellipse(0, &[0], ellipse, &scale(100.0))
+        ));
+    }
+
+    #[test]
+    fn edited_elements_are_flattened_again() {
+        let mut object_mgr = document(
+            r#"<svg><ellipse cx="0" cy="0" rx="10" ry="5"/><ellipse cx="0" cy="0" rx="4" ry="4"/></svg>"#,
+        );
+        let mut cache = CurveCache::default();
+        let first = cache.ellipse(0, &[0], ellipse_at(&object_mgr, 0), &scale(1.0));
+        let second = cache.ellipse(0, &[1], ellipse_at(&object_mgr, 1), &scale(1.0));
+
+        let svg_inst = &mut object_mgr.get_objects_mut()[0].svg_inst;
+        svg_inst
+            .modify(&[0], |element| {
+                if let Element::EmptyTag(EmptyTag::Ellipse(ellipse)) = element {
+                    ellipse.radius = [20.0, 5.0].into();
+                }
+            })
+            .unwrap();
+        cache.update(&mut object_mgr);
+
+        let edited = cache.ellipse(0, &[0], ellipse_at(&object_mgr, 0), &scale(1.0));
+        assert!(!Rc::ptr_eq(&first, &edited));
+        assert_eq!(edited[0][0], 20.0);
+        assert!(Rc::ptr_eq(
+            &second,
+            &cache.ellipse(0, &[1], ellipse_at(&object_mgr, 1), &scale(1.0))
+        ));
+    }
+}
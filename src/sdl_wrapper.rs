@@ -1,12 +1,64 @@
+use std::{fmt::Display, str::FromStr};
+
 use sdl2::{EventPump, Sdl, VideoSubsystem};
 
+#[cfg(feature = "gl")]
+use crate::render::gl::GLRenderer;
 use crate::{
     objects::ObjectMgr,
-    render::{canvas::CanvasRenderer, gl::GLRenderer, Renderer},
+    render::{canvas::CanvasRenderer, software_window::SoftwareWindowRenderer, Renderer},
 };
 
+#[cfg(feature = "gl")]
 const MULTISAMPLE_SAMPLES: u8 = 4;
 
+/// What draws into a window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    #[cfg(feature = "gl")]
+    GL,
+    Canvas,
+    Software,
+}
+
+impl Backend {
+    /// The backend to try next when this one cannot be set up, from the fastest to the one that
+    /// needs the least of the GPU.
+    pub fn fallback(self) -> Option<Self> {
+        match self {
+            #[cfg(feature = "gl")]
+            Self::GL => Some(Self::Canvas),
+            Self::Canvas => Some(Self::Software),
+            Self::Software => None,
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            #[cfg(feature = "gl")]
+            "gl" => Ok(Self::GL),
+            "canvas" => Ok(Self::Canvas),
+            "software" => Ok(Self::Software),
+            _ => Err(format!("Unknown backend {}.", s)),
+        }
+    }
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "gl")]
+            Self::GL => write!(f, "gl"),
+            Self::Canvas => write!(f, "canvas"),
+            Self::Software => write!(f, "software"),
+        }
+    }
+}
+
 pub struct SDLContext {
     pub sdl: Sdl,
     pub video_subsystem: VideoSubsystem,
@@ -24,13 +76,30 @@ impl SDLContext {
         })
     }
 
-    pub fn build_new_window<'a>(
+    /// Opens a window drawn by `backend`.
+    #[cfg_attr(not(feature = "gl"), allow(unused_variables))]
+    pub fn build_new_backend_window(
         &self,
+        backend: Backend,
         title: &str,
         width: u32,
         height: u32,
-        object_mgr: &'a ObjectMgr,
-    ) -> Result<Box<dyn Renderer + 'a>, String> {
+        object_mgr: &ObjectMgr,
+    ) -> Result<Box<dyn Renderer>, String> {
+        match backend {
+            #[cfg(feature = "gl")]
+            Backend::GL => self.build_new_gl_window(title, width, height, object_mgr),
+            Backend::Canvas => self.build_new_window(title, width, height),
+            Backend::Software => self.build_new_software_window(title, width, height),
+        }
+    }
+
+    pub fn build_new_window(
+        &self,
+        title: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<Box<dyn Renderer>, String> {
         let window = match self
             .video_subsystem
             .window(title, width, height)
@@ -41,12 +110,32 @@ impl SDLContext {
             Err(err) => return Err(format!("{err}")),
         };
 
-        match CanvasRenderer::<'a>::new(window, &object_mgr) {
+        match CanvasRenderer::new(window) {
             Ok(renderer) => Ok(Box::new(renderer)),
             Err(err) => Err(format!("{err}")),
         }
     }
 
+    pub fn build_new_software_window(
+        &self,
+        title: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<Box<dyn Renderer>, String> {
+        let window = match self
+            .video_subsystem
+            .window(title, width, height)
+            .resizable()
+            .build()
+        {
+            Ok(window) => window,
+            Err(err) => return Err(format!("{err}")),
+        };
+
+        Ok(Box::new(SoftwareWindowRenderer::new(window)?))
+    }
+
+    #[cfg(feature = "gl")]
     pub fn build_new_gl_window(
         &self,
         title: &str,
@@ -70,10 +159,11 @@ impl SDLContext {
             Err(err) => return Err(format!("{err}")),
         };
 
-        let gl_ctx = window.gl_create_context().unwrap();
+        let gl_ctx = window.gl_create_context()?;
         gl::load_with(|name| self.video_subsystem.gl_get_proc_address(name) as *const _);
 
-        self.video_subsystem.gl_set_swap_interval(sdl2::video::SwapInterval::Immediate)?;
+        self.video_subsystem
+            .gl_set_swap_interval(sdl2::video::SwapInterval::Immediate)?;
 
         let gl_attr = self.video_subsystem.gl_attr();
         debug_assert_eq!(gl_attr.context_profile(), sdl2::video::GLProfile::Core);
@@ -1,13 +1,18 @@
 use std::io::{BufReader, Cursor};
 
 use base64::Engine;
+#[cfg(feature = "gl")]
 use gl::types::GLenum;
-use png::{ColorType, Decoder};
+use png::{BitDepth, ColorType, Decoder, Encoder};
+
+use crate::color::Color;
 
 #[derive(Debug)]
 pub enum DecodeError {
     Base64DecodeError(base64::DecodeError),
     PngDecodingError(png::DecodingError),
+    DimensionLimitExceeded { width: u32, height: u32 },
+    ByteLimitExceeded,
 }
 
 impl From<base64::DecodeError> for DecodeError {
@@ -27,38 +32,116 @@ impl std::fmt::Display for DecodeError {
         match self {
             Self::Base64DecodeError(err) => write!(f, "{}", err),
             Self::PngDecodingError(err) => write!(f, "{}", err),
+            Self::DimensionLimitExceeded { width, height } => {
+                write!(f, "Image of {}x{} pixels is too large", width, height)
+            }
+            Self::ByteLimitExceeded => write!(f, "Image takes up too many bytes"),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+/// Bounds on the images `Texture::from_href_with_limits` agrees to decode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodeLimits {
+    /// Largest width and height in pixels.
+    pub max_dimension: u32,
+    /// Largest size in bytes, both encoded and decoded.
+    pub max_bytes: usize,
+}
+
+impl DecodeLimits {
+    pub const NONE: Self = Self {
+        max_dimension: u32::MAX,
+        max_bytes: usize::MAX,
+    };
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Texture {
     format: ColorType,
+    bit_depth: BitDepth,
+    palette: Option<Vec<u8>>,
     width: u32,
     height: u32,
     data: Vec<u8>,
 }
 
 impl Texture {
+    #[cfg(feature = "gl")]
     pub const GL_DATA_TYPE: GLenum = gl::UNSIGNED_BYTE;
 
     pub fn from_href(href: &str) -> Result<Self, DecodeError> {
+        Self::from_href_with_limits(href, &DecodeLimits::NONE)
+    }
+
+    /// Decodes the image only if it fits in `limits`. The size of the image is checked before
+    /// any of its pixels are decoded.
+    pub fn from_href_with_limits(href: &str, limits: &DecodeLimits) -> Result<Self, DecodeError> {
+        // Every 4 base64 characters hold 3 bytes.
+        if href.len() / 4 * 3 > limits.max_bytes {
+            return Err(DecodeError::ByteLimitExceeded);
+        }
+
         let decoded_image = Texture::decode_base64_encoded_image(href)?;
 
-        let decoder = Decoder::new(BufReader::new(Cursor::new(decoded_image)));
+        let decoder = Decoder::new_with_limits(
+            BufReader::new(Cursor::new(decoded_image)),
+            png::Limits {
+                bytes: limits.max_bytes,
+            },
+        );
         let mut reader = decoder.read_info()?;
 
-        let mut buf = vec![0; reader.output_buffer_size().unwrap()];
+        let (width, height) = reader.info().size();
+        if width > limits.max_dimension || height > limits.max_dimension {
+            return Err(DecodeError::DimensionLimitExceeded { width, height });
+        }
+
+        let buffer_size = reader
+            .output_buffer_size()
+            .filter(|size| *size <= limits.max_bytes)
+            .ok_or(DecodeError::ByteLimitExceeded)?;
+
+        let mut buf = vec![0; buffer_size];
         let info = reader.next_frame(&mut buf)?;
+        let palette = reader
+            .info()
+            .palette
+            .as_ref()
+            .map(|palette| palette.to_vec());
 
         Ok(Self {
             format: info.color_type,
+            bit_depth: info.bit_depth,
+            palette,
             width: info.width,
             height: info.height,
             data: buf,
         })
     }
 
+    /// Encodes the texture as a base64 PNG data URL that `Texture::from_href` can read back.
+    pub fn to_href(&self) -> Result<String, png::EncodingError> {
+        let mut encoded_image = Vec::new();
+
+        let mut encoder = Encoder::new(&mut encoded_image, self.width, self.height);
+        encoder.set_color(self.format);
+        encoder.set_depth(self.bit_depth);
+        if let Some(palette) = &self.palette {
+            encoder.set_palette(palette.clone());
+        }
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)?;
+        writer.finish()?;
+
+        Ok(format!(
+            "data:image/png;base64,{}",
+            base64::prelude::BASE64_STANDARD.encode(encoded_image)
+        ))
+    }
+
+    #[cfg(feature = "gl")]
     pub fn gl_internal_format(&self) -> GLenum {
         match self.format {
             ColorType::Grayscale => gl::R8,
@@ -69,6 +152,7 @@ impl Texture {
         }
     }
 
+    #[cfg(feature = "gl")]
     pub fn gl_input_format(&self) -> GLenum {
         match self.format {
             ColorType::Grayscale => gl::RED,
@@ -91,6 +175,69 @@ impl Texture {
         &self.data
     }
 
+    /// The color of the pixel at column `x` and row `y`, counting from the top left.
+    ///
+    /// Palette entries have no transparency since only the palette's colors are kept.
+    pub fn color_at(&self, x: u32, y: u32) -> Color {
+        let channels = match self.format {
+            ColorType::Grayscale | ColorType::Indexed => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        };
+        let bits = self.bit_depth as usize;
+        let row_bytes = (self.width as usize * channels * bits).div_ceil(8);
+
+        // Reads a channel scaled down to 8 bits, or the raw index for indexed images.
+        let sample = |channel: usize| -> u8 {
+            let bit = (x as usize * channels + channel) * bits;
+            let index = y as usize * row_bytes + bit / 8;
+            let byte = self.data.get(index).copied().unwrap_or(0);
+            match bits {
+                8 | 16 => byte,
+                _ => {
+                    let shift = 8 - bits - bit % 8;
+                    let value = (byte >> shift) & ((1 << bits) - 1);
+                    if self.format == ColorType::Indexed {
+                        value
+                    } else {
+                        (value as u32 * 255 / ((1 << bits) - 1)) as u8
+                    }
+                }
+            }
+        };
+
+        match self.format {
+            ColorType::Grayscale => Color::RGB(sample(0), sample(0), sample(0)),
+            ColorType::GrayscaleAlpha => Color::RGBA(sample(0), sample(0), sample(0), sample(1)),
+            ColorType::Rgb => Color::RGB(sample(0), sample(1), sample(2)),
+            ColorType::Rgba => Color::RGBA(sample(0), sample(1), sample(2), sample(3)),
+            ColorType::Indexed => {
+                let entry = sample(0) as usize * 3;
+                match self
+                    .palette
+                    .as_ref()
+                    .and_then(|palette| palette.get(entry..entry + 3))
+                {
+                    Some(rgb) => Color::RGB(rgb[0], rgb[1], rgb[2]),
+                    None => Color::TRANSPARENT,
+                }
+            }
+        }
+    }
+
+    /// Every pixel as 8-bit RGBA, row by row from the top left.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let color = self.color_at(x, y);
+                rgba.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+        rgba
+    }
+
     fn decode_base64_encoded_image(contents: &str) -> Result<Vec<u8>, base64::DecodeError> {
         let contents = if let Some(index) = contents.chars().position(|c| c == ',') {
             &contents[index + ','.len_utf8()..]
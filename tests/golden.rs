@@ -0,0 +1,235 @@
+//! Renders every document in `svg/` with the software renderer and compares it against the
+//! reference images in `tests/golden/`.
+//!
+//! When an image does not match, the rendered image and a diff are written to `golden/<corpus>/`
+//! in Cargo's directory for test files, `target/tmp/` unless the target directory moved. In the
+//! diff, pixels off by more than the tolerance are red and the rest is a faded copy of the
+//! reference.
+//!
+//! After a change that is meant to alter the output, regenerate the references with
+//! `UPDATE_GOLDEN=1 cargo test --test golden` and review them before committing.
+
+use std::{
+    env,
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use drawsvg::{
+    objects::{svg, ObjectMgr},
+    render::{software::SoftwareRenderer, Renderer},
+};
+
+// Documents are scaled to fit a square this many pixels wide.
+const IMAGE_SIZE: f32 = 256.0;
+
+// How far a channel may be from the reference before the pixel counts as different. Leaves room
+// for rounding differences between platforms.
+const CHANNEL_TOLERANCE: u8 = 8;
+
+struct Image {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl Image {
+    fn read(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut reader = png::Decoder::new(std::io::BufReader::new(file))
+            .read_info()
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+        let mut rgba = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader
+            .next_frame(&mut rgba)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            return Err(format!("{}: is not 8-bit RGBA", path.display()));
+        }
+
+        rgba.truncate(info.buffer_size());
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            rgba,
+        })
+    }
+
+    fn write(&self, path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(path).unwrap()),
+            self.width,
+            self.height,
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&self.rgba).unwrap();
+        writer.finish().unwrap();
+    }
+}
+
+fn render(svg_path: &Path) -> Result<Image, String> {
+    let svg_object =
+        svg::read_from_file(svg_path).map_err(|err| format!("{}: {}", svg_path.display(), err))?;
+
+    let scale = IMAGE_SIZE / svg_object.dimension[0].max(svg_object.dimension[1]);
+    let width = (svg_object.dimension[0] * scale).round().max(1.0) as u32;
+    let height = (svg_object.dimension[1] * scale).round().max(1.0) as u32;
+
+    let mut object_mgr = ObjectMgr::new();
+    object_mgr.add_object(svg_object.into());
+
+    let mut renderer = SoftwareRenderer::new(width, height);
+    renderer
+        .get_viewer()
+        .center_on_object(&object_mgr.get_objects()[0]);
+    renderer.clear();
+    renderer.update_objects(&mut object_mgr);
+    renderer.render_objects(&object_mgr);
+
+    Ok(Image {
+        width,
+        height,
+        rgba: renderer.to_rgba8(),
+    })
+}
+
+// Returns a diff image when any pixel is off by more than the tolerance.
+fn compare(actual: &Image, expected: &Image) -> Option<Image> {
+    let mut diff = Vec::with_capacity(expected.rgba.len());
+    let mut num_different = 0;
+
+    for (actual, expected) in actual.rgba.chunks(4).zip(expected.rgba.chunks(4)) {
+        let different = actual
+            .iter()
+            .zip(expected)
+            .any(|(a, e)| a.abs_diff(*e) > CHANNEL_TOLERANCE);
+
+        if different {
+            num_different += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let gray = (expected[0] as u32 + expected[1] as u32 + expected[2] as u32) / 3;
+            let faded = (255 - (255 - gray) * expected[3] as u32 / 255 / 4) as u8;
+            diff.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+
+    (num_different > 0).then_some(Image {
+        width: expected.width,
+        height: expected.height,
+        rgba: diff,
+    })
+}
+
+fn check_corpus(corpus: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+
+    // Images left from an earlier run would be mistaken for failures of this one.
+    let output_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("golden")
+        .join(corpus);
+    let _ = fs::remove_dir_all(&output_dir);
+
+    let mut svg_paths: Vec<PathBuf> = fs::read_dir(root.join("svg").join(corpus))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "svg"))
+        .collect();
+    svg_paths.sort();
+    assert!(!svg_paths.is_empty(), "svg/{} has no documents", corpus);
+
+    let mut failures = Vec::new();
+    for svg_path in svg_paths {
+        let name = svg_path.file_stem().unwrap().to_string_lossy().into_owned();
+        let golden_path = root
+            .join("tests/golden")
+            .join(corpus)
+            .join(format!("{}.png", name));
+
+        let actual = match render(&svg_path) {
+            Ok(actual) => actual,
+            Err(err) => {
+                failures.push(err);
+                continue;
+            }
+        };
+
+        if update {
+            actual.write(&golden_path);
+            continue;
+        }
+
+        let expected = match Image::read(&golden_path) {
+            Ok(expected) => expected,
+            Err(err) => {
+                actual.write(&output_dir.join(format!("{}.png", name)));
+                failures.push(format!("{} (run with UPDATE_GOLDEN=1 to create it)", err));
+                continue;
+            }
+        };
+
+        if (actual.width, actual.height) != (expected.width, expected.height) {
+            actual.write(&output_dir.join(format!("{}.png", name)));
+            failures.push(format!(
+                "{}: rendered at {}x{} but the reference is {}x{}",
+                svg_path.display(),
+                actual.width,
+                actual.height,
+                expected.width,
+                expected.height
+            ));
+            continue;
+        }
+
+        if let Some(diff) = compare(&actual, &expected) {
+            actual.write(&output_dir.join(format!("{}.png", name)));
+            diff.write(&output_dir.join(format!("{}_diff.png", name)));
+            failures.push(format!(
+                "{}: does not match {}, see {}",
+                svg_path.display(),
+                golden_path.display(),
+                output_dir.join(format!("{}_diff.png", name)).display()
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of svg/{} failed:\n{}",
+        failures.len(),
+        corpus,
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn alpha() {
+    check_corpus("alpha");
+}
+
+#[test]
+fn basic() {
+    check_corpus("basic");
+}
+
+#[test]
+fn hardcore() {
+    check_corpus("hardcore");
+}
+
+#[test]
+fn illustration() {
+    check_corpus("illustration");
+}
+
+#[test]
+fn subdiv() {
+    check_corpus("subdiv");
+}